@@ -131,14 +131,20 @@
 //! ```
 
 use std::{
-    collections::{HashMap, HashSet},
+    borrow::Cow,
+    collections::{BTreeMap, HashMap, HashSet, VecDeque, hash_map::Entry},
     env::{current_dir, current_exe},
     ffi::OsStr,
     fs::{self, File, create_dir, remove_dir, remove_dir_all, remove_file},
     hash::Hash,
     io::{self, Write},
     path::{Path, PathBuf},
-    time::{SystemTime, UNIX_EPOCH},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 use thiserror::Error;
 
@@ -149,6 +155,86 @@ const MILLION: u64 = 1_000_000;
 const BILLION: u64 = 1_000_000_000;
 const TRILLION: u64 = 1_000_000_000_000;
 const QUADRILLION: u64 = 1_000_000_000_000_000;
+const MAX_NAME_LENGTH: usize = 255;
+
+/// Extensions that `read_auto` always returns as **`AutoContents::Binary`** without sniffing.
+const BINARY_EXTENSIONS: &[&str] = &[
+    "bin", "dat", "db", "sqlite", "png", "jpg", "jpeg", "gif", "bmp", "ico", "webp", "pdf", "zip",
+    "gz", "tar", "7z", "rar", "mp3", "mp4", "wav", "ogg", "flac", "avi", "mov", "exe", "dll", "so",
+    "dylib", "wasm",
+];
+
+/// Value of `format` in documents written by `export_index_json`.
+const INDEX_DOCUMENT_FORMAT: &str = "file_database.index";
+
+/// Current `version` of the `export_index_json` document layout.
+const INDEX_DOCUMENT_VERSION: u32 = 1;
+
+/// File name of the per-directory manifest written when manifests are enabled.
+///
+/// Manifest files (and their temp files) are never added to the `index`.
+pub const MANIFEST_FILE_NAME: &str = ".fdb_manifest.json";
+
+/// File name of the index file written by `save_index` and read by `load_index`.
+///
+/// The index file is never added to the `index`.
+pub const INDEX_FILE_NAME: &str = ".fdb_index.json";
+
+/// Suffix appended to a file name for the staging file used by atomic writes.
+///
+/// Staging files are never added to the `index`. A leftover one means a write was interrupted.
+pub const TEMP_FILE_SUFFIX: &str = ".fdb-tmp";
+
+/// File name of the journal written in the database root while a journaled move runs.
+///
+/// The journal is never added to the `index`. A leftover one is replayed when the database is
+/// opened.
+pub const JOURNAL_FILE_NAME: &str = ".fdb_journal.json";
+
+/// Name of the directory in the database root that holds data parked by the undo history.
+///
+/// The directory is never added to the `index` and is removed when the manager is dropped.
+pub const UNDO_DIR_NAME: &str = ".fdb_undo";
+
+/// Name of the directory in the database root that holds items moved there by
+/// `delete_to_trash`.
+///
+/// The directory is never added to the `index`.
+pub const TRASH_DIR_NAME: &str = ".fdb_trash";
+
+/// Default minimum time between two usage watchdog checks.
+const DEFAULT_USAGE_WATCHDOG_INTERVAL: Duration = Duration::from_secs(1);
+
+/// File name of the restore map kept inside `TRASH_DIR_NAME`.
+const TRASH_MAP_FILE_NAME: &str = "trash.json";
+
+/// File name of the ignore list read from the database root by scans.
+///
+/// Each line that is not empty and does not start with `#` is an ignore pattern (see
+/// `ScanOptions::with_ignore`). The file itself is never added to the `index`.
+pub const IGNORE_FILE_NAME: &str = ".dbignore";
+
+/// File name of the directory listings saved by `scan_for_changes_incremental`.
+///
+/// The state file is never added to the `index`.
+pub const SCAN_STATE_FILE_NAME: &str = ".fdb_scan_state.json";
+
+/// Name of the directory in the database root that holds the lock files used by `lock_item`.
+///
+/// The directory is never added to the `index`.
+pub const LOCKS_DIR_NAME: &str = ".fdb_locks";
+
+/// Name of the directory in the database root that holds staging files when
+/// `StagingStrategy::Directory` is used.
+///
+/// The directory is never added to the `index`.
+pub const STAGING_DIR_NAME: &str = ".fdb_staging";
+
+/// File name of the lock file a manager holds in the database root while it is open.
+///
+/// The lock file is never added to the `index`. It is left in place when the manager is dropped;
+/// only the advisory lock on it is released.
+pub const LOCK_FILE_NAME: &str = ".fdb.lock";
 
 // -------- Enums --------
 #[derive(Debug, Error)]
@@ -202,6 +288,58 @@ pub enum DatabaseError {
     /// Returned when converting an absolute path into a database-relative path fails.
     #[error(transparent)]
     PathBufConversion(#[from] std::path::StripPrefixError),
+    /// Returned when a **`ScopedManager`** principal is not allowed to perform an operation.
+    #[error("Principal '{0}' is not allowed to {1:?} ID '{2}'")]
+    AccessDenied(String, AccessOperation, String),
+    /// Returned when a name passed to `ItemId::try_new` cannot be used as an item name.
+    #[error("Invalid item name '{0}': {1}")]
+    InvalidItemName(String, &'static str),
+    /// Returned when a **`Batch`** step fails validation or cannot be applied.
+    #[error("Batch step {0} failed: {1}")]
+    BatchStepFailed(usize, Box<DatabaseError>),
+    /// Returned when a transactional **`Batch`** step fails and undoing the earlier steps fails
+    /// too, so the tree may be left half rolled back; holds the step, its error, and every
+    /// rollback error.
+    #[error("Batch step {0} failed: {1}; rollback errors: {count}", count = .2.len())]
+    BatchRollbackFailed(usize, Box<DatabaseError>, Vec<DatabaseError>),
+    /// Returned when `fulfill` or `release` targets an item that is not reserved.
+    #[error("ID '{0}' is not reserved")]
+    NotReserved(String),
+    /// Returned when two export destinations are the same path or one is inside the other.
+    #[error("Export destination '{0}' collides with another destination")]
+    ExportDestinationCollision(PathBuf),
+    /// Returned in strict mode when an `index` entry no longer matches the filesystem.
+    #[error("Index entry '{0}' is stale: '{1}'")]
+    StaleIndexEntry(String, PathBuf),
+    /// Returned when a long-running operation is stopped through its cancel flag.
+    #[error("Operation on ID '{0}' was cancelled")]
+    Cancelled(String),
+    /// Returned when text read from a file is not valid UTF-8; holds the path and the byte
+    /// offset of the first invalid byte.
+    #[error("File '{0}' is not valid UTF-8 (invalid byte at offset {1})")]
+    InvalidUtf8(PathBuf, usize),
+    /// Returned when the database root directory no longer exists; see `reattach`.
+    #[error("Database root '{0}' no longer exists")]
+    DatabaseRootMissing(PathBuf),
+    /// Returned when `import_index_json` gets a document it cannot use; holds the reason.
+    #[error("Invalid index document: {0}")]
+    InvalidIndexDocument(String),
+    /// Returned by `try_open` and `create_database` when another manager holds the lock on the
+    /// database root.
+    #[error("Database '{0}' is locked by another manager")]
+    DatabaseLocked(PathBuf),
+    /// Returned when a write would push a directory past its quota; holds the directory, the
+    /// quota, and the size in bytes it would have reached.
+    #[error("Quota of {1} bytes for '{0}' exceeded: the write needs {2} bytes")]
+    QuotaExceeded(PathBuf, u64, u64),
+    /// Returned when the filesystem holding a path has less free space than an operation needs;
+    /// holds the path, the bytes needed, and the bytes available.
+    #[error("Not enough free space at '{0}': {1} bytes needed, {2} available")]
+    InsufficientSpace(PathBuf, u64, u64),
+    /// Returned when a setting that changes how the `index` is keyed is changed while it still
+    /// tracks items; holds the number of tracked items.
+    #[error("Setting can only change while the index is empty ({0} items tracked)")]
+    IndexNotEmpty(usize),
 }
 
 #[derive(Debug, PartialEq, Clone, Default)]
@@ -260,6 +398,65 @@ impl From<bool> for ShouldSort {
     }
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+/// Controls how list results are ordered.
+///
+/// `true`/`ShouldSort::Sort` convert to `SortBy::Id`, and `false`/`ShouldSort::NoSort`
+/// convert to `SortBy::Unsorted`, so existing call sites keep working.
+///
+/// The metadata orderings (`Size`, `Created`, `Modified`) read each listed item's metadata
+/// once. Items whose metadata or timestamp is unavailable come last in either direction. Ties
+/// are broken by relative path.
+pub enum SortBy {
+    /// Index order, with no sorting applied.
+    ///
+    /// The `index` is kept in an ordered map, so this order is still deterministic across runs
+    /// and platforms.
+    Unsorted,
+    /// **`ItemId`** ordering (`name`, then `index`).
+    #[default]
+    Id,
+    /// Depth-first relative path order, so items are grouped by directory.
+    Path,
+    /// Item name, including the extension.
+    Name(SortOrder),
+    /// Extension; items without one sort before every extension in ascending order.
+    Extension(SortOrder),
+    /// Size in bytes. Directories count as `0`; their contents are not summed.
+    Size(SortOrder),
+    /// Creation time, where the filesystem records it.
+    Created(SortOrder),
+    /// Last modified time.
+    Modified(SortOrder),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+/// Direction for the **`SortBy`** orderings that take one.
+pub enum SortOrder {
+    /// Smallest, oldest, or alphabetically first item first.
+    #[default]
+    Ascending,
+    /// Largest, newest, or alphabetically last item first.
+    Descending,
+}
+
+impl From<bool> for SortBy {
+    /// Converts a boolean into **`SortBy`**.
+    fn from(value: bool) -> Self {
+        match value {
+            true => SortBy::Id,
+            false => SortBy::Unsorted,
+        }
+    }
+}
+
+impl From<ShouldSort> for SortBy {
+    /// Converts **`ShouldSort`** into **`SortBy`**.
+    fn from(value: ShouldSort) -> Self {
+        bool::from(value).into()
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Default)]
 /// Controls whether export copies or moves the source.
 pub enum ExportMode {
@@ -268,6 +465,107 @@ pub enum ExportMode {
     Move,
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy, serde::Serialize, serde::Deserialize)]
+/// Whether an item is a file or a directory.
+pub enum ItemKind {
+    File,
+    Directory,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+/// Whether scans and listings include hidden items.
+///
+/// An item is hidden when its name starts with `.` or, on Windows, when it has the hidden
+/// attribute.
+pub enum HiddenPolicy {
+    /// Treat hidden items like any other item.
+    #[default]
+    Include,
+    /// Leave hidden items, and everything below a hidden directory, out.
+    Exclude,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+/// How `lock_item` locks an item.
+pub enum LockKind {
+    /// Any number of holders at once; waits for exclusive holders.
+    Shared,
+    /// A single holder; waits for every other holder.
+    Exclusive,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+/// Controls how `import_item` brings an external item under management.
+///
+/// The chosen mode is recorded for the item and returned by `get_import_mode`.
+pub enum ImportMode {
+    /// Copy the source into the database. The source is left untouched.
+    #[default]
+    Copy,
+    /// Move the source into the database, falling back to copy and remove across filesystems.
+    Move,
+    /// Hard link the source file into the database. Directories are recreated and their files
+    /// hard linked. Source and destination must be on the same filesystem.
+    Hardlink,
+    /// Create a symbolic link to the absolute source path. Deleting the item removes only the
+    /// link.
+    Symlink,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+/// Controls which files `evict_until_under` and `trash_until_under` remove first.
+pub enum EvictionPolicy {
+    /// Remove the file whose last read or write is oldest first. Falls back to the modified
+    /// time on filesystems that do not update access times.
+    #[default]
+    LeastRecentlyAccessed,
+    /// Remove the file whose last write is oldest first.
+    LeastRecentlyModified,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+/// Controls where atomic writes stage new file contents before renaming them into place.
+///
+/// Every staging file name ends in `TEMP_FILE_SUFFIX`, so `cleanup_temp_files` recognizes
+/// leftovers whichever strategy wrote them.
+pub enum StagingStrategy {
+    /// `<file name>.fdb-tmp` next to the target.
+    #[default]
+    Sibling,
+    /// `.<file name>.<process id>.fdb-tmp` next to the target, hidden on Unix-like systems and
+    /// distinct per process.
+    Hidden,
+    /// A uniquely named file in `STAGING_DIR_NAME` in the database root, keeping data
+    /// directories free of staging files. Targets outside the database root fall back to
+    /// `Sibling`, since the rename must stay on one filesystem.
+    Directory,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+/// Controls how far writes are flushed to disk before they return.
+pub enum Durability {
+    /// Never sync. Fastest, but a crash can lose recent writes or leave empty files behind.
+    None,
+    /// Sync file contents before renaming them into place.
+    #[default]
+    File,
+    /// Also sync the parent directory after an item is created, renamed, moved, deleted, or
+    /// replaced by a new file, so the directory entry change survives a crash. Scans, index
+    /// document imports, and tiering do not sync directories. Directory syncs only happen on
+    /// Unix-like systems.
+    FileAndDirectory,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+/// Controls what `create_database_with_report` does with leftover temp files.
+pub enum TempFilePolicy {
+    /// Leave temp files in place and list them in the **`OpenReport`**.
+    #[default]
+    Report,
+    /// Delete temp files with `cleanup_temp_files` and list them in the **`OpenReport`**.
+    Delete,
+}
+
 #[derive(Debug, PartialEq, Clone, Default)]
 /// Controls how `scan_for_changes` handles newly found files.
 pub enum ScanPolicy {
@@ -277,6 +575,81 @@ pub enum ScanPolicy {
     AddNew,
 }
 
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+/// Operations that an access control entry can allow for a principal.
+pub enum AccessOperation {
+    Read,
+    Write,
+    Create,
+    Delete,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+/// Serialization format used by `read_existing_as`, `overwrite_existing_as`, and `typed_file!`.
+pub enum FileFormat {
+    #[default]
+    Json,
+    JsonPretty,
+    Binary,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+/// File contents decoded by `read_auto` and written by `write_auto`.
+pub enum AutoContents {
+    /// Parsed JSON document.
+    Json(serde_json::Value),
+    /// UTF-8 text.
+    Text(String),
+    /// Raw bytes that are not JSON or UTF-8 text.
+    Binary(Vec<u8>),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+/// Long-running operation reported by a **`Progress`** event.
+pub enum ProgressOperation {
+    /// `migrate_database`.
+    Migrate,
+    /// `export_item` and `export_map`.
+    Export,
+    /// `import_item`.
+    Import,
+    /// `copy_to`.
+    Transfer,
+    /// `duplicate_item` and `duplicate_item_with`.
+    Duplicate,
+    /// `freeze_with_contents`.
+    Snapshot,
+    /// `scan_for_changes`.
+    Scan,
+    /// Backups made by `run_due_backups` and `run_maintenance`.
+    Backup,
+    /// `read_many`.
+    ReadMany,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord, Hash)]
+/// Operation measured by the latency histograms (see `set_latency_tracking`).
+pub enum LatencyOperation {
+    /// `read_existing` and the typed reads built on it.
+    Read,
+    /// `overwrite_existing` and the typed writes built on it.
+    Write,
+    /// `write_new`.
+    Create,
+    /// `delete`.
+    Delete,
+    /// `rename`.
+    Rename,
+    /// `migrate_item`.
+    Move,
+    /// `export_item`.
+    Export,
+    /// `copy_to`.
+    Transfer,
+    /// `scan_for_changes`.
+    Scan,
+}
+
 #[derive(Debug, Default, PartialEq, PartialOrd, Eq, Ord, Clone, Copy)]
 /// Units used by **`FileSize`**.
 pub enum FileSizeUnit {
@@ -429,7 +802,9 @@ impl GenPath {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+#[derive(
+    Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
 /// Identifier used to select a tracked item by `name` and `index`.
 ///
 /// Use this when:
@@ -469,6 +844,15 @@ where
     }
 }
 
+impl std::str::FromStr for ItemId {
+    type Err = DatabaseError;
+
+    /// Parses a validated **`ItemId`** with `index` `0`. See `ItemId::try_new`.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::try_new(name)
+    }
+}
+
 impl From<&ItemId> for ItemId {
     /// Clones an **`ItemId`** from a reference.
     fn from(value: &ItemId) -> Self {
@@ -544,6 +928,46 @@ impl ItemId {
         }
     }
 
+    /// Creates an **`ItemId`** with `index` `0`, rejecting names that cannot be a single item.
+    ///
+    /// A valid name is non-empty, at most 255 bytes long, is not `.` or `..`, and contains no
+    /// path separators or NUL characters. Names the database uses for its own files, such as
+    /// `INDEX_FILE_NAME`, `IGNORE_FILE_NAME`, or anything ending in `TEMP_FILE_SUFFIX`, are
+    /// rejected too. Use `ItemId::database_id()` for the root instead of an empty name.
+    ///
+    /// # Parameters
+    /// - `name`: shared `name` key to validate.
+    ///
+    /// # Errors
+    /// Returns `DatabaseError::InvalidItemName` when `name` breaks any of the rules above.
+    ///
+    /// # Examples
+    /// ```
+    /// use file_database::ItemId;
+    ///
+    /// assert!(ItemId::try_new("notes.txt").is_ok());
+    /// assert!(ItemId::try_new("").is_err());
+    /// assert!(ItemId::try_new("a/b.txt").is_err());
+    /// assert!(ItemId::try_new(".fdb_index.json").is_err());
+    /// assert!(ItemId::try_new(".dbignore").is_err());
+    /// assert!("notes.txt".parse::<ItemId>().is_ok());
+    /// ```
+    pub fn try_new(name: impl Into<String>) -> Result<Self, DatabaseError> {
+        Self::try_with_index(name, 0)
+    }
+
+    /// Creates an **`ItemId`** with an explicit `index` after validating `name`.
+    ///
+    /// Uses the same rules as `ItemId::try_new`.
+    ///
+    /// # Errors
+    /// Returns `DatabaseError::InvalidItemName` when `name` is not a valid item name.
+    pub fn try_with_index(name: impl Into<String>, index: usize) -> Result<Self, DatabaseError> {
+        let name = name.into();
+        validate_item_name(&name)?;
+        Ok(Self { name, index })
+    }
+
     /// Returns the shared `name` of this **`ItemId`**.
     pub fn get_name(&self) -> &str {
         &self.name
@@ -658,6 +1082,67 @@ impl FileSize {
     }
 }
 
+#[derive(Debug, PartialEq, Clone)]
+/// One item yielded by `DatabaseManager::walk`.
+pub struct WalkEntry {
+    id: ItemId,
+    path: PathBuf,
+    depth: usize,
+    kind: ItemKind,
+}
+
+impl WalkEntry {
+    /// Returns the item's **`ItemId`**.
+    pub fn get_id(&self) -> &ItemId {
+        &self.id
+    }
+
+    /// Returns the item's path relative to the database root.
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns how many levels below the walk's starting directory the item is. Direct
+    /// children are at depth `1`.
+    pub fn get_depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns whether the item is a file or a directory on disk.
+    pub fn get_kind(&self) -> ItemKind {
+        self.kind
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, serde::Serialize, serde::Deserialize)]
+/// One item in the nested structure returned by `DatabaseManager::tree`.
+///
+/// Serializes as `{"id": {"name": ..., "index": ...}, "kind": "File" | "Directory",
+/// "children": [...]}`, ready for rendering in a UI or dumping to JSON.
+pub struct TreeNode {
+    id: ItemId,
+    kind: ItemKind,
+    children: Vec<TreeNode>,
+}
+
+impl TreeNode {
+    /// Returns the item's **`ItemId`**. The root of a tree built from the database root is
+    /// `ItemId::database_id()`.
+    pub fn get_id(&self) -> &ItemId {
+        &self.id
+    }
+
+    /// Returns whether the item is a file or a directory.
+    pub fn get_kind(&self) -> ItemKind {
+        self.kind
+    }
+
+    /// Returns the tracked children, sorted by name. Files have none.
+    pub fn get_children(&self) -> &[TreeNode] {
+        &self.children
+    }
+}
+
 #[derive(Debug, Default, PartialEq, PartialOrd, Clone)]
 /// Metadata returned by `get_file_information`.
 pub struct FileInformation {
@@ -722,8 +1207,8 @@ impl FileInformation {
 #[derive(Debug, PartialEq, Clone)]
 /// A file or folder change found by `scan_for_changes`.
 pub enum ExternalChange {
-    Added { id: ItemId, path: PathBuf },
-    Removed { id: ItemId, path: PathBuf },
+    Added { id: ItemId, path: RelativePath },
+    Removed { id: ItemId, path: RelativePath },
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -764,1722 +1249,14993 @@ impl ScanReport {
     }
 }
 
-#[derive(Debug, PartialEq, Default)]
-struct StableVec<T> {
-    list: Vec<Option<T>>,
-    free: Vec<usize>,
+#[derive(Debug, PartialEq, Clone, Default)]
+/// Filtering options for `scan_for_changes_with`.
+///
+/// # Examples
+/// ```no_run
+/// use file_database::{HiddenPolicy, ScanOptions};
+///
+/// let _options = ScanOptions::new()
+///     .with_ignore("target")
+///     .with_ignore("*.swp")
+///     .with_hidden(HiddenPolicy::Exclude);
+/// ```
+pub struct ScanOptions {
+    ignore: Vec<String>,
+    hidden: HiddenPolicy,
 }
 
-impl<T> StableVec<T> {
-    fn push(&mut self, data: T) -> usize {
-        if let Some(index) = self.free.pop() {
-            self.list[index] = Some(data);
-            index
-        } else {
-            self.list.push(Some(data));
-            self.list.len() - 1
-        }
+impl ScanOptions {
+    /// Creates options with no ignore patterns that include hidden items.
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    fn remove(&mut self, index: usize) -> bool {
-        if index >= self.list.len() || self.list[index].is_none() {
-            return false;
-        }
+    /// Sets whether untracked hidden items are scanned.
+    ///
+    /// With **`HiddenPolicy::Exclude`**, untracked hidden items (and everything below a hidden
+    /// directory) are treated like ignored ones.
+    pub fn with_hidden(mut self, hidden: HiddenPolicy) -> Self {
+        self.hidden = hidden;
+        self
+    }
 
-        self.list[index] = None;
-        self.free.push(index);
-        true
+    /// Returns the hidden-item policy.
+    pub fn get_hidden(&self) -> HiddenPolicy {
+        self.hidden
     }
 
-    fn get(&self, index: usize) -> Option<&T> {
-        self.list.get(index).and_then(|value| value.as_ref())
+    /// Adds an ignore pattern, in **`Selector`** syntax, relative to the database root.
+    ///
+    /// Untracked items that match, or that are below a directory that matches, are never
+    /// reported, added, or deleted by the scan. Items already in the `index` are unaffected.
+    pub fn with_ignore(mut self, pattern: impl Into<String>) -> Self {
+        self.ignore.push(pattern.into());
+        self
     }
 
-    fn insert_at(&mut self, index: usize, data: T) -> bool {
-        if index < self.list.len() {
-            if self.list[index].is_some() {
-                return false;
-            }
-            self.list[index] = Some(data);
-            if let Some(free_index) = self.free.iter().position(|slot| *slot == index) {
-                self.free.swap_remove(free_index);
-            }
-            return true;
-        }
+    /// Returns the patterns added with `with_ignore`.
+    pub fn get_ignore_patterns(&self) -> &[String] {
+        &self.ignore
+    }
+}
 
-        while self.list.len() < index {
-            let free_slot = self.list.len();
-            self.list.push(None);
-            self.free.push(free_slot);
-        }
+#[derive(Debug, PartialEq, Clone)]
+/// Summary returned by `merge_text`.
+pub struct MergeReport {
+    output: ItemId,
+    conflict_count: usize,
+}
 
-        self.list.push(Some(data));
-        true
+impl MergeReport {
+    /// Returns the **`ItemId`** the merged text was written to.
+    pub fn get_output(&self) -> &ItemId {
+        &self.output
     }
 
-    fn iter(&self) -> impl Iterator<Item = (usize, &T)> {
-        self.list
-            .iter()
-            .enumerate()
-            .filter_map(|(index, value)| value.as_ref().map(|value| (index, value)))
+    /// Returns how many conflicting regions were written with conflict markers.
+    pub fn get_conflict_count(&self) -> usize {
+        self.conflict_count
     }
 
-    fn is_empty(&self) -> bool {
-        self.list.iter().all(Option::is_none)
+    /// Returns `true` when the merge could not be resolved cleanly.
+    pub fn has_conflicts(&self) -> bool {
+        self.conflict_count > 0
     }
 }
 
-#[derive(Debug, PartialEq)]
-/// Main type that manages a database directory and its index.
-pub struct DatabaseManager {
-    path: PathBuf,
-    items: HashMap<String, StableVec<PathBuf>>,
+#[derive(Debug, PartialEq, Clone)]
+/// Summary returned by `export_item`.
+pub struct ExportSummary {
+    destination: PathBuf,
+    bytes_copied: u64,
+    items_affected: usize,
 }
 
-impl DatabaseManager {
-    /// Creates or opens a database directory and returns a manager for it.
-    ///
-    /// # Parameters
-    /// - `path`: parent directory where the database folder should exist.
-    /// - `name`: database directory name appended to `path`.
-    ///
-    /// # Errors
-    /// Returns an error if:
-    /// - the target path exists but is not a directory,
-    /// - parent directories are missing when creating a new database directory,
-    /// - the process cannot create/read directories at the destination.
-    ///
-    /// # Examples
-    /// ```no_run
-    /// use file_database::{DatabaseError, DatabaseManager};
-    ///
-    /// fn main() -> Result<(), DatabaseError> {
-    ///     let _manager = DatabaseManager::create_database(".", "database")?;
-    ///     Ok(())
-    /// }
-    /// ```
-    pub fn create_database(path: impl AsRef<Path>, name: impl AsRef<Path>) -> Result<Self, DatabaseError> {
-        let mut path: PathBuf = path.as_ref().to_path_buf();
+impl ExportSummary {
+    /// Returns the absolute path the item was exported to.
+    pub fn get_destination(&self) -> &Path {
+        &self.destination
+    }
 
-        path.push(name);
+    /// Returns the total size in bytes of all exported files.
+    pub fn get_bytes_copied(&self) -> u64 {
+        self.bytes_copied
+    }
 
-        if !path.exists() {
-            create_dir(&path)?;
-        } else if !path.is_dir() {
-            return Err(DatabaseError::NotADirectory(path));
-        }
+    /// Returns how many files and directories were exported, including the item itself.
+    pub fn get_items_affected(&self) -> usize {
+        self.items_affected
+    }
+}
 
-        let mut manager = Self {
-            path,
-            items: HashMap::new(),
-        };
+#[derive(Debug, PartialEq, Eq, Clone)]
+/// Summary returned by `evict_until_under` and `trash_until_under`.
+pub struct EvictionReport {
+    evicted: Vec<ItemId>,
+    bytes_freed: u64,
+    size: u64,
+}
 
-        let discovered = manager.collect_paths_in_scope(&manager.path, true)?;
-        for relative_path in discovered {
-            let name = os_str_to_string(relative_path.file_name())?;
-            manager.insert_generated_path(name, relative_path);
-        }
+impl EvictionReport {
+    /// Returns the evicted files in eviction order.
+    pub fn get_evicted(&self) -> &[ItemId] {
+        &self.evicted
+    }
 
-        Ok(manager)
+    /// Returns the total size in bytes of the evicted files.
+    pub fn get_bytes_freed(&self) -> u64 {
+        self.bytes_freed
     }
 
-    /// Creates a new file or directory under `parent`.
-    ///
-    /// Name interpretation is extension-based:
-    /// - if `id.name` has an extension, a file is created,
-    /// - otherwise, a directory is created.
-    ///
-    /// # Parameters
-    /// - `id`: name key for the new item. Root **`ItemId`** is not allowed.
-    /// - `parent`: destination parent item. Use `ItemId::database_id()` for database root.
+    /// Returns the total size in bytes of the tracked files left after eviction.
+    pub fn get_size(&self) -> u64 {
+        self.size
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+/// Summary returned by `delete`.
+pub struct DeleteSummary {
+    kind: ItemKind,
+    descendants_removed: usize,
+    bytes_freed: u64,
+}
+
+impl DeleteSummary {
+    /// Returns whether the deleted item was a file or a directory.
+    pub fn get_kind(&self) -> ItemKind {
+        self.kind
+    }
+
+    /// Returns how many files and directories below the item were removed, not counting the
+    /// item itself.
+    pub fn get_descendants_removed(&self) -> usize {
+        self.descendants_removed
+    }
+
+    /// Returns the total size in bytes of all removed files.
+    ///
+    /// Removing a symbolic link created by `ImportMode::Symlink` frees `0` bytes. Deleting a
+    /// tiered file counts the data removed from secondary storage.
+    pub fn get_bytes_freed(&self) -> u64 {
+        self.bytes_freed
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+/// A path relative to the database root.
+///
+/// The database root itself is the empty path, so a **`RelativePath`** is never absolute.
+/// Join it onto `locate_absolute(ItemId::database_id())` to get a filesystem path.
+///
+/// # Examples
+/// ```no_run
+/// use file_database::{DatabaseError, DatabaseManager, ItemId};
+///
+/// fn main() -> Result<(), DatabaseError> {
+///     let mut manager = DatabaseManager::create_database(".", "database")?;
+///     manager.write_new(ItemId::id("folder"), ItemId::database_id())?;
+///
+///     let root = manager.locate_relative(ItemId::database_id())?;
+///     let folder = manager.locate_relative(ItemId::id("folder"))?;
+///     assert!(root.is_root());
+///     assert!(!folder.is_absolute());
+///     Ok(())
+/// }
+/// ```
+pub struct RelativePath(PathBuf);
+
+impl RelativePath {
+    /// Returns `true` when this path refers to the database root.
+    pub fn is_root(&self) -> bool {
+        self.0.as_os_str().is_empty()
+    }
+
+    /// Returns the path as a borrowed `Path`.
+    pub fn as_path(&self) -> &Path {
+        &self.0
+    }
+
+    /// Converts this value into an owned `PathBuf`.
+    pub fn into_path_buf(self) -> PathBuf {
+        self.0
+    }
+}
+
+impl std::ops::Deref for RelativePath {
+    type Target = Path;
+
+    /// Borrows the inner path.
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for RelativePath {
+    /// Borrows the inner path.
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl From<RelativePath> for PathBuf {
+    /// Converts a **`RelativePath`** into its inner `PathBuf`.
+    fn from(value: RelativePath) -> Self {
+        value.0
+    }
+}
+
+impl PartialEq<Path> for RelativePath {
+    /// Compares the inner path with a `Path`.
+    fn eq(&self, other: &Path) -> bool {
+        self.0 == other
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+/// Details passed to a usage watchdog callback when a threshold is crossed.
+pub struct UsageAlert {
+    threshold: f64,
+    used_bytes: u64,
+    limit_bytes: u64,
+}
+
+impl UsageAlert {
+    /// Returns the crossed threshold as a fraction of the limit (for example `0.8`).
+    pub fn get_threshold(&self) -> f64 {
+        self.threshold
+    }
+
+    /// Returns the database size in bytes when the threshold was crossed.
+    pub fn get_used_bytes(&self) -> u64 {
+        self.used_bytes
+    }
+
+    /// Returns the limit in bytes the thresholds are measured against.
+    ///
+    /// For a watchdog registered with `set_disk_usage_watchdog`, this is the database size plus
+    /// the free space left on its filesystem at the time of the check.
+    pub fn get_limit_bytes(&self) -> u64 {
+        self.limit_bytes
+    }
+}
+
+/// What a usage watchdog measures the database size against.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum UsageLimit {
+    /// A fixed number of bytes, from `set_usage_watchdog`.
+    Bytes(u64),
+    /// The database size plus the free space on its filesystem, from `set_disk_usage_watchdog`.
+    Disk,
+}
+
+/// Thresholds and callback registered with `set_usage_watchdog` or `set_disk_usage_watchdog`.
+struct UsageWatchdog {
+    limit: UsageLimit,
+    thresholds: Vec<f64>,
+    callback: Arc<dyn Fn(&UsageAlert) + Send + Sync>,
+    level: Mutex<usize>,
+    last_checked: Mutex<Option<Instant>>,
+}
+
+impl UsageWatchdog {
+    /// Creates a watchdog with sorted `thresholds` that has not fired or checked yet.
+    fn new<F>(limit: UsageLimit, thresholds: impl IntoIterator<Item = f64>, callback: F) -> Self
+    where
+        F: Fn(&UsageAlert) + Send + Sync + 'static,
+    {
+        let mut thresholds: Vec<f64> = thresholds.into_iter().collect();
+        thresholds.sort_by(f64::total_cmp);
+
+        Self {
+            limit,
+            thresholds,
+            callback: Arc::new(callback),
+            level: Mutex::new(0),
+            last_checked: Mutex::new(None),
+        }
+    }
+}
+
+impl std::fmt::Debug for UsageWatchdog {
+    /// Formats the watchdog without the callback.
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("UsageWatchdog")
+            .field("limit", &self.limit)
+            .field("thresholds", &self.thresholds)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for UsageWatchdog {
+    /// Compares limits, thresholds, and callback identity.
+    fn eq(&self, other: &Self) -> bool {
+        self.limit == other.limit
+            && self.thresholds == other.thresholds
+            && Arc::ptr_eq(&self.callback, &other.callback)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+/// A daily time range, in UTC, during which maintenance work may run.
+///
+/// `start` and `end` are offsets from UTC midnight and are taken modulo 24 hours. A window
+/// whose `start` is later than its `end` wraps past midnight, and a window whose `start` equals
+/// its `end` covers the whole day.
+///
+/// # Examples
+/// ```no_run
+/// use std::time::Duration;
+/// use file_database::MaintenanceWindow;
+///
+/// // 23:00 to 05:00 UTC.
+/// let _night = MaintenanceWindow::new(
+///     Duration::from_secs(23 * 3600),
+///     Duration::from_secs(5 * 3600),
+/// );
+/// ```
+pub struct MaintenanceWindow {
+    start: Duration,
+    end: Duration,
+}
+
+impl MaintenanceWindow {
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    /// Creates a window from `start` to `end`, both measured from UTC midnight.
+    pub fn new(start: Duration, end: Duration) -> Self {
+        Self {
+            start: Duration::from_nanos((start.as_nanos() % Self::DAY.as_nanos()) as u64),
+            end: Duration::from_nanos((end.as_nanos() % Self::DAY.as_nanos()) as u64),
+        }
+    }
+
+    /// Returns the start offset from UTC midnight.
+    pub fn get_start(&self) -> Duration {
+        self.start
+    }
+
+    /// Returns the end offset from UTC midnight.
+    pub fn get_end(&self) -> Duration {
+        self.end
+    }
+
+    /// Returns `true` when `time` falls inside the window.
+    pub fn contains(&self, time: SystemTime) -> bool {
+        let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let time_of_day =
+            Duration::from_nanos((since_epoch.as_nanos() % Self::DAY.as_nanos()) as u64);
+
+        match self.start.cmp(&self.end) {
+            std::cmp::Ordering::Equal => true,
+            std::cmp::Ordering::Less => time_of_day >= self.start && time_of_day < self.end,
+            std::cmp::Ordering::Greater => time_of_day >= self.start || time_of_day < self.end,
+        }
+    }
+}
+
+/// Windows and idle check registered with `set_maintenance_windows` and `set_idle_check`.
+#[derive(Default)]
+struct MaintenanceSchedule {
+    windows: Vec<MaintenanceWindow>,
+    idle_check: Option<Arc<dyn Fn() -> bool + Send + Sync>>,
+}
+
+impl std::fmt::Debug for MaintenanceSchedule {
+    /// Formats the schedule without the idle check.
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("MaintenanceSchedule")
+            .field("windows", &self.windows)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for MaintenanceSchedule {
+    /// Compares windows and idle check identity.
+    fn eq(&self, other: &Self) -> bool {
+        self.windows == other.windows
+            && match (&self.idle_check, &other.idle_check) {
+                (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+                (None, None) => true,
+                _ => false,
+            }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord, Hash)]
+/// How serious a **`HealthIssue`** is. Ordered from least to most serious.
+pub enum HealthSeverity {
+    /// Worth knowing, nothing is wrong with the data.
+    Info,
+    /// The database works, but something should be cleaned up or re-scanned.
+    Warning,
+    /// The `index` or file data is inconsistent and needs repair.
+    Error,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// Which `self_check` check reported a **`HealthIssue`**.
+pub enum HealthCheck {
+    /// An `index` entry points to a path that no longer exists.
+    MissingEntry,
+    /// An item with tracked children is no longer a directory.
+    KindMismatch,
+    /// A file or directory on disk is not in the `index`.
+    UntrackedItem,
+    /// A staging file from an interrupted atomic write was left behind.
+    OrphanedTempFile,
+    /// A manifest file exists but cannot be parsed.
+    UnreadableManifest,
+    /// A file's hash differs from its manifest although its size and modified time did not
+    /// change.
+    ChecksumMismatch,
+    /// A file changed since its manifest was written.
+    StaleManifest,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+/// One finding in a **`HealthReport`**.
+pub struct HealthIssue {
+    severity: HealthSeverity,
+    check: HealthCheck,
+    path: PathBuf,
+}
+
+impl HealthIssue {
+    /// Returns how serious the issue is.
+    pub fn get_severity(&self) -> HealthSeverity {
+        self.severity
+    }
+
+    /// Returns the check that found the issue.
+    pub fn get_check(&self) -> HealthCheck {
+        self.check
+    }
+
+    /// Returns the database-relative path the issue is about.
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+/// Result of `self_check`. Issues are sorted by severity, most serious first.
+pub struct HealthReport {
+    issues: Vec<HealthIssue>,
+}
+
+impl HealthReport {
+    /// Returns every issue found.
+    pub fn get_issues(&self) -> &[HealthIssue] {
+        &self.issues
+    }
+
+    /// Returns the most serious severity found, or `None` when there are no issues.
+    pub fn get_worst_severity(&self) -> Option<HealthSeverity> {
+        self.issues.iter().map(|issue| issue.severity).max()
+    }
+
+    /// Returns `true` when no issue is a warning or an error.
+    pub fn is_healthy(&self) -> bool {
+        self.get_worst_severity()
+            .is_none_or(|severity| severity == HealthSeverity::Info)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+/// Summary returned by `run_maintenance`.
+pub struct MaintenanceReport {
+    scan: ScanReport,
+    tiered: usize,
+    backup: Option<PathBuf>,
+}
+
+impl MaintenanceReport {
+    /// Returns the report of the scan over the whole database.
+    pub fn get_scan(&self) -> &ScanReport {
+        &self.scan
+    }
+
+    /// Returns how many files `apply_tiering` moved to secondary storage.
+    pub fn get_tiered(&self) -> usize {
+        self.tiered
+    }
+
+    /// Returns the path of the backup created, if one was due.
+    pub fn get_backup(&self) -> Option<&Path> {
+        self.backup.as_deref()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, serde::Serialize, serde::Deserialize)]
+/// One item listed in a **`DirectoryManifest`**.
+pub struct ManifestEntry {
+    name: String,
+    is_dir: bool,
+    size: u64,
+    hash: Option<String>,
+    modified: Option<u64>,
+}
+
+impl ManifestEntry {
+    /// Returns the item's file or directory name.
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns `true` when the entry is a directory.
+    pub fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+
+    /// Returns the file size in bytes, or `0` for directories.
+    pub fn get_size(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns the FNV-1a 64-bit content hash as 16 hex digits, or `None` for directories.
+    pub fn get_hash(&self) -> Option<&str> {
+        self.hash.as_deref()
+    }
+
+    /// Returns the last modified time in nanoseconds since the Unix epoch, when available.
+    pub fn get_modified(&self) -> Option<u64> {
+        self.modified
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, serde::Serialize, serde::Deserialize)]
+/// Contents of a per-directory manifest file (`MANIFEST_FILE_NAME`).
+///
+/// Lists the tracked direct children of one directory, sorted by name.
+pub struct DirectoryManifest {
+    entries: Vec<ManifestEntry>,
+}
+
+impl DirectoryManifest {
+    /// Returns every entry in the manifest.
+    pub fn get_entries(&self) -> &[ManifestEntry] {
+        &self.entries
+    }
+
+    /// Returns the entry with `name`, if listed.
+    pub fn get_entry(&self, name: impl AsRef<str>) -> Option<&ManifestEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.name == name.as_ref())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+/// Schedule used by `run_due_backups`.
+///
+/// Backups are full copies of the database directory written to `destination` as
+/// `<database name>-backup-<unix millis>`. Only the newest `keep_last` backups are kept.
+pub struct BackupPolicy {
+    interval: Duration,
+    destination: PathBuf,
+    keep_last: usize,
+}
+
+impl BackupPolicy {
+    /// Creates a backup policy.
+    ///
+    /// # Parameters
+    /// - `interval`: minimum time between backups.
+    /// - `destination`: external directory that receives the backups.
+    /// - `keep_last`: number of backups to keep. `0` is treated as `1`.
+    pub fn new(interval: Duration, destination: impl Into<PathBuf>, keep_last: usize) -> Self {
+        Self {
+            interval,
+            destination: destination.into(),
+            keep_last: keep_last.max(1),
+        }
+    }
+
+    /// Returns the minimum time between backups.
+    pub fn get_interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Returns the directory that receives the backups.
+    pub fn get_destination(&self) -> &Path {
+        &self.destination
+    }
+
+    /// Returns how many backups are kept.
+    pub fn get_keep_last(&self) -> usize {
+        self.keep_last
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+/// Rule used by `apply_tiering` to move files to a secondary storage location.
+///
+/// A file matches when it is at least `min_size` bytes, or when its extension is in
+/// `extensions` (compared case-insensitively). A rule without either matches nothing.
+pub struct TierRule {
+    location: PathBuf,
+    min_size: Option<u64>,
+    extensions: Vec<String>,
+}
+
+impl TierRule {
+    /// Creates a rule that moves matching files below `location`.
+    pub fn new(location: impl Into<PathBuf>) -> Self {
+        Self {
+            location: location.into(),
+            min_size: None,
+            extensions: Vec::new(),
+        }
+    }
+
+    /// Moves files of at least `min_size` bytes.
+    pub fn with_min_size(mut self, min_size: u64) -> Self {
+        self.min_size = Some(min_size);
+        self
+    }
+
+    /// Moves files with one of these extensions (without the leading dot).
+    pub fn with_extensions<S: AsRef<str>>(
+        mut self,
+        extensions: impl IntoIterator<Item = S>,
+    ) -> Self {
+        self.extensions = extensions
+            .into_iter()
+            .map(|extension| extension.as_ref().to_ascii_lowercase())
+            .collect();
+        self
+    }
+
+    /// Returns the secondary storage location.
+    pub fn get_location(&self) -> &Path {
+        &self.location
+    }
+
+    /// Returns the size threshold in bytes, if any.
+    pub fn get_min_size(&self) -> Option<u64> {
+        self.min_size
+    }
+
+    /// Returns the lowercase extensions that are moved.
+    pub fn get_extensions(&self) -> &[String] {
+        &self.extensions
+    }
+
+    /// Returns `true` when a file at `path` with `size` bytes should be moved.
+    fn matches(&self, path: &Path, size: u64) -> bool {
+        self.min_size.is_some_and(|min_size| size >= min_size)
+            || path.extension().is_some_and(|extension| {
+                let extension = extension.to_string_lossy().to_ascii_lowercase();
+                self.extensions.contains(&extension)
+            })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+/// Where the data of an item is stored, returned by `get_storage_tier`.
+pub enum StorageTier {
+    /// Inside the database directory.
+    Primary,
+    /// In the secondary location of the **`TierRule`**, linked from the database directory.
+    Secondary,
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+/// Summary returned by `create_database_with_report` and `open_with_report`.
+pub struct OpenReport {
+    orphaned_temp_files: Vec<PathBuf>,
+    removed_temp_files: Vec<PathBuf>,
+    recovered_moves: Vec<PathBuf>,
+    dropped_index_entries: Vec<PathBuf>,
+    purged_expired: Vec<ItemId>,
+    load_time: Duration,
+}
+
+impl OpenReport {
+    /// Returns absolute paths of leftover temp files that were left in place.
+    pub fn get_orphaned_temp_files(&self) -> &[PathBuf] {
+        &self.orphaned_temp_files
+    }
+
+    /// Returns absolute paths of leftover temp files that were deleted.
+    pub fn get_removed_temp_files(&self) -> &[PathBuf] {
+        &self.removed_temp_files
+    }
+
+    /// Returns absolute source paths of interrupted moves that were finished or rolled back
+    /// from the journal.
+    pub fn get_recovered_moves(&self) -> &[PathBuf] {
+        &self.recovered_moves
+    }
+
+    /// Returns relative paths of saved index entries that were dropped because nothing exists
+    /// at their path anymore.
+    pub fn get_dropped_index_entries(&self) -> &[PathBuf] {
+        &self.dropped_index_entries
+    }
+
+    /// Returns the expired items deleted because `set_purge_expired_on_open` was enabled. These
+    /// do not make the report unclean.
+    pub fn get_purged_expired(&self) -> &[ItemId] {
+        &self.purged_expired
+    }
+
+    /// Returns how long opening took, including recovery, scanning, and loading the index.
+    pub fn get_load_time(&self) -> Duration {
+        self.load_time
+    }
+
+    /// Returns `true` when nothing needed attention.
+    pub fn is_clean(&self) -> bool {
+        self.orphaned_temp_files.is_empty()
+            && self.removed_temp_files.is_empty()
+            && self.recovered_moves.is_empty()
+            && self.dropped_index_entries.is_empty()
+    }
+}
+
+/// Pacing and progress options for `overwrite_existing_from_reader_with`.
+///
+/// # Examples
+/// ```no_run
+/// use file_database::StreamOptions;
+///
+/// let _options = StreamOptions::new()
+///     .with_chunk_size(256 * 1024)
+///     .with_max_bytes_per_second(4 * 1024 * 1024)
+///     .with_progress(|written| println!("{written} bytes written"));
+/// ```
+pub struct StreamOptions<'a> {
+    chunk_size: usize,
+    max_bytes_per_second: Option<u64>,
+    progress: Option<Box<dyn FnMut(u64) + 'a>>,
+}
+
+impl<'a> StreamOptions<'a> {
+    /// Creates options with 64 KiB chunks, no throughput limit, and no progress callback.
+    pub fn new() -> Self {
+        Self {
+            chunk_size: 64 * 1024,
+            max_bytes_per_second: None,
+            progress: None,
+        }
+    }
+
+    /// Sets how many bytes are read and written per step. `0` is treated as `1`.
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Limits average throughput by sleeping between chunks.
+    pub fn with_max_bytes_per_second(mut self, max_bytes_per_second: u64) -> Self {
+        self.max_bytes_per_second = Some(max_bytes_per_second.max(1));
+        self
+    }
+
+    /// Sets a callback that receives the total bytes written after every chunk.
+    pub fn with_progress(mut self, progress: impl FnMut(u64) + 'a) -> Self {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+
+    /// Returns the chunk size in bytes.
+    pub fn get_chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Returns the throughput limit in bytes per second, if any.
+    pub fn get_max_bytes_per_second(&self) -> Option<u64> {
+        self.max_bytes_per_second
+    }
+}
+
+impl Default for StreamOptions<'_> {
+    /// Same as `StreamOptions::new`.
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for StreamOptions<'_> {
+    /// Formats the options without the progress callback.
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("StreamOptions")
+            .field("chunk_size", &self.chunk_size)
+            .field("max_bytes_per_second", &self.max_bytes_per_second)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Boxed predicate over a relative path, used by exclude filters.
+type PathFilter<'a> = Box<dyn Fn(&Path) -> bool + 'a>;
+
+/// Progress, cancellation, and filtering options for `duplicate_item_with`.
+///
+/// # Examples
+/// ```no_run
+/// use std::sync::atomic::AtomicBool;
+/// use file_database::DuplicateOptions;
+///
+/// let cancel = AtomicBool::new(false);
+/// let _options = DuplicateOptions::new()
+///     .with_progress(|items, bytes| println!("{items} items, {bytes} bytes copied"))
+///     .with_cancel_flag(&cancel)
+///     .with_exclude(|path| {
+///         path.ends_with(".git") || path.extension().is_some_and(|ext| ext == "tmp")
+///     });
+/// ```
+pub struct DuplicateOptions<'a> {
+    progress: Option<Box<dyn FnMut(usize, u64) + 'a>>,
+    cancel: Option<&'a AtomicBool>,
+    exclude: Option<PathFilter<'a>>,
+}
+
+impl<'a> DuplicateOptions<'a> {
+    /// Creates options with no progress callback, no cancel flag, and no exclude filter.
+    pub fn new() -> Self {
+        Self {
+            progress: None,
+            cancel: None,
+            exclude: None,
+        }
+    }
+
+    /// Sets a callback that receives the items and bytes copied so far after every item.
+    pub fn with_progress(mut self, progress: impl FnMut(usize, u64) + 'a) -> Self {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+
+    /// Sets a flag that stops the copy when it becomes `true`.
+    ///
+    /// A cancelled duplicate removes what it already copied and returns
+    /// `DatabaseError::Cancelled`.
+    pub fn with_cancel_flag(mut self, cancel: &'a AtomicBool) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Sets a filter that skips entries for which it returns `true`.
+    ///
+    /// The filter receives each path relative to the duplicated item. Skipping a directory
+    /// skips everything below it.
+    pub fn with_exclude(mut self, exclude: impl Fn(&Path) -> bool + 'a) -> Self {
+        self.exclude = Some(Box::new(exclude));
+        self
+    }
+
+    /// Returns `true` when the cancel flag is set.
+    fn is_cancelled(&self) -> bool {
+        self.cancel
+            .is_some_and(|cancel| cancel.load(Ordering::Acquire))
+    }
+
+    /// Returns `true` when `path` should be skipped.
+    fn is_excluded(&self, path: &Path) -> bool {
+        self.exclude.as_ref().is_some_and(|exclude| exclude(path))
+    }
+}
+
+impl Default for DuplicateOptions<'_> {
+    /// Same as `DuplicateOptions::new`.
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for DuplicateOptions<'_> {
+    /// Formats the options without the callbacks.
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("DuplicateOptions")
+            .field("cancel", &self.cancel)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+/// When an item was added to the `index`, returned by `get_insertion_record`.
+///
+/// Records live in memory. Items found when a database is opened are recorded in discovery
+/// order at open time. Renames and moves keep the original record.
+pub struct InsertionRecord {
+    sequence: u64,
+    inserted_at: SystemTime,
+}
+
+impl InsertionRecord {
+    /// Returns the insertion sequence number. Later insertions have larger numbers.
+    pub fn get_sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Returns the time the item was added to the `index`.
+    pub fn get_inserted_at(&self) -> SystemTime {
+        self.inserted_at
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+/// Opaque reference to one item that keeps resolving across `rename`, `migrate_item`, and any
+/// change of its **`ItemId`**.
+///
+/// Returned by `write_new` (in **`CreatedItem`**) and `get_handle`, and turned back into the current **`ItemId`** by
+/// `resolve_handle`. Handles live as long as the manager that issued them; `load_index`
+/// replaces them.
+pub struct ItemHandle(u64);
+
+impl std::fmt::Display for ItemHandle {
+    /// Formats the handle as `#<number>`.
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(formatter, "#{}", self.0)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+/// What `write_new` created.
+pub struct CreatedItem {
+    id: ItemId,
+    path: PathBuf,
+    handle: ItemHandle,
+}
+
+impl CreatedItem {
+    /// Returns the exact **`ItemId`** of the new item, including its `index`.
+    pub fn get_id(&self) -> &ItemId {
+        &self.id
+    }
+
+    /// Returns the absolute path of the new file or directory.
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the **`ItemHandle`** of the new item.
+    pub fn get_handle(&self) -> ItemHandle {
+        self.handle
+    }
+}
+
+/// One `index` entry as stored in `INDEX_FILE_NAME`.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct IndexFileEntry {
+    name: String,
+    index: usize,
+    path: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_at: Option<SystemTime>,
+}
+
+/// Contents of `INDEX_FILE_NAME`.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct IndexFile {
+    entries: Vec<IndexFileEntry>,
+    #[serde(default)]
+    subtree_configs: BTreeMap<PathBuf, SubtreeConfig>,
+    #[serde(default)]
+    purge_expired_on_open: bool,
+    #[serde(default)]
+    normalize_extensions: bool,
+}
+
+/// Per-path bookkeeping stored in **`DatabaseManager`**. Follows the item through renames and
+/// moves.
+#[derive(Debug, PartialEq, Clone)]
+struct ItemRecord {
+    insertion: InsertionRecord,
+    import_mode: Option<ImportMode>,
+    expires_at: Option<SystemTime>,
+}
+
+/// Mutation counter stored in **`DatabaseManager`**.
+///
+/// Uses an atomic so methods that mutate through `&self` (like `overwrite_existing`) can bump it.
+#[derive(Debug, Default)]
+struct Generation(AtomicU64);
+
+impl Generation {
+    /// Returns the current value.
+    fn get(&self) -> u64 {
+        self.0.load(Ordering::Acquire)
+    }
+
+    /// Increments the counter by one.
+    fn bump(&self) {
+        self.0.fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+impl PartialEq for Generation {
+    /// Compares the current values.
+    fn eq(&self, other: &Self) -> bool {
+        self.get() == other.get()
+    }
+}
+
+/// Measured sizes of quota directories, valid while the **`Generation`** stays the same.
+///
+/// Uses a mutex so `overwrite_existing` can update it through `&self`.
+#[derive(Debug, Default)]
+struct QuotaUsage {
+    sizes: Mutex<QuotaSizes>,
+}
+
+#[derive(Debug, Default)]
+struct QuotaSizes {
+    /// Generation the sizes were measured or last updated at.
+    generation: u64,
+    /// Size in bytes keyed by quota directory path relative to the root.
+    sizes: HashMap<PathBuf, u64>,
+}
+
+impl QuotaUsage {
+    /// Locks the sizes, recovering them if a previous holder panicked.
+    fn lock(&self) -> std::sync::MutexGuard<'_, QuotaSizes> {
+        self.sizes
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl PartialEq for QuotaUsage {
+    /// The sizes are only a cache, so they never distinguish managers.
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+/// Sizes the quota directories above a write reach once the write lands, returned by
+/// `check_quotas` and stored with `record_quota_usage`.
+#[derive(Debug)]
+struct QuotaCheck {
+    generation: u64,
+    sizes: Vec<(PathBuf, u64)>,
+}
+
+/// Pending write-behind state stored in **`DatabaseManager`**.
+///
+/// Uses a mutex so `overwrite_existing` can queue data through `&self`.
+#[derive(Debug, Default)]
+struct WriteBehind {
+    debounce: Option<Duration>,
+    pending: Mutex<BTreeMap<PathBuf, PendingWrite>>,
+}
+
+/// Latest queued contents for one file.
+#[derive(Debug)]
+struct PendingWrite {
+    data: Vec<u8>,
+    queued_at: Instant,
+}
+
+impl WriteBehind {
+    /// Locks the pending map, recovering it if a previous holder panicked.
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<PathBuf, PendingWrite>> {
+        self.pending
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl PartialEq for WriteBehind {
+    /// Compares the debounce interval and the queued paths.
+    fn eq(&self, other: &Self) -> bool {
+        self.debounce == other.debounce && self.lock().keys().eq(other.lock().keys())
+    }
+}
+
+/// One progress report from a long-running operation.
+///
+/// Every operation listed in **`ProgressOperation`** sends these to the handler set with
+/// `DatabaseManager::set_progress_handler` or `set_progress_channel`: one event when it starts,
+/// one after each item, and a final one where the done counts match the totals.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Progress {
+    operation: ProgressOperation,
+    items_done: usize,
+    items_total: Option<usize>,
+    bytes_done: u64,
+    bytes_total: Option<u64>,
+    current_path: Option<PathBuf>,
+}
+
+impl Progress {
+    /// Returns the operation reporting progress.
+    pub fn get_operation(&self) -> ProgressOperation {
+        self.operation
+    }
+
+    /// Returns how many items were processed so far.
+    pub fn get_items_done(&self) -> usize {
+        self.items_done
+    }
+
+    /// Returns the total number of items, or `None` if it is not known up front.
+    pub fn get_items_total(&self) -> Option<usize> {
+        self.items_total
+    }
+
+    /// Returns how many bytes were processed so far.
+    pub fn get_bytes_done(&self) -> u64 {
+        self.bytes_done
+    }
+
+    /// Returns the total number of bytes, or `None` if it is not known up front.
+    pub fn get_bytes_total(&self) -> Option<u64> {
+        self.bytes_total
+    }
+
+    /// Returns the path that was just processed, or `None` for start and final events.
+    pub fn get_current_path(&self) -> Option<&Path> {
+        self.current_path.as_deref()
+    }
+
+    /// Returns the completed fraction from `0.0` to `1.0`, by bytes when the byte total is known
+    /// and non-zero, otherwise by items.
+    pub fn get_fraction(&self) -> Option<f64> {
+        match (self.bytes_total, self.items_total) {
+            (Some(total), _) if total > 0 => Some(self.bytes_done as f64 / total as f64),
+            (_, Some(0)) => Some(1.0),
+            (_, Some(total)) => Some(self.items_done as f64 / total as f64),
+            _ => None,
+        }
+    }
+}
+
+/// Receives simple progress updates for a progress bar.
+///
+/// Install one with `DatabaseManager::set_progress_reporter`. It sees the same events as a
+/// **`Progress`** handler, reduced to item counts: `scan_for_changes`, `migrate_database`,
+/// `export_item`, `import_item`, and every other operation listed in **`ProgressOperation`**
+/// report through it.
+///
+/// # Examples
+/// ```no_run
+/// use std::path::Path;
+/// use file_database::ProgressReporter;
+///
+/// struct PrintReporter;
+///
+/// impl ProgressReporter for PrintReporter {
+///     fn on_progress(&self, done: usize, total: Option<usize>, current_path: Option<&Path>) {
+///         println!("{done}/{total:?} {current_path:?}");
+///     }
+/// }
+/// ```
+pub trait ProgressReporter: Send + Sync {
+    /// Called when an operation starts, after each item, and when it finishes.
+    ///
+    /// # Parameters
+    /// - `done`: items finished so far.
+    /// - `total`: items in the whole operation, when known.
+    /// - `current_path`: the item just finished, if any.
+    fn on_progress(&self, done: usize, total: Option<usize>, current_path: Option<&Path>);
+}
+
+/// Shared progress callback stored in **`DatabaseManager`**.
+type ProgressCallback = Arc<dyn Fn(&Progress) + Send + Sync>;
+
+/// Progress handler stored in **`DatabaseManager`**.
+#[derive(Default, Clone)]
+struct ProgressHandler(Option<ProgressCallback>);
+
+impl std::fmt::Debug for ProgressHandler {
+    /// Formats whether a handler is set.
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_tuple("ProgressHandler")
+            .field(&self.0.is_some())
+            .finish()
+    }
+}
+
+impl PartialEq for ProgressHandler {
+    /// Compares handler identity.
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Shared fetch callback stored in **`DatabaseManager`**.
+type FetchCallback = Arc<dyn Fn(&ItemId, &Path) -> Result<Vec<u8>, DatabaseError> + Send + Sync>;
+
+/// Read-through fetch handler stored in **`DatabaseManager`**.
+#[derive(Default, Clone)]
+struct FetchHandler(Option<FetchCallback>);
+
+impl std::fmt::Debug for FetchHandler {
+    /// Formats whether a handler is set.
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_tuple("FetchHandler")
+            .field(&self.0.is_some())
+            .finish()
+    }
+}
+
+impl PartialEq for FetchHandler {
+    /// Compares handler identity.
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Shared callback for create, delete, and overwrite events.
+type ItemHook = Arc<dyn Fn(&ItemId, &Path) + Send + Sync>;
+
+/// Shared callback for rename events.
+type RenameHook = Arc<dyn Fn(&ItemId, &Path, &ItemId, &Path) + Send + Sync>;
+
+/// Event hooks stored in **`DatabaseManager`**.
+#[derive(Default, Clone)]
+struct EventHooks {
+    create: Vec<ItemHook>,
+    delete: Vec<ItemHook>,
+    rename: Vec<RenameHook>,
+    overwrite: Vec<ItemHook>,
+}
+
+impl std::fmt::Debug for EventHooks {
+    /// Formats how many hooks of each kind are registered.
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("EventHooks")
+            .field("create", &self.create.len())
+            .field("delete", &self.delete.len())
+            .field("rename", &self.rename.len())
+            .field("overwrite", &self.overwrite.len())
+            .finish()
+    }
+}
+
+impl PartialEq for EventHooks {
+    /// Compares hook identity.
+    fn eq(&self, other: &Self) -> bool {
+        fn same<T: ?Sized>(a: &[Arc<T>], b: &[Arc<T>]) -> bool {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| Arc::ptr_eq(a, b))
+        }
+        same(&self.create, &other.create)
+            && same(&self.delete, &other.delete)
+            && same(&self.rename, &other.rename)
+            && same(&self.overwrite, &other.overwrite)
+    }
+}
+
+/// Running counts for one operation, sent to the progress handler as items complete.
+///
+/// Counts are kept even without a handler, so callers can use them as totals.
+struct ProgressTracker {
+    handler: ProgressHandler,
+    progress: Progress,
+}
+
+impl ProgressTracker {
+    /// Records one finished item of `bytes` bytes at `path` and reports it.
+    fn advance(&mut self, path: &Path, bytes: u64) {
+        self.progress.items_done += 1;
+        self.progress.bytes_done += bytes;
+        self.emit(Some(path));
+    }
+
+    /// Marks the operation as done and sends the final event if the counts are not complete.
+    fn finish(&mut self) {
+        let items_total = self
+            .progress
+            .items_total
+            .unwrap_or(self.progress.items_done);
+        let bytes_total = self
+            .progress
+            .bytes_total
+            .unwrap_or(self.progress.bytes_done);
+        if self.progress.items_done == items_total && self.progress.bytes_done == bytes_total {
+            return;
+        }
+        self.progress.items_done = items_total;
+        self.progress.bytes_done = bytes_total;
+        self.emit(None);
+    }
+
+    /// Sends the current counts to the handler, if any.
+    fn emit(&mut self, path: Option<&Path>) {
+        if let Some(handler) = &self.handler.0 {
+            self.progress.current_path = path.map(Path::to_path_buf);
+            handler(&self.progress);
+        }
+    }
+}
+
+/// Settings that override the database-wide configuration for one directory subtree.
+///
+/// Set it with `DatabaseManager::set_subtree_config`. Every setting left as `None` is inherited
+/// from the nearest ancestor directory that sets it, and finally from the database-wide value,
+/// so `cache/` can run lax while `documents/` is strict in the same database.
+///
+/// The quota is the exception: it limits the total size of the one directory it is set on, and
+/// every quota on a write's enclosing directories is checked separately.
+///
+/// # Examples
+/// ```no_run
+/// use file_database::SubtreeConfig;
+///
+/// let paranoid = SubtreeConfig::new().with_manifests(true).with_strict_mode(true);
+/// assert_eq!(paranoid.get_manifests(), Some(true));
+/// ```
+#[derive(Debug, PartialEq, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct SubtreeConfig {
+    manifests: Option<bool>,
+    strict: Option<bool>,
+    #[serde(default)]
+    quota: Option<u64>,
+}
+
+impl SubtreeConfig {
+    /// Creates a config that inherits every setting.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Overrides whether directories in the subtree keep manifests with content checksums
+    /// (see `DatabaseManager::set_manifests`).
+    pub fn with_manifests(mut self, enabled: bool) -> Self {
+        self.manifests = Some(enabled);
+        self
+    }
+
+    /// Overrides strict mode for items in the subtree (see `DatabaseManager::set_strict_mode`).
+    pub fn with_strict_mode(mut self, enabled: bool) -> Self {
+        self.strict = Some(enabled);
+        self
+    }
+
+    /// Returns the manifest override, or `None` when it is inherited.
+    pub fn get_manifests(&self) -> Option<bool> {
+        self.manifests
+    }
+
+    /// Returns the strict mode override, or `None` when it is inherited.
+    pub fn get_strict_mode(&self) -> Option<bool> {
+        self.strict
+    }
+
+    /// Limits the total size of the directory and everything below it to `bytes` (see
+    /// `DatabaseManager::set_quota`).
+    pub fn with_quota(mut self, bytes: u64) -> Self {
+        self.quota = Some(bytes);
+        self
+    }
+
+    /// Returns the quota in bytes, or `None` when the directory has none.
+    pub fn get_quota(&self) -> Option<u64> {
+        self.quota
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+/// Dry-run and cancellation options for `gc`.
+///
+/// # Examples
+/// ```no_run
+/// use std::sync::atomic::AtomicBool;
+/// use file_database::GcOptions;
+///
+/// let cancel = AtomicBool::new(false);
+/// let _options = GcOptions::new().with_dry_run(true).with_cancel_flag(&cancel);
+/// ```
+pub struct GcOptions<'a> {
+    dry_run: bool,
+    cancel: Option<&'a AtomicBool>,
+    trash_older_than: Option<Duration>,
+}
+
+impl<'a> GcOptions<'a> {
+    /// Creates options that delete garbage and cannot be cancelled.
+    pub fn new() -> Self {
+        Self {
+            dry_run: false,
+            cancel: None,
+            trash_older_than: None,
+        }
+    }
+
+    /// When `true`, `gc` only reports what it would delete.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Sets a flag that stops the collection when it becomes `true`.
+    ///
+    /// A cancelled collection keeps what it already deleted and returns a report with
+    /// `is_complete() == false`.
+    pub fn with_cancel_flag(mut self, cancel: &'a AtomicBool) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Also purges trashed items that were trashed at least `older_than` ago.
+    pub fn with_trash_older_than(mut self, older_than: Duration) -> Self {
+        self.trash_older_than = Some(older_than);
+        self
+    }
+
+    /// Returns `true` when this is a dry run.
+    pub fn get_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Returns the minimum trash age purged by `gc`, if trash purging is on.
+    pub fn get_trash_older_than(&self) -> Option<Duration> {
+        self.trash_older_than
+    }
+
+    /// Returns `true` when the cancel flag is set.
+    fn is_cancelled(&self) -> bool {
+        self.cancel
+            .is_some_and(|cancel| cancel.load(Ordering::Acquire))
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+/// Summary returned by `gc`.
+pub struct GcReport {
+    temp_files: Vec<PathBuf>,
+    manifests: Vec<PathBuf>,
+    backups: Vec<PathBuf>,
+    trash: Vec<PathBuf>,
+    reclaimed_bytes: u64,
+    dry_run: bool,
+    complete: bool,
+}
+
+impl GcReport {
+    /// Returns absolute paths of leftover staging files that were (or would be) deleted.
+    pub fn get_temp_files(&self) -> &[PathBuf] {
+        &self.temp_files
+    }
+
+    /// Returns absolute paths of stale manifest files that were (or would be) deleted.
+    pub fn get_manifests(&self) -> &[PathBuf] {
+        &self.manifests
+    }
+
+    /// Returns absolute paths of backups beyond `keep_last` that were (or would be) deleted.
+    pub fn get_backups(&self) -> &[PathBuf] {
+        &self.backups
+    }
+
+    /// Returns absolute paths of trashed items that were (or would be) purged.
+    pub fn get_trash(&self) -> &[PathBuf] {
+        &self.trash
+    }
+
+    /// Returns how many bytes were (or would be) freed.
+    pub fn get_reclaimed_bytes(&self) -> u64 {
+        self.reclaimed_bytes
+    }
+
+    /// Returns `true` when nothing was deleted because this was a dry run.
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Returns `false` when the collection was cancelled before it finished.
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// Returns the total number of entries collected.
+    pub fn get_count(&self) -> usize {
+        self.temp_files.len() + self.manifests.len() + self.backups.len() + self.trash.len()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+/// Wildcard pattern that picks tracked items for bulk operations such as `delete_matching`.
+///
+/// Patterns without a `/` are matched against item names. Patterns with a `/` are matched
+/// against paths relative to the selector parent (the database root by default). `*` matches
+/// any run of characters inside one path component, `?` matches a single character, and a
+/// `**` component matches any number of components.
+///
+/// # Examples
+/// ```no_run
+/// use file_database::{ItemId, Selector};
+///
+/// let _logs = Selector::new("*.log");
+/// let _nested = Selector::new("**/cache/*.tmp").with_parent(ItemId::id("projects"));
+/// ```
+pub struct Selector {
+    pattern: String,
+    parent: ItemId,
+}
+
+impl Selector {
+    /// Creates a selector that matches `pattern` anywhere in the database.
+    pub fn new(pattern: impl Into<String>) -> Self {
+        Self {
+            pattern: pattern.into(),
+            parent: ItemId::database_id(),
+        }
+    }
+
+    /// Only matches descendants of the directory `parent`.
+    pub fn with_parent(mut self, parent: impl Into<ItemId>) -> Self {
+        self.parent = parent.into();
+        self
+    }
+
+    /// Returns the wildcard pattern.
+    pub fn get_pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Returns the directory the selector is restricted to.
+    pub fn get_parent(&self) -> &ItemId {
+        &self.parent
+    }
+
+    /// Returns `true` when `path`, relative to the selector parent, matches the pattern.
+    pub fn matches(&self, path: impl AsRef<Path>) -> bool {
+        let path = path.as_ref();
+        if self.pattern.contains('/') {
+            path_matches(&self.pattern, path)
+        } else {
+            path.file_name()
+                .is_some_and(|name| wildcard_match(&self.pattern, &name.to_string_lossy()))
+        }
+    }
+}
+
+impl From<&str> for Selector {
+    /// Same as `Selector::new`.
+    fn from(pattern: &str) -> Self {
+        Self::new(pattern)
+    }
+}
+
+impl From<String> for Selector {
+    /// Same as `Selector::new`.
+    fn from(pattern: String) -> Self {
+        Self::new(pattern)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, serde::Serialize, serde::Deserialize)]
+/// One move recorded in `JOURNAL_FILE_NAME` before the filesystem is touched.
+struct JournalEntry {
+    from: PathBuf,
+    to: PathBuf,
+    /// Set once `to` holds a complete copy, so only removing `from` is left.
+    copied: bool,
+}
+
+/// One recorded operation that `undo` can reverse and `redo` can repeat.
+///
+/// Paths are relative to the database root.
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum UndoAction {
+    /// An item was created at `path`. Undoing it parks the item at `stash`.
+    Created { path: PathBuf, stash: PathBuf },
+    /// The item at `path` was deleted and parked at `stash`.
+    Deleted { path: PathBuf, stash: PathBuf },
+    /// An item was renamed or moved from `from` to `to`.
+    Moved { from: PathBuf, to: PathBuf },
+    /// The file at `path` was overwritten. `stash` holds the contents it does not currently have.
+    Overwritten { path: PathBuf, stash: PathBuf },
+}
+
+impl UndoAction {
+    /// Returns the parked data of this action, if it has any.
+    fn stash(&self) -> Option<&Path> {
+        match self {
+            UndoAction::Created { stash, .. }
+            | UndoAction::Deleted { stash, .. }
+            | UndoAction::Overwritten { stash, .. } => Some(stash),
+            UndoAction::Moved { .. } => None,
+        }
+    }
+}
+
+/// Bounded undo and redo stacks for the current session.
+///
+/// Uses a mutex so `overwrite_existing` can record through `&self`.
+#[derive(Debug, Default)]
+struct UndoHistory {
+    depth: usize,
+    stacks: Mutex<UndoStacks>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct UndoStacks {
+    undo: VecDeque<UndoAction>,
+    redo: Vec<UndoAction>,
+    next_stash: u64,
+    /// How many actions were ever recorded, including ones dropped beyond the depth.
+    recorded: u64,
+}
+
+impl UndoHistory {
+    /// Locks the stacks, recovering them if a previous holder panicked.
+    fn lock(&self) -> std::sync::MutexGuard<'_, UndoStacks> {
+        self.stacks
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl PartialEq for UndoHistory {
+    /// Compares the depth and both stacks.
+    fn eq(&self, other: &Self) -> bool {
+        self.depth == other.depth && *self.lock() == *other.lock()
+    }
+}
+
+/// Advisory lock on `LOCK_FILE_NAME` held for the lifetime of a manager.
+///
+/// Uses `File::lock`, which maps to `flock` on Unix and `LockFileEx` on Windows.
+#[derive(Debug)]
+struct DatabaseLock {
+    file: File,
+}
+
+impl DatabaseLock {
+    /// Opens the lock file in `root` and locks it, waiting for other holders when `wait` is set.
+    fn acquire(root: &Path, wait: bool) -> Result<Self, DatabaseError> {
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(root.join(LOCK_FILE_NAME))?;
+        if wait {
+            file.lock()?;
+        } else {
+            match file.try_lock() {
+                Ok(()) => {}
+                Err(fs::TryLockError::WouldBlock) => {
+                    return Err(DatabaseError::DatabaseLocked(root.to_path_buf()));
+                }
+                Err(fs::TryLockError::Error(error)) => return Err(error.into()),
+            }
+        }
+        Ok(Self { file })
+    }
+
+    /// Moves the lock to `root` without waiting, keeping the current lock when that fails.
+    ///
+    /// The current lock is released first, because `root` may hold the same lock file after the
+    /// folder was moved.
+    fn relocate(&mut self, root: &Path) -> Result<(), DatabaseError> {
+        self.file.unlock()?;
+        match Self::acquire(root, false) {
+            Ok(lock) => {
+                *self = lock;
+                Ok(())
+            }
+            Err(error) => {
+                let _ = self.file.try_lock();
+                Err(error)
+            }
+        }
+    }
+}
+
+impl Drop for DatabaseLock {
+    /// Releases the lock; the lock file itself stays in the database root.
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+impl PartialEq for DatabaseLock {
+    /// Every manager holds exactly one lock, so locks never distinguish managers.
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+/// Contents of `SCAN_STATE_FILE_NAME`: directory listings from the last incremental scan.
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct ScanState {
+    /// When the scan that saved this state started, in nanoseconds since the unix epoch.
+    scanned_at: u64,
+    /// Listings keyed by directory path relative to the database root.
+    directories: BTreeMap<PathBuf, ScanStateDirectory>,
+}
+
+/// One directory listing in a **`ScanState`**.
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct ScanStateDirectory {
+    /// Modified time of the directory, in nanoseconds since the unix epoch.
+    modified: u64,
+    files: Vec<PathBuf>,
+    directories: Vec<PathBuf>,
+}
+
+/// Interchange document read and written by `import_index_json` and `export_index_json`.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct IndexDocument {
+    format: String,
+    version: u32,
+    items: Vec<IndexDocumentItem>,
+}
+
+/// One tracked item in an **`IndexDocument`**.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct IndexDocumentItem {
+    name: String,
+    index: usize,
+    path: String,
+    kind: IndexDocumentKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    modified: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hash: Option<String>,
+}
+
+#[derive(PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum IndexDocumentKind {
+    File,
+    Directory,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+/// One item in the trash, returned by `delete_to_trash` and `get_trash`.
+pub struct TrashedItem {
+    id: ItemId,
+    path: PathBuf,
+    deleted_at: SystemTime,
+}
+
+impl TrashedItem {
+    /// Returns the **`ItemId`** the item had when it was deleted. Pass it to
+    /// `restore_from_trash`.
+    pub fn get_id(&self) -> &ItemId {
+        &self.id
+    }
+
+    /// Returns the relative path the item is restored to.
+    pub fn get_original_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns when the item was moved to the trash.
+    pub fn get_deleted_at(&self) -> SystemTime {
+        self.deleted_at
+    }
+}
+
+/// One entry of the restore map kept in `TRASH_DIR_NAME`.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct TrashEntry {
+    slot: u64,
+    name: String,
+    index: usize,
+    path: PathBuf,
+    deleted_at: u64,
+}
+
+impl TrashEntry {
+    /// Returns the public view of this entry.
+    fn to_trashed_item(&self) -> TrashedItem {
+        TrashedItem {
+            id: ItemId::with_index(self.name.clone(), self.index),
+            path: self.path.clone(),
+            deleted_at: UNIX_EPOCH + Duration::from_millis(self.deleted_at),
+        }
+    }
+}
+
+/// Number of buckets in a **`LatencyHistogram`**.
+const LATENCY_BUCKETS: usize = 32;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+/// Latency distribution of one **`LatencyOperation`**.
+///
+/// Bucket `i` counts calls that took at least `2^i` and less than `2^(i + 1)` microseconds;
+/// bucket `0` also counts calls under one microsecond, and the last bucket counts everything
+/// slower.
+pub struct LatencyHistogram {
+    operation: LatencyOperation,
+    count: u64,
+    total: Duration,
+    max: Duration,
+    buckets: [u64; LATENCY_BUCKETS],
+}
+
+impl LatencyHistogram {
+    /// Creates an empty histogram for `operation`.
+    fn new(operation: LatencyOperation) -> Self {
+        Self {
+            operation,
+            count: 0,
+            total: Duration::ZERO,
+            max: Duration::ZERO,
+            buckets: [0; LATENCY_BUCKETS],
+        }
+    }
+
+    /// Adds one call that took `duration`.
+    fn record(&mut self, duration: Duration) {
+        let micros = duration.as_micros().max(1);
+        let bucket = (u128::BITS - 1 - micros.leading_zeros()) as usize;
+        self.buckets[bucket.min(LATENCY_BUCKETS - 1)] += 1;
+        self.count += 1;
+        self.total += duration;
+        self.max = self.max.max(duration);
+    }
+
+    /// Returns the measured operation.
+    pub fn get_operation(&self) -> LatencyOperation {
+        self.operation
+    }
+
+    /// Returns how many calls were measured.
+    pub fn get_count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns the summed duration of all calls.
+    pub fn get_total(&self) -> Duration {
+        self.total
+    }
+
+    /// Returns the mean duration, or zero when nothing was measured.
+    pub fn get_mean(&self) -> Duration {
+        match self.count {
+            0 => Duration::ZERO,
+            count => self.total.div_f64(count as f64),
+        }
+    }
+
+    /// Returns the slowest call.
+    pub fn get_max(&self) -> Duration {
+        self.max
+    }
+
+    /// Returns the call count of every bucket.
+    pub fn get_buckets(&self) -> &[u64] {
+        &self.buckets
+    }
+
+    /// Returns an upper bound for the `percentile` (`0.0` to `100.0`) latency: the upper edge
+    /// of the bucket it falls in, capped at `get_max`.
+    pub fn get_percentile(&self, percentile: f64) -> Duration {
+        let target = ((percentile.clamp(0.0, 100.0) / 100.0) * self.count as f64).ceil() as u64;
+        let mut seen = 0;
+        for (bucket, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= target.max(1) {
+                return Duration::from_micros(1 << (bucket + 1)).min(self.max);
+            }
+        }
+
+        self.max
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+/// One call that took longer than the threshold of `set_slow_operation_handler`.
+pub struct SlowOperation {
+    operation: LatencyOperation,
+    id: ItemId,
+    path: Option<PathBuf>,
+    duration: Duration,
+}
+
+impl SlowOperation {
+    /// Returns the operation that was slow.
+    pub fn get_operation(&self) -> LatencyOperation {
+        self.operation
+    }
+
+    /// Returns the **`ItemId`** the call targeted.
+    pub fn get_id(&self) -> &ItemId {
+        &self.id
+    }
+
+    /// Returns the absolute path of the item, when the call got far enough to resolve it.
+    pub fn get_path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Returns how long the call took.
+    pub fn get_duration(&self) -> Duration {
+        self.duration
+    }
+}
+
+/// Shared slow-operation callback stored in **`LatencyRecorder`**.
+type SlowOperationCallback = Arc<dyn Fn(&SlowOperation) + Send + Sync>;
+
+/// Latency histograms and the slow-operation hook.
+///
+/// Shared through an `Arc` so a **`LatencyTimer`** can report from inside `&mut self` methods.
+#[derive(Default)]
+struct LatencyRecorder {
+    state: Mutex<LatencyState>,
+}
+
+#[derive(Default)]
+struct LatencyState {
+    tracking: bool,
+    histograms: BTreeMap<LatencyOperation, LatencyHistogram>,
+    slow: Option<(Duration, SlowOperationCallback)>,
+}
+
+impl LatencyRecorder {
+    /// Locks the state, recovering it if a previous holder panicked.
+    fn lock(&self) -> std::sync::MutexGuard<'_, LatencyState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Returns `true` when calls need to be timed at all.
+    fn is_active(&self) -> bool {
+        let state = self.lock();
+        state.tracking || state.slow.is_some()
+    }
+
+    /// Adds one finished call to the histograms and reports it when it was slow.
+    fn record(
+        &self,
+        operation: LatencyOperation,
+        id: ItemId,
+        path: Option<PathBuf>,
+        duration: Duration,
+    ) {
+        let slow = {
+            let mut state = self.lock();
+            if state.tracking {
+                state
+                    .histograms
+                    .entry(operation)
+                    .or_insert_with(|| LatencyHistogram::new(operation))
+                    .record(duration);
+            }
+            state
+                .slow
+                .as_ref()
+                .filter(|(threshold, _)| duration >= *threshold)
+                .map(|(_, callback)| callback.clone())
+        };
+
+        if let Some(callback) = slow {
+            callback(&SlowOperation {
+                operation,
+                id,
+                path,
+                duration,
+            });
+        }
+    }
+}
+
+impl std::fmt::Debug for LatencyRecorder {
+    /// Formats the tracking flag, the histograms, and whether a slow-operation handler is set.
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.lock();
+        formatter
+            .debug_struct("LatencyRecorder")
+            .field("tracking", &state.tracking)
+            .field("histograms", &state.histograms)
+            .field(
+                "slow_threshold",
+                &state.slow.as_ref().map(|(threshold, _)| threshold),
+            )
+            .finish()
+    }
+}
+
+impl PartialEq for LatencyRecorder {
+    /// Compares the tracking flag, the histograms, and handler identity.
+    fn eq(&self, other: &Self) -> bool {
+        let (a, b) = (self.lock(), other.lock());
+        a.tracking == b.tracking
+            && a.histograms == b.histograms
+            && match (&a.slow, &b.slow) {
+                (Some((a_threshold, a)), Some((b_threshold, b))) => {
+                    a_threshold == b_threshold && Arc::ptr_eq(a, b)
+                }
+                (None, None) => true,
+                _ => false,
+            }
+    }
+}
+
+/// Times one call and reports it to the **`LatencyRecorder`** when dropped, so every return
+/// path is measured.
+struct LatencyTimer {
+    recorder: Option<Arc<LatencyRecorder>>,
+    operation: LatencyOperation,
+    id: ItemId,
+    path: Option<PathBuf>,
+    started: Instant,
+}
+
+impl LatencyTimer {
+    /// Remembers the resolved path for the slow-operation report.
+    fn set_path(&mut self, path: &Path) {
+        if self.recorder.is_some() {
+            self.path = Some(path.to_path_buf());
+        }
+    }
+}
+
+impl Drop for LatencyTimer {
+    /// Reports the elapsed time.
+    fn drop(&mut self) {
+        if let Some(recorder) = self.recorder.take() {
+            recorder.record(
+                self.operation,
+                std::mem::replace(&mut self.id, ItemId::database_id()),
+                self.path.take(),
+                self.started.elapsed(),
+            );
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Default, Clone)]
+struct StableVec<T> {
+    list: Vec<Option<T>>,
+    free: Vec<usize>,
+}
+
+impl<T> StableVec<T> {
+    fn push(&mut self, data: T) -> usize {
+        if let Some(index) = self.free.pop() {
+            self.list[index] = Some(data);
+            index
+        } else {
+            self.list.push(Some(data));
+            self.list.len() - 1
+        }
+    }
+
+    fn remove(&mut self, index: usize) -> bool {
+        if index >= self.list.len() || self.list[index].is_none() {
+            return false;
+        }
+
+        self.list[index] = None;
+        self.free.push(index);
+        true
+    }
+
+    fn get(&self, index: usize) -> Option<&T> {
+        self.list.get(index).and_then(|value| value.as_ref())
+    }
+
+    fn insert_at(&mut self, index: usize, data: T) -> bool {
+        if index < self.list.len() {
+            if self.list[index].is_some() {
+                return false;
+            }
+            self.list[index] = Some(data);
+            if let Some(free_index) = self.free.iter().position(|slot| *slot == index) {
+                self.free.swap_remove(free_index);
+            }
+            return true;
+        }
+
+        while self.list.len() < index {
+            let free_slot = self.list.len();
+            self.list.push(None);
+            self.free.push(free_slot);
+        }
+
+        self.list.push(Some(data));
+        true
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.list
+            .iter()
+            .enumerate()
+            .filter_map(|(index, value)| value.as_ref().map(|value| (index, value)))
+    }
+
+    fn iter_mut(&mut self) -> impl Iterator<Item = (usize, &mut T)> {
+        self.list
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, value)| value.as_mut().map(|value| (index, value)))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.list.iter().all(Option::is_none)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+/// Main type that manages a database directory and its index.
+pub struct DatabaseManager {
+    path: PathBuf,
+    items: BTreeMap<String, StableVec<PathBuf>>,
+    access: HashMap<PathBuf, HashMap<String, HashSet<AccessOperation>>>,
+    usage_watchdog: Option<UsageWatchdog>,
+    usage_watchdog_interval: Duration,
+    reservations: HashSet<PathBuf>,
+    normalize_extensions: bool,
+    manifests: bool,
+    backup_policy: Option<BackupPolicy>,
+    maintenance: MaintenanceSchedule,
+    tier_rule: Option<TierRule>,
+    mounts: Vec<PathBuf>,
+    strict: bool,
+    subtree_configs: BTreeMap<PathBuf, SubtreeConfig>,
+    quota: Option<u64>,
+    quota_usage: QuotaUsage,
+    free_space_check: bool,
+    purge_expired_on_open: bool,
+    staging: StagingStrategy,
+    durability: Durability,
+    records: HashMap<PathBuf, ItemRecord>,
+    next_sequence: u64,
+    generation: Generation,
+    write_behind: WriteBehind,
+    progress: ProgressHandler,
+    fetch: FetchHandler,
+    hooks: EventHooks,
+    journal: bool,
+    #[cfg(feature = "os-trash")]
+    os_trash: bool,
+    #[cfg(feature = "parallel")]
+    scan_threads: usize,
+    undo: UndoHistory,
+    latency: Arc<LatencyRecorder>,
+    lock: DatabaseLock,
+    flush_on_drop: bool,
+    index_saved: bool,
+}
+
+impl Drop for DatabaseManager {
+    /// Writes queued write-behind data, removes data parked by the undo history, then saves the
+    /// index if this manager saved or loaded it before and flushing is enabled.
+    fn drop(&mut self) {
+        let _ = self.flush();
+        if self.undo_enabled() {
+            let _ = self.clear_undo_history();
+        }
+        if self.flush_on_drop && self.index_saved && !self.path.as_os_str().is_empty() {
+            let _ = self.save_index();
+        }
+    }
+}
+
+impl DatabaseManager {
+    /// Creates or opens a database directory and returns a manager for it, along with an
+    /// **`OpenReport`** describing leftovers from earlier sessions.
+    ///
+    /// Staging files (ending in `TEMP_FILE_SUFFIX`) left by interrupted atomic writes are
+    /// never indexed. `temp_policy` decides whether they are deleted or left for the caller.
+    /// Moves interrupted while the journal was enabled are finished or rolled back first and
+    /// listed in `get_recovered_moves()`. If an index was saved with `save_index`, it is loaded
+    /// as by `load_index`, and entries whose path is gone are listed in
+    /// `get_dropped_index_entries()`. `get_load_time()` covers all of it.
+    ///
+    /// Fails with `DatabaseLocked` instead of waiting when another manager has the database
+    /// open; `open_with_report` waits.
+    ///
+    /// # Parameters
+    /// - `path`: parent directory where the database folder should exist.
+    /// - `name`: database directory name appended to `path`.
+    /// - `temp_policy`: what to do with leftover temp files.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `create_database` fails (including `DatabaseLocked`),
+    /// - the saved index cannot be read or parsed, or two of its entries claim the same
+    ///   **`ItemId`**,
+    /// - searching for or deleting temp files fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, TempFilePolicy};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let (_manager, report) =
+    ///         DatabaseManager::create_database_with_report(".", "database", TempFilePolicy::Delete)?;
+    ///     for path in report.get_removed_temp_files() {
+    ///         println!("Removed leftover {}", path.display());
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn create_database_with_report(
+        path: impl AsRef<Path>,
+        name: impl AsRef<Path>,
+        temp_policy: TempFilePolicy,
+    ) -> Result<(Self, OpenReport), DatabaseError> {
+        Self::open_reporting(path, name, temp_policy, false)
+    }
+
+    /// Opens (or creates) a database directory like `create_database_with_report`, waiting
+    /// until no other manager holds its lock.
+    ///
+    /// # Parameters
+    /// - `path`: parent directory where the database folder should exist.
+    /// - `name`: database directory name appended to `path`.
+    /// - `temp_policy`: what to do with leftover temp files.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `open` fails,
+    /// - the saved index cannot be read or parsed, or two of its entries claim the same
+    ///   **`ItemId`**,
+    /// - searching for or deleting temp files fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, TempFilePolicy};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let (_manager, report) =
+    ///         DatabaseManager::open_with_report(".", "database", TempFilePolicy::Report)?;
+    ///     if !report.is_clean() {
+    ///         println!("Startup found problems: {report:?}");
+    ///     }
+    ///     println!("Opened in {:?}", report.get_load_time());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn open_with_report(
+        path: impl AsRef<Path>,
+        name: impl AsRef<Path>,
+        temp_policy: TempFilePolicy,
+    ) -> Result<(Self, OpenReport), DatabaseError> {
+        Self::open_reporting(path, name, temp_policy, true)
+    }
+
+    /// Shared body of `create_database_with_report` and `open_with_report`.
+    fn open_reporting(
+        path: impl AsRef<Path>,
+        name: impl AsRef<Path>,
+        temp_policy: TempFilePolicy,
+        wait: bool,
+    ) -> Result<(Self, OpenReport), DatabaseError> {
+        let started = Instant::now();
+        let (mut manager, recovered_moves) = Self::open_locked(path, name, wait)?;
+        let mut report = OpenReport {
+            recovered_moves,
+            ..OpenReport::default()
+        };
+
+        if manager.path.join(INDEX_FILE_NAME).is_file() {
+            report.dropped_index_entries = manager.load_index_file()?.1;
+            if manager.purge_expired_on_open {
+                report.purged_expired = manager.purge_expired()?;
+            }
+        }
+
+        match temp_policy {
+            TempFilePolicy::Report => report.orphaned_temp_files = find_temp_files(&manager.path)?,
+            TempFilePolicy::Delete => report.removed_temp_files = manager.cleanup_temp_files()?,
+        }
+
+        report.load_time = started.elapsed();
+        Ok((manager, report))
+    }
+
+    /// Creates or opens a database directory and returns a manager for it.
+    ///
+    /// The manager holds an advisory lock on `LOCK_FILE_NAME` in the database root until it is
+    /// dropped, so a second manager for the same directory (in this or another process) fails
+    /// with `DatabaseLocked` instead of corrupting it. Use `open` to wait for the lock instead.
+    ///
+    /// # Parameters
+    /// - `path`: parent directory where the database folder should exist.
+    /// - `name`: database directory name appended to `path`.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - the target path exists but is not a directory,
+    /// - parent directories are missing when creating a new database directory,
+    /// - the process cannot create/read directories at the destination,
+    /// - another manager holds the lock (`DatabaseLocked`).
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let _manager = DatabaseManager::create_database(".", "database")?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn create_database(path: impl AsRef<Path>, name: impl AsRef<Path>) -> Result<Self, DatabaseError> {
+        Self::open_locked(path, name, false).map(|(manager, _)| manager)
+    }
+
+    /// Opens (or creates) a database directory, waiting until no other manager holds its lock.
+    ///
+    /// Otherwise the same as `create_database`. Blocks indefinitely while another process (or
+    /// another manager in this process) keeps the database open.
+    ///
+    /// # Parameters
+    /// - `path`: parent directory where the database folder should exist.
+    /// - `name`: database directory name appended to `path`.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - the target path exists but is not a directory,
+    /// - the database directory or lock file cannot be created or locked.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let _manager = DatabaseManager::open(".", "database")?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn open(path: impl AsRef<Path>, name: impl AsRef<Path>) -> Result<Self, DatabaseError> {
+        Self::open_locked(path, name, true).map(|(manager, _)| manager)
+    }
+
+    /// Opens (or creates) a database directory without waiting for its lock.
+    ///
+    /// The same as `create_database`; named to pair with `open`.
+    ///
+    /// # Parameters
+    /// - `path`: parent directory where the database folder should exist.
+    /// - `name`: database directory name appended to `path`.
+    ///
+    /// # Errors
+    /// Returns `DatabaseLocked` if another manager holds the lock, or any error
+    /// `create_database` returns.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     match DatabaseManager::try_open(".", "database") {
+    ///         Ok(_manager) => println!("Opened"),
+    ///         Err(DatabaseError::DatabaseLocked(path)) => println!("{} is busy", path.display()),
+    ///         Err(error) => return Err(error),
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn try_open(path: impl AsRef<Path>, name: impl AsRef<Path>) -> Result<Self, DatabaseError> {
+        Self::open_locked(path, name, false).map(|(manager, _)| manager)
+    }
+
+    /// Locks the database root, replays the journal, and scans the tree. Also returns the moves
+    /// recovered from the journal.
+    fn open_locked(
+        path: impl AsRef<Path>,
+        name: impl AsRef<Path>,
+        wait: bool,
+    ) -> Result<(Self, Vec<PathBuf>), DatabaseError> {
+        let mut path: PathBuf = path.as_ref().to_path_buf();
+
+        path.push(name);
+
+        if !path.exists() {
+            create_dir(&path)?;
+        } else if !path.is_dir() {
+            return Err(DatabaseError::NotADirectory(path));
+        }
+
+        let lock = DatabaseLock::acquire(&path, wait)?;
+        let recovered_moves = replay_journal(&path)?;
+
+        let mut manager = Self {
+            path,
+            items: BTreeMap::new(),
+            access: HashMap::new(),
+            usage_watchdog: None,
+            usage_watchdog_interval: DEFAULT_USAGE_WATCHDOG_INTERVAL,
+            reservations: HashSet::new(),
+            normalize_extensions: false,
+            manifests: false,
+            backup_policy: None,
+            maintenance: MaintenanceSchedule::default(),
+            tier_rule: None,
+            mounts: Vec::new(),
+            strict: false,
+            subtree_configs: BTreeMap::new(),
+            quota: None,
+            quota_usage: QuotaUsage::default(),
+            free_space_check: false,
+            purge_expired_on_open: false,
+            staging: StagingStrategy::default(),
+            durability: Durability::default(),
+            records: HashMap::new(),
+            next_sequence: 0,
+            generation: Generation::default(),
+            write_behind: WriteBehind::default(),
+            progress: ProgressHandler::default(),
+            fetch: FetchHandler::default(),
+            hooks: EventHooks::default(),
+            journal: false,
+            #[cfg(feature = "os-trash")]
+            os_trash: false,
+            #[cfg(feature = "parallel")]
+            scan_threads: 0,
+            undo: UndoHistory::default(),
+            latency: Arc::default(),
+            lock,
+            flush_on_drop: true,
+            index_saved: false,
+        };
+
+        let discovered = manager.collect_paths_in_scope(&manager.path, true)?;
+        let ignore = manager.ignore_selectors(&ScanOptions::new())?;
+        for relative_path in discovered {
+            if is_ignored(&ignore, &relative_path) {
+                continue;
+            }
+            let name = os_str_to_string(relative_path.file_name())?;
+            manager.insert_generated_path(name, relative_path);
+        }
+
+        Ok((manager, recovered_moves))
+    }
+
+    /// Creates a new file or directory under `parent`.
+    ///
+    /// Name interpretation is extension-based:
+    /// - if `id.name` has an extension, a file is created,
+    /// - otherwise, a directory is created.
+    ///
+    /// Returns a **`CreatedItem`** with the exact **`ItemId`**, the absolute path, and an
+    /// **`ItemHandle`** that keeps pointing at the new item after renames and moves.
+    ///
+    /// # Parameters
+    /// - `id`: name key for the new item. Root **`ItemId`** is not allowed.
+    /// - `parent`: destination parent item. Use `ItemId::database_id()` for database root.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` is the `ItemId::database_id()`,
+    /// - `id` is not a valid item name (`DatabaseError::InvalidItemName`, see `ItemId::try_new`),
+    /// - `parent` cannot be found,
+    /// - another item already exists at the target relative path,
+    /// - filesystem create operations fail.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     let created = manager.write_new(ItemId::id("notes.txt"), ItemId::database_id())?;
+    ///     println!("created {}", created.get_path().display());
+    ///     manager.rename(created.get_id(), "renamed.txt")?;
+    ///     assert_eq!(manager.resolve_handle(created.get_handle())?, ItemId::id("renamed.txt"));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn write_new(
+        &mut self,
+        id: impl Into<ItemId>,
+        parent: impl Into<ItemId>,
+    ) -> Result<CreatedItem, DatabaseError> {
+        let id = self.normalize_id(id.into());
+        let mut timer = self.time_operation(LatencyOperation::Create, &id);
+        let (absolute_path, relative_path) = self.new_item_paths(&id, &parent.into())?;
+        timer.set_path(&absolute_path);
+
+        if relative_path.extension().is_none() {
+            create_dir(&absolute_path)?;
+        } else {
+            File::create_new(&absolute_path)?;
+        }
+
+        self.insert_path_for_id(&id, relative_path.clone())?;
+        self.sync_parent_directory(&absolute_path)?;
+        self.emit_created(&id, &relative_path);
+        self.record_created(&relative_path)?;
+        self.after_mutation(&[&relative_path])?;
+
+        Ok(CreatedItem {
+            handle: self.get_handle(&id)?,
+            id,
+            path: absolute_path,
+        })
+    }
+
+    /// Creates `path` under `parent`, including any missing intermediate directories.
+    ///
+    /// Every component before the last is a directory. The last component follows the
+    /// `write_new` rule: a file if it has an extension, otherwise a directory. Directories that
+    /// are already tracked are reused; untracked ones that already exist on disk are added to
+    /// the `index`. Returns the **`ItemId`** of every newly indexed item, outermost first.
+    ///
+    /// If creating a level fails, the directories created by this call are removed again.
+    ///
+    /// # Parameters
+    /// - `path`: relative path such as `"a/b/c/file.txt"`.
+    /// - `parent`: directory item the path starts from (or `ItemId::database_id()`).
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `path` is empty, absolute, or has an invalid component (`DatabaseError::InvalidItemName`),
+    /// - `parent` cannot be found,
+    /// - an intermediate component exists but is not a directory,
+    /// - the final item already exists,
+    /// - filesystem create operations fail.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     let created = manager.write_new_all("a/b/c/file.txt", ItemId::database_id())?;
+    ///     assert_eq!(created.len(), 4);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn write_new_all(
+        &mut self,
+        path: impl AsRef<Path>,
+        parent: impl Into<ItemId>,
+    ) -> Result<Vec<ItemId>, DatabaseError> {
+        let path = path.as_ref();
+        let parent = parent.into();
+
+        let mut names = Vec::new();
+        for component in path.components() {
+            match component {
+                std::path::Component::Normal(name) => {
+                    let name = os_str_to_string(Some(name))?;
+                    ItemId::try_new(name.as_str())?;
+                    names.push(self.index_key(&name).into_owned());
+                }
+                _ => {
+                    return Err(DatabaseError::InvalidItemName(
+                        path.display().to_string(),
+                        "path must be relative and must not contain `.` or `..`",
+                    ));
+                }
+            }
+        }
+        if names.is_empty() {
+            return Err(DatabaseError::InvalidItemName(
+                path.display().to_string(),
+                "path is empty",
+            ));
+        }
+
+        let parent_absolute = self.locate_absolute(&parent)?;
+        if !parent_absolute.is_dir() {
+            return Err(DatabaseError::NotADirectory(parent_absolute));
+        }
+        let mut relative = self.locate_relative(&parent)?.into_path_buf();
+
+        let mut to_index = Vec::new();
+        let mut created_on_disk = Vec::new();
+        let last = names.len() - 1;
+        let result = (|| {
+            for (position, name) in names.iter().enumerate() {
+                relative.push(name);
+                let absolute = self.path.join(&relative);
+                let tracked = self.path_exists_in_index(&relative);
+
+                if position == last {
+                    if tracked || fs::symlink_metadata(&absolute).is_ok() {
+                        return Err(DatabaseError::IdAlreadyExists(name.clone()));
+                    }
+                    if relative.extension().is_none() {
+                        create_dir(&absolute)?;
+                    } else {
+                        File::create_new(&absolute)?;
+                    }
+                    created_on_disk.push(absolute);
+                    to_index.push(relative.clone());
+                } else if absolute.is_dir() {
+                    if !tracked {
+                        to_index.push(relative.clone());
+                    }
+                } else if tracked || fs::symlink_metadata(&absolute).is_ok() {
+                    return Err(DatabaseError::NotADirectory(absolute));
+                } else {
+                    create_dir(&absolute)?;
+                    created_on_disk.push(absolute);
+                    to_index.push(relative.clone());
+                }
+            }
+            Ok(())
+        })();
+
+        if let Err(error) = result {
+            for absolute in created_on_disk.iter().rev() {
+                if absolute.is_dir() {
+                    let _ = remove_dir(absolute);
+                } else {
+                    let _ = remove_file(absolute);
+                }
+            }
+            return Err(error);
+        }
+        for absolute in &created_on_disk {
+            self.sync_parent_directory(absolute)?;
+        }
+
+        let mut ids = Vec::new();
+        for relative in &to_index {
+            let name = os_str_to_string(relative.file_name())?;
+            let id = self.insert_generated_path(name, relative.clone());
+            self.emit_created(&id, relative);
+            ids.push(id);
+        }
+        for absolute in &created_on_disk {
+            if let Ok(relative) = absolute.strip_prefix(&self.path) {
+                self.record_created(relative)?;
+            }
+        }
+        let touched: Vec<&Path> = to_index.iter().map(|path| path.as_path()).collect();
+        self.after_mutation(&touched)?;
+
+        Ok(ids)
+    }
+
+    /// Creates a new file under `parent` that already contains `data`.
+    ///
+    /// The data is written to a temp file first and moved into place, so the file never
+    /// appears on disk empty or half-written.
+    ///
+    /// # Parameters
+    /// - `id`: name key for the new file. It must have an extension.
+    /// - `parent`: destination parent item. Use `ItemId::database_id()` for database root.
+    /// - `data`: initial file contents.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - any `write_new` error occurs,
+    /// - `id` has no extension (`DatabaseError::NotAFile`),
+    /// - writing, syncing, or renaming the temp file fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new_with_contents(ItemId::id("notes.txt"), ItemId::database_id(), "hi")?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn write_new_with_contents<T>(
+        &mut self,
+        id: impl Into<ItemId>,
+        parent: impl Into<ItemId>,
+        data: T,
+    ) -> Result<CreatedItem, DatabaseError>
+    where
+        T: AsRef<[u8]>,
+    {
+        let id = self.normalize_id(id.into());
+        let (absolute_path, relative_path) = self.new_item_paths(&id, &parent.into())?;
+
+        if relative_path.extension().is_none() {
+            return Err(DatabaseError::NotAFile(absolute_path));
+        }
+        if fs::symlink_metadata(&absolute_path).is_ok() {
+            return Err(DatabaseError::IdAlreadyExists(id.as_string()));
+        }
+        let check = self.check_quotas(&relative_path, data.as_ref().len() as u64, 0)?;
+
+        let buffer = self.item_staging_path(&absolute_path)?;
+        let result = (|| {
+            let mut file = File::create(&buffer)?;
+            file.write_all(data.as_ref())?;
+            self.sync_file(&file)?;
+            fs::rename(&buffer, &absolute_path)
+        })();
+        if let Err(error) = result {
+            let _ = remove_file(&buffer);
+            return Err(error.into());
+        }
+        self.sync_parent_directory(&absolute_path)?;
+
+        self.insert_path_for_id(&id, relative_path.clone())?;
+        self.emit_created(&id, &relative_path);
+        self.record_created(&relative_path)?;
+        self.after_mutation(&[&relative_path])?;
+        self.record_quota_usage(check);
+
+        Ok(CreatedItem {
+            handle: self.get_handle(&id)?,
+            id,
+            path: absolute_path,
+        })
+    }
+
+    /// Creates a new file under `parent` that already contains `value` as JSON.
+    ///
+    /// See `write_new_with_contents` for how the file is written.
+    ///
+    /// # Parameters
+    /// - `id`: name key for the new file. It must have an extension.
+    /// - `parent`: destination parent item.
+    /// - `value`: serializable value.
+    /// - `pretty`: whether to pretty-print the JSON.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - JSON serialization fails,
+    /// - any `write_new_with_contents` error occurs.
+    pub fn write_new_with_json<T: serde::Serialize>(
+        &mut self,
+        id: impl Into<ItemId>,
+        parent: impl Into<ItemId>,
+        value: &T,
+        pretty: impl Into<bool>,
+    ) -> Result<CreatedItem, DatabaseError> {
+        let data = if pretty.into() {
+            serde_json::to_vec_pretty(value)?
+        } else {
+            serde_json::to_vec(value)?
+        };
+        self.write_new_with_contents(id, parent, data)
+    }
+
+    /// Creates a new file under `parent` that already contains `value` as bincode.
+    ///
+    /// See `write_new_with_contents` for how the file is written.
+    ///
+    /// # Parameters
+    /// - `id`: name key for the new file. It must have an extension.
+    /// - `parent`: destination parent item.
+    /// - `value`: serializable value.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - bincode serialization fails,
+    /// - any `write_new_with_contents` error occurs.
+    pub fn write_new_with_binary<T: serde::Serialize>(
+        &mut self,
+        id: impl Into<ItemId>,
+        parent: impl Into<ItemId>,
+        value: &T,
+    ) -> Result<CreatedItem, DatabaseError> {
+        let data = bincode::serialize(value)?;
+        self.write_new_with_contents(id, parent, data)
+    }
+
+    /// Overwrites an existing file with raw bytes in a safe way.
+    ///
+    /// It writes to a temp file first, then replaces the target file.
+    ///
+    /// # Parameters
+    /// - `id`: target file **`ItemId`**.
+    /// - `data`: raw bytes to write.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` cannot be found,
+    /// - `id` points to a directory,
+    /// - writing, syncing, or renaming fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("blob.bin"), ItemId::database_id())?;
+    ///     manager.overwrite_existing(ItemId::id("blob.bin"), [1_u8, 2, 3, 4])?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn overwrite_existing<T>(&self, id: impl Into<ItemId>, data: T) -> Result<(), DatabaseError>
+    where
+        T: AsRef<[u8]>,
+    {
+        let id = id.into();
+        let bytes = data.as_ref();
+
+        let mut timer = self.time_operation(LatencyOperation::Write, &id);
+        let path = self.locate_absolute(id)?;
+        timer.set_path(&path);
+        let stash = match self.undo_enabled() {
+            true => Some(self.stash_contents(&path)?),
+            false => None,
+        };
+        let relative_path = path.strip_prefix(&self.path)?.to_path_buf();
+
+        let result = if self.write_behind.debounce.is_some() {
+            if path.is_dir() {
+                return Err(DatabaseError::NotAFile(path));
+            }
+            self.write_behind.lock().insert(
+                path,
+                PendingWrite {
+                    data: bytes.to_vec(),
+                    queued_at: Instant::now(),
+                },
+            );
+            self.flush_due().map(|_| ())
+        } else {
+            self.overwrite_path_atomic_with(&path, |file| {
+                file.write_all(bytes)?;
+                Ok(bytes.len() as u64)
+            })
+            .map(|_| ())
+        };
+
+        if let Some(stash) = stash {
+            match result {
+                Ok(()) => self.record_undo(UndoAction::Overwritten {
+                    path: relative_path,
+                    stash,
+                })?,
+                Err(_) => {
+                    let _ = remove_file(self.path.join(stash));
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Converts `value` to JSON and overwrites the target file.
+    ///
+    /// # Parameters
+    /// - `id`: target file **`ItemId`**.
+    /// - `value`: serializable value.
+    /// - `pretty`: if `true`, writes pretty-printed JSON; otherwise compact JSON.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - JSON serialization fails,
+    /// - finding `id` or overwriting the file fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Config {
+    ///     retries: u8,
+    /// }
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("config.json"), ItemId::database_id())?;
+    ///     manager.overwrite_existing_json(ItemId::id("config.json"), &Config { retries: 3 }, false)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn overwrite_existing_json<T: serde::Serialize>(
+        &self,
+        id: impl Into<ItemId>,
+        value: &T,
+        pretty: impl Into<bool>,
+    ) -> Result<(), DatabaseError> {
+        let pretty = pretty.into();
+        let data = if pretty {
+            serde_json::to_vec_pretty(value)?
+        } else {
+            serde_json::to_vec(value)?
+        };
+        self.overwrite_existing(id, data)
+    }
+
+    /// Converts `value` to bincode and overwrites the target file.
+    ///
+    /// # Parameters
+    /// - `id`: target file **`ItemId`**.
+    /// - `value`: serializable value.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - bincode serialization fails,
+    /// - finding `id` or overwriting the file fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// enum State {
+    ///     Ready,
+    /// }
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("state.bin"), ItemId::database_id())?;
+    ///     manager.overwrite_existing_binary(ItemId::id("state.bin"), &State::Ready)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn overwrite_existing_binary<T: serde::Serialize>(
+        &self,
+        id: impl Into<ItemId>,
+        value: &T,
+    ) -> Result<(), DatabaseError> {
+        let data = bincode::serialize(value)?;
+        self.overwrite_existing(id, data)
+    }
+
+    /// Streams bytes from `reader` into the target file and returns bytes written.
+    ///
+    /// This uses chunked I/O and a safe replace step, so it works well for large payloads.
+    ///
+    /// # Parameters
+    /// - `id`: target file **`ItemId`**.
+    /// - `reader`: source stream consumed until EOF.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` cannot be found,
+    /// - target is not a file,
+    /// - stream read/write/sync/rename fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use std::io::Cursor;
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("stream.bin"), ItemId::database_id())?;
+    ///     let mut source = Cursor::new(vec![9_u8; 1024]);
+    ///     let _bytes = manager.overwrite_existing_from_reader(ItemId::id("stream.bin"), &mut source)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn overwrite_existing_from_reader<R: io::Read>(
+        &self,
+        id: impl Into<ItemId>,
+        reader: &mut R,
+    ) -> Result<u64, DatabaseError> {
+        let id = id.into();
+        let path = self.locate_absolute(id)?;
+        self.write_behind.lock().remove(&path);
+        self.overwrite_path_atomic_with(&path, |file| Ok(io::copy(reader, file)?))
+    }
+
+    /// Streams bytes from `reader` into the target file with chunk pacing and progress reports.
+    ///
+    /// Works like `overwrite_existing_from_reader`, but reads in `options` sized chunks, sleeps
+    /// between chunks to stay under the throughput limit, and calls the progress callback after
+    /// each chunk. Use it for large background imports that should not starve other disk I/O.
+    ///
+    /// # Parameters
+    /// - `id`: target file **`ItemId`**.
+    /// - `reader`: source stream consumed until EOF.
+    /// - `options`: chunk size, throughput limit, and progress callback.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` cannot be found,
+    /// - target is not a file,
+    /// - stream read/write/sync/rename fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use std::io::Cursor;
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId, StreamOptions};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("stream.bin"), ItemId::database_id())?;
+    ///     let mut source = Cursor::new(vec![9_u8; 1024 * 1024]);
+    ///     let options = StreamOptions::new()
+    ///         .with_max_bytes_per_second(512 * 1024)
+    ///         .with_progress(|written| println!("{written} bytes"));
+    ///     manager.overwrite_existing_from_reader_with(ItemId::id("stream.bin"), &mut source, options)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn overwrite_existing_from_reader_with<R: io::Read>(
+        &self,
+        id: impl Into<ItemId>,
+        reader: &mut R,
+        mut options: StreamOptions<'_>,
+    ) -> Result<u64, DatabaseError> {
+        let id = id.into();
+        let path = self.locate_absolute(id)?;
+        self.write_behind.lock().remove(&path);
+
+        self.overwrite_path_atomic_with(&path, |file| {
+            let mut buffer = vec![0u8; options.chunk_size];
+            let started = Instant::now();
+            let mut written = 0u64;
+
+            loop {
+                let read = match reader.read(&mut buffer) {
+                    Ok(0) => break,
+                    Ok(read) => read,
+                    Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(error) => return Err(error.into()),
+                };
+
+                file.write_all(&buffer[..read])?;
+                written += read as u64;
+
+                if let Some(progress) = options.progress.as_mut() {
+                    progress(written);
+                }
+
+                if let Some(limit) = options.max_bytes_per_second {
+                    let target = Duration::from_secs_f64(written as f64 / limit as f64);
+                    if let Some(wait) = target.checked_sub(started.elapsed()) {
+                        thread::sleep(wait);
+                    }
+                }
+            }
+
+            Ok(written)
+        })
+    }
+
+    /// Reads a managed file and returns its raw bytes.
+    ///
+    /// # Parameters
+    /// - `id`: target file **`ItemId`**.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` cannot be found,
+    /// - `id` points to a directory,
+    /// - file reading fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("data.bin"), ItemId::database_id())?;
+    ///     manager.overwrite_existing(ItemId::id("data.bin"), [1_u8, 2, 3])?;
+    ///     let _data = manager.read_existing(ItemId::id("data.bin"))?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn read_existing(&self, id: impl Into<ItemId>) -> Result<Vec<u8>, DatabaseError> {
+        let id = id.into();
+        let mut timer = self.time_operation(LatencyOperation::Read, &id);
+        self.fetch_if_missing(&id)?;
+        let path = self.locate_absolute(id)?;
+        timer.set_path(&path);
+
+        if path.is_dir() {
+            return Err(DatabaseError::NotAFile(path));
+        }
+
+        if let Some(pending) = self.write_behind.lock().get(&path) {
+            return Ok(pending.data.clone());
+        }
+
+        Ok(fs::read(path)?)
+    }
+
+    /// Opens a managed file for buffered, seekable streaming reads.
+    ///
+    /// Unlike `read_existing`, nothing is loaded up front, so this works for files larger than
+    /// memory. Queued write-behind data for the file is flushed first so the reader sees it.
+    ///
+    /// # Parameters
+    /// - `id`: target file **`ItemId`**.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` cannot be found,
+    /// - `id` points to a directory,
+    /// - flushing queued data or opening the file fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use std::io::{self, Read, Seek, SeekFrom};
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let manager = DatabaseManager::create_database(".", "database")?;
+    ///     let mut reader = manager.open_reader(ItemId::id("video.bin"))?;
+    ///     reader.seek(SeekFrom::Start(1024))?;
+    ///     let mut header = [0_u8; 16];
+    ///     reader.read_exact(&mut header)?;
+    ///     io::copy(&mut reader, &mut io::sink())?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn open_reader(&self, id: impl Into<ItemId>) -> Result<io::BufReader<File>, DatabaseError> {
+        let id = id.into();
+        self.fetch_if_missing(&id)?;
+        let path = self.locate_absolute(id)?;
+
+        if path.is_dir() {
+            return Err(DatabaseError::NotAFile(path));
+        }
+        self.flush_pending_writes(|pending, _| pending == path)?;
+
+        Ok(io::BufReader::new(File::open(path)?))
+    }
+
+    /// Opens a managed file for incremental writes that replace it atomically on commit.
+    ///
+    /// This is the streaming form of `overwrite_existing`: write through the returned
+    /// **`ItemWriter`** as data becomes available, then call `commit`. Until then the file keeps
+    /// its old contents, and dropping the writer discards the new data.
+    ///
+    /// # Parameters
+    /// - `id`: target file **`ItemId`**.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` cannot be found,
+    /// - `id` points to a directory,
+    /// - creating the staging file fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use std::io::Write;
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("export.csv"), ItemId::database_id())?;
+    ///     let mut writer = manager.open_writer(ItemId::id("export.csv"))?;
+    ///     writeln!(writer, "id,name")?;
+    ///     for row in 0..1000 {
+    ///         writeln!(writer, "{row},item {row}")?;
+    ///     }
+    ///     writer.commit()?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn open_writer(&self, id: impl Into<ItemId>) -> Result<ItemWriter<'_>, DatabaseError> {
+        let path = self.locate_absolute(id)?;
+
+        if path.is_dir() {
+            return Err(DatabaseError::NotAFile(path));
+        }
+
+        let buffer = self.item_staging_path(&path)?;
+        let file = File::create(&buffer)?;
+
+        Ok(ItemWriter {
+            manager: self,
+            path,
+            buffer,
+            file: Some(io::BufWriter::new(file)),
+            written: 0,
+        })
+    }
+
+    /// Locks one managed item so cooperating processes can coordinate access to it, waiting
+    /// until the lock is available.
+    ///
+    /// Returns an **`ItemLock`** guard; the lock is released when it is dropped. Locks are
+    /// advisory and keyed by the item's path: they are held on a lock file in `LOCKS_DIR_NAME`
+    /// rather than on the item itself, so they survive atomic overwrites (which replace the
+    /// file) but do not follow the item when it is renamed or moved. Only code that calls
+    /// `lock_item` is blocked; other reads and writes go ahead, including
+    /// `append_existing_locked`, which locks the file itself.
+    ///
+    /// # Parameters
+    /// - `id`: item to lock. The database root cannot be locked this way.
+    /// - `kind`: **`LockKind::Shared`** or **`LockKind::Exclusive`**.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` is the database root (`RootIdUnsupported`),
+    /// - `id` cannot be found,
+    /// - the lock file cannot be created or locked.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId, LockKind};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let manager = DatabaseManager::create_database(".", "database")?;
+    ///     let lock = manager.lock_item(ItemId::id("ledger.txt"), LockKind::Exclusive)?;
+    ///     manager.append_existing(ItemId::id("ledger.txt"), b"entry\n")?;
+    ///     drop(lock);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn lock_item(
+        &self,
+        id: impl Into<ItemId>,
+        kind: LockKind,
+    ) -> Result<ItemLock, DatabaseError> {
+        let id = id.into();
+        if id.get_name().is_empty() {
+            return Err(DatabaseError::RootIdUnsupported);
+        }
+
+        let relative_path = self.locate_relative(&id)?;
+        let key = relative_path.as_path().to_string_lossy().replace('\\', "/");
+        let directory = self.path.join(LOCKS_DIR_NAME);
+        fs::create_dir_all(&directory)?;
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(directory.join(format!(
+                "{:016x}.lock",
+                fnv1a(FNV_OFFSET_BASIS, key.as_bytes())
+            )))?;
+
+        match kind {
+            LockKind::Shared => file.lock_shared()?,
+            LockKind::Exclusive => file.lock()?,
+        }
+
+        Ok(ItemLock { id, kind, file })
+    }
+
+    /// Maps a managed file into memory read-only instead of copying it into a `Vec<u8>`.
+    ///
+    /// Only available with the `mmap` feature, and only on 64-bit Unix targets; elsewhere it
+    /// returns an `Unsupported` I/O error. Queued write-behind data for the file is flushed first.
+    ///
+    /// # Safety
+    /// The file must not be truncated or modified in place while the map is alive. Shrinking a
+    /// mapped file makes reads past the new end crash the process (`SIGBUS`), and other in-place
+    /// changes show up as bytes changing under the `&[u8]`.
+    ///
+    /// Most writes in this crate (`overwrite_existing` and the writes built on it, `ItemWriter`
+    /// commits, `FileHandle::write`) rename a new file over the old one and leave existing maps
+    /// untouched. These methods change the file in place instead:
+    /// - `truncate_existing` and `FileHandle::set_len` resize it, and must not be called on a
+    ///   mapped file,
+    /// - `append_existing`, `append_existing_locked`, and `FileHandle::append` add bytes at the
+    ///   end; the mapped bytes stay valid, but the map does not grow to include the new data.
+    ///
+    /// The caller must also ensure other processes and code outside this crate do not truncate
+    /// or modify the file in place while it is mapped.
+    ///
+    /// # Parameters
+    /// - `id`: target file **`ItemId`**.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` cannot be found,
+    /// - `id` points to a directory,
+    /// - opening or mapping the file fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # #[cfg(feature = "mmap")]
+    /// # fn main() -> Result<(), file_database::DatabaseError> {
+    /// use file_database::{DatabaseManager, ItemId};
+    ///
+    /// let manager = DatabaseManager::create_database(".", "database")?;
+    /// let map = unsafe { manager.read_existing_mmap(ItemId::id("corpus.txt"))? };
+    /// let lines = map.iter().filter(|byte| **byte == b'\n').count();
+    /// println!("{lines} lines");
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "mmap"))]
+    /// # fn main() {}
+    /// ```
+    #[cfg(feature = "mmap")]
+    pub unsafe fn read_existing_mmap(
+        &self,
+        id: impl Into<ItemId>,
+    ) -> Result<MappedFile, DatabaseError> {
+        let id = id.into();
+        self.fetch_if_missing(&id)?;
+        let path = self.locate_absolute(id)?;
+
+        if path.is_dir() {
+            return Err(DatabaseError::NotAFile(path));
+        }
+        self.flush_pending_writes(|pending, _| pending == path)?;
+
+        let file = File::open(&path)?;
+        let length = usize::try_from(file.metadata()?.len())
+            .map_err(|_| io::Error::from(io::ErrorKind::OutOfMemory))?;
+        if length == 0 {
+            return Ok(MappedFile {
+                pointer: std::ptr::null(),
+                length,
+            });
+        }
+
+        let pointer = map_file(&file, length)?;
+        Ok(MappedFile { pointer, length })
+    }
+
+    /// Reads a managed file and returns its contents as UTF-8 text.
+    ///
+    /// # Parameters
+    /// - `id`: target file **`ItemId`**.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` cannot be found,
+    /// - `id` points to a directory,
+    /// - file reading fails,
+    /// - the contents are not valid UTF-8 (`DatabaseError::InvalidUtf8`).
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("notes.txt"), ItemId::database_id())?;
+    ///     manager.overwrite_existing(ItemId::id("notes.txt"), "hello")?;
+    ///     assert_eq!(manager.read_existing_string(ItemId::id("notes.txt"))?, "hello");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn read_existing_string(&self, id: impl Into<ItemId>) -> Result<String, DatabaseError> {
+        let id = id.into();
+        let bytes = self.read_existing(&id)?;
+        String::from_utf8(bytes).map_err(|error| {
+            let path = self.locate_absolute(id).unwrap_or_default();
+            DatabaseError::InvalidUtf8(path, error.utf8_error().valid_up_to())
+        })
+    }
+
+    /// Reads a managed file and turns JSON into `T`.
+    ///
+    /// # Parameters
+    /// - `id`: target file **`ItemId`**.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - finding `id` or reading the file fails,
+    /// - JSON deserialization fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// #[derive(Serialize, Deserialize)]
+    /// struct Config {
+    ///     retries: u8,
+    /// }
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("config.json"), ItemId::database_id())?;
+    ///     manager.overwrite_existing_json(ItemId::id("config.json"), &Config { retries: 3 }, false)?;
+    ///     let _loaded: Config = manager.read_existing_json(ItemId::id("config.json"))?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn read_existing_json<T: serde::de::DeserializeOwned>(
+        &self,
+        id: impl Into<ItemId>,
+    ) -> Result<T, DatabaseError> {
+        let bytes = self.read_existing(id)?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    /// Reads a managed file and turns bincode into `T`.
+    ///
+    /// # Parameters
+    /// - `id`: target file **`ItemId`**.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - finding `id` or reading the file fails,
+    /// - bincode deserialization fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    /// use serde::{Deserialize, Serialize};
+    ///
+    /// #[derive(Serialize, Deserialize)]
+    /// enum State {
+    ///     Ready,
+    /// }
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("state.bin"), ItemId::database_id())?;
+    ///     manager.overwrite_existing_binary(ItemId::id("state.bin"), &State::Ready)?;
+    ///     let _loaded: State = manager.read_existing_binary(ItemId::id("state.bin"))?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn read_existing_binary<T: serde::de::DeserializeOwned>(
+        &self,
+        id: impl Into<ItemId>,
+    ) -> Result<T, DatabaseError> {
+        let bytes = self.read_existing(id)?;
+        Ok(bincode::deserialize(&bytes)?)
+    }
+
+    /// Returns every tracked item in the database.
+    ///
+    /// # Parameters
+    /// - `sort`: output ordering (**`SortBy`**, or a `bool` for **`ItemId`** ordering).
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId, SortBy};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("a.txt"), ItemId::database_id())?;
+    ///     let _all = manager.get_all(true);
+    ///     let _grouped = manager.get_all(SortBy::Path);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn get_all(&self, sort: impl Into<SortBy>) -> Vec<ItemId> {
+        sort_listing(self.all_paths(), sort.into(), &self.path)
+    }
+
+    /// Returns every tracked file in the database.
+    ///
+    /// The kind is read from disk, so symlinked files (tiered or attached items) count as files.
+    /// Items missing on disk are left out.
+    ///
+    /// # Parameters
+    /// - `sort`: output ordering (**`SortBy`**, or a `bool` for **`ItemId`** ordering).
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, SortBy};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let manager = DatabaseManager::create_database(".", "database")?;
+    ///     for id in manager.get_all_files(SortBy::Path) {
+    ///         println!("{id:?}");
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn get_all_files(&self, sort: impl Into<SortBy>) -> Vec<ItemId> {
+        sort_listing(self.paths_of_kind(ItemKind::File), sort.into(), &self.path)
+    }
+
+    /// Returns every tracked directory in the database.
+    ///
+    /// See `get_all_files` for how the kind is decided.
+    ///
+    /// # Parameters
+    /// - `sort`: output ordering (**`SortBy`**, or a `bool` for **`ItemId`** ordering).
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let manager = DatabaseManager::create_database(".", "database")?;
+    ///     let directories = manager.get_all_directories(true);
+    ///     println!("{} directories", directories.len());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn get_all_directories(&self, sort: impl Into<SortBy>) -> Vec<ItemId> {
+        sort_listing(
+            self.paths_of_kind(ItemKind::Directory),
+            sort.into(),
+            &self.path,
+        )
+    }
+
+    /// Returns all tracked items that are direct children of `parent`.
+    ///
+    /// If `parent` is the `ItemId::database_id()`, this returns all top-level items.
+    ///
+    /// # Parameters
+    /// - `parent`: parent directory item to query.
+    /// - `sort`: output ordering (**`SortBy`**, or a `bool` for **`ItemId`** ordering).
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `parent` cannot be found,
+    /// - `parent` points to a file instead of a directory.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("folder"), ItemId::database_id())?;
+    ///     manager.write_new(ItemId::id("a.txt"), ItemId::id("folder"))?;
+    ///     let _children = manager.get_by_parent(ItemId::id("folder"), true)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn get_by_parent(
+        &self,
+        parent: impl Into<ItemId>,
+        sort: impl Into<SortBy>,
+    ) -> Result<Vec<ItemId>, DatabaseError> {
+        let parent_path = self.directory_relative_path(&parent.into())?;
+        let children = self
+            .all_paths()
+            .into_iter()
+            .filter(|(_, item_path)| item_path.parent() == Some(parent_path.as_path()))
+            .collect();
+
+        Ok(sort_listing(children, sort.into(), &self.path))
+    }
+
+    /// Returns tracked direct children of `parent` like `get_by_parent`, optionally leaving out
+    /// hidden items.
+    ///
+    /// # Parameters
+    /// - `parent`: parent directory item to query.
+    /// - `sort`: output ordering (**`SortBy`**, or a `bool` for **`ItemId`** ordering).
+    /// - `hidden`: **`HiddenPolicy::Exclude`** drops items whose name starts with `.` or that
+    ///   have the Windows hidden attribute.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `parent` cannot be found,
+    /// - `parent` points to a file instead of a directory.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, HiddenPolicy, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let manager = DatabaseManager::create_database(".", "database")?;
+    ///     let _visible =
+    ///         manager.get_by_parent_with(ItemId::database_id(), true, HiddenPolicy::Exclude)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn get_by_parent_with(
+        &self,
+        parent: impl Into<ItemId>,
+        sort: impl Into<SortBy>,
+        hidden: HiddenPolicy,
+    ) -> Result<Vec<ItemId>, DatabaseError> {
+        let parent_path = self.directory_relative_path(&parent.into())?;
+        let children = self
+            .all_paths()
+            .into_iter()
+            .filter(|(_, item_path)| item_path.parent() == Some(parent_path.as_path()))
+            .filter(|(_, item_path)| {
+                hidden == HiddenPolicy::Include || !is_hidden(&self.path.join(item_path))
+            })
+            .collect();
+
+        Ok(sort_listing(children, sort.into(), &self.path))
+    }
+
+    /// Returns every tracked item below `parent`, at any depth.
+    ///
+    /// `parent` itself is not included. For the `ItemId::database_id()`, this returns every
+    /// tracked item, like `get_all`. Use `SortBy::Path` to get each directory followed by its
+    /// contents.
+    ///
+    /// # Parameters
+    /// - `parent`: directory item whose subtree should be listed.
+    /// - `sort`: output ordering (**`SortBy`**, or a `bool` for **`ItemId`** ordering).
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `parent` cannot be found,
+    /// - `parent` points to a file instead of a directory.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId, SortBy};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new_all("folder/nested/a.txt", ItemId::database_id())?;
+    ///     let subtree = manager.get_descendants(ItemId::id("folder"), SortBy::Path)?;
+    ///     assert_eq!(subtree.len(), 2);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn get_descendants(
+        &self,
+        parent: impl Into<ItemId>,
+        sort: impl Into<SortBy>,
+    ) -> Result<Vec<ItemId>, DatabaseError> {
+        let parent_path = self.directory_relative_path(&parent.into())?;
+        let descendants = self.descendant_paths(&parent_path);
+
+        Ok(sort_listing(descendants, sort.into(), &self.path))
+    }
+
+    /// Returns a lazy iterator over every tracked item below `from`.
+    ///
+    /// Items are read straight from the `index` as the iterator advances, so no list of the
+    /// whole subtree is built. They come in `index` order (like `get_all(SortBy::Unsorted)`),
+    /// not parent-first; use **`WalkEntry::get_depth`** or `get_descendants` with
+    /// `SortBy::Path` when tree order matters. `from` itself is not yielded.
+    ///
+    /// Each item's kind is read from disk when it is reached. An item whose metadata lookup
+    /// fails yields the error in its place, and iteration can continue past it. If `from`
+    /// cannot be resolved, the iterator yields that error and stops.
+    ///
+    /// # Parameters
+    /// - `from`: directory to walk (or `ItemId::database_id()` for the whole database).
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId, ItemKind};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let manager = DatabaseManager::create_database(".", "database")?;
+    ///     for entry in manager.walk(ItemId::database_id()) {
+    ///         let entry = entry?;
+    ///         if entry.get_kind() == ItemKind::File {
+    ///             println!("{} (depth {})", entry.get_path().display(), entry.get_depth());
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn walk(
+        &self,
+        from: impl Into<ItemId>,
+    ) -> impl Iterator<Item = Result<WalkEntry, DatabaseError>> {
+        let (root, error) = match self.directory_relative_path(&from.into()) {
+            Ok(root) => (Some(root.into_path_buf()), None),
+            Err(error) => (None, Some(error)),
+        };
+
+        let entries = self
+            .items
+            .iter()
+            .flat_map(|(name, paths)| paths.iter().map(move |(index, path)| (name, index, path)))
+            .filter_map(move |(name, index, path)| {
+                let below = path
+                    .strip_prefix(root.as_ref()?)
+                    .ok()
+                    .filter(|below| !below.as_os_str().is_empty())?;
+                let depth = below.components().count();
+
+                Some(match fs::metadata(self.path.join(path)) {
+                    Ok(metadata) => Ok(WalkEntry {
+                        id: ItemId::with_index(name.clone(), index),
+                        path: path.clone(),
+                        depth,
+                        kind: match metadata.is_dir() {
+                            true => ItemKind::Directory,
+                            false => ItemKind::File,
+                        },
+                    }),
+                    Err(error) => Err(error.into()),
+                })
+            });
+
+        error.map(Err).into_iter().chain(entries)
+    }
+
+    /// Returns the tracked subtree below `from` as a nested **`TreeNode`**.
+    ///
+    /// The root node is `from` itself, and every node's children are sorted by name. A node's
+    /// kind is read from disk; an item missing on disk counts as a directory when it has tracked
+    /// children or no extension, and as a file otherwise. The whole tree is built in memory, so
+    /// use `walk` for very large subtrees.
+    ///
+    /// # Parameters
+    /// - `from`: directory at the root of the tree (or `ItemId::database_id()`).
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `from` cannot be found,
+    /// - `from` points to a file instead of a directory.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new_all("docs/guide/intro.md", ItemId::database_id())?;
+    ///     let tree = manager.tree(ItemId::database_id())?;
+    ///     println!("{}", serde_json::to_string_pretty(&tree)?);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn tree(&self, from: impl Into<ItemId>) -> Result<TreeNode, DatabaseError> {
+        let from = from.into();
+        let root = self.directory_relative_path(&from)?.into_path_buf();
+
+        let mut children: HashMap<&Path, Vec<(ItemId, &PathBuf)>> = HashMap::new();
+        for (id, path) in self.all_paths() {
+            let below = path
+                .strip_prefix(&root)
+                .is_ok_and(|below| !below.as_os_str().is_empty());
+            if let (true, Some(parent)) = (below, path.parent()) {
+                children.entry(parent).or_default().push((id, path));
+            }
+        }
+
+        let id = match root.as_os_str().is_empty() {
+            true => ItemId::database_id(),
+            false => self.id_for_path(&root).unwrap_or(from),
+        };
+        Ok(self.tree_node(id, &root, &mut children))
+    }
+
+    /// Returns how many tracked items are direct children of `parent`.
+    ///
+    /// This counts straight from the `index` without building a list of **`ItemId`** values.
+    ///
+    /// # Parameters
+    /// - `parent`: parent directory item to query.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `parent` cannot be found,
+    /// - `parent` points to a file instead of a directory.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("folder"), ItemId::database_id())?;
+    ///     manager.write_new(ItemId::id("a.txt"), ItemId::id("folder"))?;
+    ///     assert_eq!(manager.count_children(ItemId::id("folder"))?, 1);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn count_children(&self, parent: impl Into<ItemId>) -> Result<usize, DatabaseError> {
+        let parent_path = self.directory_relative_path(&parent.into())?;
+
+        Ok(self
+            .items
+            .values()
+            .flat_map(|paths| paths.iter())
+            .filter(|(_, path)| path.parent() == Some(parent_path.as_path()))
+            .count())
+    }
+
+    /// Returns `true` when `parent` has at least one tracked direct child.
+    ///
+    /// Stops at the first match, so it is cheaper than `count_children` for large directories.
+    ///
+    /// # Parameters
+    /// - `parent`: parent directory item to query.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `parent` cannot be found,
+    /// - `parent` points to a file instead of a directory.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("folder"), ItemId::database_id())?;
+    ///     assert!(!manager.has_children(ItemId::id("folder"))?);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn has_children(&self, parent: impl Into<ItemId>) -> Result<bool, DatabaseError> {
+        let parent_path = self.directory_relative_path(&parent.into())?;
+
+        Ok(self
+            .items
+            .values()
+            .flat_map(|paths| paths.iter())
+            .any(|(_, path)| path.parent() == Some(parent_path.as_path())))
+    }
+
+    /// Returns the parent **`ItemId`** for an item.
+    ///
+    /// Top-level items return [`ItemId::database_id`]. The parent is resolved by its path, so
+    /// the returned `index` is correct even when several directories share the parent's `name`.
+    ///
+    /// # Parameters
+    /// - `id`: item whose parent should be looked up.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` cannot be found,
+    /// - the parent directory is not tracked in the `index`.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("folder"), ItemId::database_id())?;
+    ///     manager.write_new(ItemId::id("a.txt"), ItemId::id("folder"))?;
+    ///     let parent = manager.get_parent(ItemId::id("a.txt"))?;
+    ///     assert_eq!(parent, ItemId::id("folder"));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn get_parent(&self, id: impl Into<ItemId>) -> Result<ItemId, DatabaseError> {
+        let id = id.into();
+        let path = self.locate_relative(&id)?;
+
+        let parent = match path.parent() {
+            Some(parent) => parent,
+            None => return Ok(ItemId::database_id()),
+        };
+
+        if parent.as_os_str().is_empty() {
+            return Ok(ItemId::database_id());
+        }
+
+        self.id_for_path(parent)
+            .ok_or_else(|| DatabaseError::NoParent(id.as_string()))
+    }
+
+    /// Renames the chosen item to `to` in the same parent directory.
+    ///
+    /// Returns the renamed **`ItemId`**. It keeps the index of `id` when that slot is free under
+    /// the new `name`, otherwise it takes the next free index.
+    ///
+    /// # Parameters
+    /// - `id`: source **`ItemId`** to rename.
+    /// - `to`: new file or directory name.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` is the `ItemId::database_id()`,
+    /// - `to` is not a valid item name (`DatabaseError::InvalidItemName`, see `ItemId::try_new`),
+    /// - `id` cannot be found,
+    /// - destination `name` already exists at the same relative `path`,
+    /// - underlying filesystem rename fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("old.txt"), ItemId::database_id())?;
+    ///     let renamed = manager.rename(ItemId::id("old.txt"), "new.txt")?;
+    ///     let _data = manager.read_existing(renamed)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn rename(
+        &mut self,
+        id: impl Into<ItemId>,
+        to: impl AsRef<str>,
+    ) -> Result<ItemId, DatabaseError> {
+        self.flush()?;
+
+        let id = id.into();
+        let name = self.index_key(to.as_ref()).into_owned();
+        let mut timer = self.time_operation(LatencyOperation::Rename, &id);
+
+        if id.get_name().is_empty() {
+            return Err(DatabaseError::RootIdUnsupported);
+        }
+        validate_item_name(&name)?;
+
+        let path = self.locate_absolute(&id)?;
+        timer.set_path(&path);
+        let mut relative_path = self.locate_relative(&id)?.into_path_buf();
+        let previous_relative_path = relative_path.clone();
+
+        let renamed_path = path.with_file_name(&name);
+        relative_path = match relative_path.pop() {
+            true => {
+                relative_path.push(&name);
+                relative_path
+            }
+            false => PathBuf::from(&name),
+        };
+
+        if self
+            .all_paths()
+            .iter()
+            .any(|(entry_id, entry_path)| entry_id != &id && *entry_path == &relative_path)
+        {
+            return Err(DatabaseError::IdAlreadyExists(name));
+        }
+
+        fs::rename(&path, &renamed_path)?;
+
+        let record = self.records.get(&previous_relative_path).cloned();
+        self.remove_id_from_index(&id)?;
+        self.relocate_index_paths(&previous_relative_path, &relative_path);
+        self.relocate_access(&previous_relative_path, &relative_path);
+        self.relocate_subtree_configs(&previous_relative_path, &relative_path);
+        self.relocate_reservations(&previous_relative_path, &relative_path);
+
+        let slot_taken = self
+            .items
+            .get(&name)
+            .is_some_and(|paths| paths.get(id.get_index()).is_some());
+        let new_id = if slot_taken {
+            self.insert_generated_path(name, relative_path.clone())
+        } else {
+            let new_id = ItemId::with_index(name, id.get_index());
+            self.insert_path_for_id(&new_id, relative_path.clone())?;
+            new_id
+        };
+        self.restore_record(relative_path.clone(), record);
+        self.sync_parent_directory(&renamed_path)?;
+        self.emit_renamed(&id, &previous_relative_path, &new_id, &relative_path);
+        if self.undo_enabled() {
+            self.record_undo(UndoAction::Moved {
+                from: previous_relative_path.clone(),
+                to: relative_path.clone(),
+            })?;
+        }
+
+        self.after_mutation(&[&previous_relative_path, &relative_path])?;
+
+        Ok(new_id)
+    }
+
+    /// Deletes a file, directory, or the whole database root.
+    ///
+    /// Returns a **`DeleteSummary`** with the item kind, how many descendants were removed, and
+    /// how many bytes were freed. Index entries for the item and everything below it are removed.
+    ///
+    /// # Parameters
+    /// - `id`: item to delete. Use `ItemId::database_id()` to target the database folder itself.
+    /// - `force`: when deleting directories, controls recursive vs empty-only behavior.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` cannot be found,
+    /// - directory deletion does not match `force` rules,
+    /// - filesystem delete operations fail.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ForceDeletion, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("tmp.txt"), ItemId::database_id())?;
+    ///     let summary = manager.delete(ItemId::id("tmp.txt"), ForceDeletion::Force)?;
+    ///     println!("freed {} bytes", summary.get_bytes_freed());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn delete(
+        &mut self,
+        id: impl Into<ItemId>,
+        force: impl Into<bool>,
+    ) -> Result<DeleteSummary, DatabaseError> {
+        self.flush()?;
+
+        let id = id.into();
+        let force = force.into();
+        let mut timer = self.time_operation(LatencyOperation::Delete, &id);
+
+        if id.get_name().is_empty() {
+            let path = self.locate_absolute(id)?;
+            let (bytes_freed, count) = tree_totals(&path)?;
+            match self.delete_directory(&path, force) {
+                Ok(_) => {
+                    self.sync_parent_directory(&path)?;
+                    self.path = PathBuf::new();
+                    self.items.clear();
+                    self.access.clear();
+                    self.reservations.clear();
+                    self.records.clear();
+                    *self.undo.lock() = UndoStacks::default();
+                    self.generation.bump();
+                    return Ok(DeleteSummary {
+                        kind: ItemKind::Directory,
+                        descendants_removed: count - 1,
+                        bytes_freed,
+                    });
+                }
+                Err(error) => return Err(error),
+            }
+        }
+
+        let path = self.locate_absolute(&id)?;
+        let relative_path = self.locate_relative(&id)?;
+        timer.set_path(&path);
+
+        #[cfg(feature = "os-trash")]
+        if self.os_trash && !is_symlink(&path) {
+            let kind = removable_kind(&path, force)?;
+            let (bytes_freed, count) = tree_totals(&path)?;
+            os_trash::move_to_trash(&path)?;
+            self.forget_subtree(&id, &relative_path)?;
+            self.sync_parent_directory(&path)?;
+            self.emit_deleted(&id, &relative_path);
+            self.after_mutation(&[&relative_path])?;
+
+            return Ok(DeleteSummary {
+                kind,
+                descendants_removed: count - 1,
+                bytes_freed,
+            });
+        }
+
+        if self.undo_enabled() {
+            let kind = removable_kind(&path, force)?;
+            let (bytes_freed, count) = tree_totals(&path)?;
+            let stash = self.next_undo_stash()?;
+            self.park_item(&relative_path, &stash)?;
+            self.sync_parent_directory(&path)?;
+            self.record_undo(UndoAction::Deleted {
+                path: relative_path.clone().into_path_buf(),
+                stash,
+            })?;
+            self.after_mutation(&[&relative_path])?;
+
+            return Ok(DeleteSummary {
+                kind,
+                descendants_removed: count - 1,
+                bytes_freed,
+            });
+        }
+
+        let summary = if is_symlink(&path) {
+            let kind = if path.is_dir() {
+                ItemKind::Directory
+            } else {
+                ItemKind::File
+            };
+            let mut bytes_freed = 0;
+            if let Some(target) = self.tiered_target(&path) {
+                bytes_freed = fs::metadata(&target).map_or(0, |metadata| metadata.len());
+                remove_file(target)?;
+            }
+            remove_symlink(&path)?;
+            DeleteSummary {
+                kind,
+                descendants_removed: 0,
+                bytes_freed,
+            }
+        } else if path.is_dir() {
+            let (bytes_freed, count) = tree_totals(&path)?;
+            self.delete_directory(&path, force)?;
+            DeleteSummary {
+                kind: ItemKind::Directory,
+                descendants_removed: count - 1,
+                bytes_freed,
+            }
+        } else {
+            let bytes_freed = fs::metadata(&path)?.len();
+            remove_file(&path)?;
+            DeleteSummary {
+                kind: ItemKind::File,
+                descendants_removed: 0,
+                bytes_freed,
+            }
+        };
+
+        self.forget_subtree(&id, &relative_path)?;
+        self.sync_parent_directory(&path)?;
+        self.emit_deleted(&id, &relative_path);
+
+        self.after_mutation(&[&relative_path])?;
+
+        Ok(summary)
+    }
+
+    /// Gets the absolute file path for an **`ItemId`**.
+    ///
+    /// For the `ItemId::database_id()`, this returns the database directory path.
+    ///
+    /// # Parameters
+    /// - `id`: **`ItemId`** to look up.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - the database root directory is gone (`DatabaseError::DatabaseRootMissing`),
+    /// - `id` does not exist,
+    /// - strict mode is on and the entry is stale (`DatabaseError::StaleIndexEntry`).
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("a.txt"), ItemId::database_id())?;
+    ///     let _path = manager.locate_absolute(ItemId::id("a.txt"))?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn locate_absolute(&self, id: impl Into<ItemId>) -> Result<PathBuf, DatabaseError> {
+        let id = id.into();
+        self.check_root()?;
+
+        if id.get_name().is_empty() {
+            return Ok(self.path.to_path_buf());
+        }
+
+        let relative_path = self.resolve_path_by_id(&id)?;
+        self.check_strict(&id, relative_path)?;
+
+        Ok(self.path.join(relative_path))
+    }
+
+    /// Gets the database-relative path for an **`ItemId`**.
+    ///
+    /// For the `ItemId::database_id()`, this returns an empty **`RelativePath`**.
+    ///
+    /// # Parameters
+    /// - `id`: **`ItemId`** to look up.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` does not exist,
+    /// - strict mode is on and the entry is stale (`DatabaseError::StaleIndexEntry`).
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("a.txt"), ItemId::database_id())?;
+    ///     let _relative = manager.locate_relative(ItemId::id("a.txt"))?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn locate_relative(&self, id: impl Into<ItemId>) -> Result<RelativePath, DatabaseError> {
+        let id = id.into();
+        let relative_path = self.relative_path_of(&id)?;
+        if !relative_path.as_os_str().is_empty() {
+            self.check_strict(&id, &relative_path)?;
+        }
+
+        Ok(RelativePath(relative_path))
+    }
+
+    /// Returns all stored **`ItemId`** values that share a `name`.
+    ///
+    /// The returned IDs use the occupied stable indexes from that internal name bucket.
+    pub fn get_ids_by_name(&self, name: impl AsRef<str>) -> Vec<ItemId> {
+        let name = self.index_key(name.as_ref());
+        self.items
+            .get(name.as_ref())
+            .map(|paths| {
+                paths
+                    .iter()
+                    .map(|(index, _)| ItemId::with_index(name.as_ref(), index))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns all stored **`ItemId`** values that share an `index`.
+    ///
+    /// This scans all name buckets and returns every ID whose stable slot equals `index`.
+    pub fn get_ids_by_index(&self, index: usize) -> Vec<ItemId> {
+        self.items
+            .iter()
+            .filter_map(|(name, paths)| {
+                paths
+                    .get(index)
+                    .map(|_| ItemId::with_index(name.clone(), index))
+            })
+            .collect()
+    }
+
+    /// Scans files on disk and compares them to entries in this scan area.
+    ///
+    /// Missing tracked items are always removed from the `items` index kept in memory.
+    ///
+    /// Policy behavior for newly discovered external items:
+    /// - `DetectOnly`: detect only.
+    /// - `AddNew`: detect and add to the `index`.
+    /// - `RemoveNew`: delete from disk and do not keep them in the report.
+    ///
+    /// # Parameters
+    /// - `scan_from`: root **`ItemId`** to scan from (`ItemId::database_id()` scans the full database).
+    /// - `policy`: change handling policy.
+    /// - `recursive`: `true` scans full subtree, `false` scans immediate children only.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `scan_from` cannot be found,
+    /// - `scan_from` points to a file,
+    /// - path-to-string conversion fails for discovered entries,
+    /// - filesystem read or delete operations fail.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId, ScanPolicy};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     let _report = manager.scan_for_changes(ItemId::database_id(), ScanPolicy::AddNew, true)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn scan_for_changes(
+        &mut self,
+        scan_from: impl Into<ItemId>,
+        policy: ScanPolicy,
+        recursive: bool,
+    ) -> Result<ScanReport, DatabaseError> {
+        self.scan_for_changes_with(scan_from, policy, recursive, ScanOptions::new())
+    }
+
+    /// Scans like `scan_for_changes`, skipping untracked items that match an ignore pattern or
+    /// are hidden under **`HiddenPolicy::Exclude`**.
+    ///
+    /// Patterns come from `IGNORE_FILE_NAME` (`.dbignore`) in the database root, one per line,
+    /// and from `options`. The `.dbignore` file also applies to plain `scan_for_changes` and to
+    /// the scan run when the database is opened. Skipped items are never reported, and the
+    /// `AddNew` and `RemoveNew` policies leave them alone. Skipping a directory skips
+    /// everything below it.
+    ///
+    /// # Parameters
+    /// - `scan_from`: root **`ItemId`** to scan from (`ItemId::database_id()` scans the full database).
+    /// - `policy`: change handling policy.
+    /// - `recursive`: `true` scans full subtree, `false` scans immediate children only.
+    /// - `options`: extra ignore patterns and the hidden-item policy.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `scan_for_changes` would fail,
+    /// - `.dbignore` exists but cannot be read.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId, ScanOptions, ScanPolicy};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     let options = ScanOptions::new().with_ignore("**/build").with_ignore("*.swp");
+    ///     let _report =
+    ///         manager.scan_for_changes_with(ItemId::database_id(), ScanPolicy::RemoveNew, true, options)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn scan_for_changes_with(
+        &mut self,
+        scan_from: impl Into<ItemId>,
+        policy: ScanPolicy,
+        recursive: bool,
+        options: ScanOptions,
+    ) -> Result<ScanReport, DatabaseError> {
+        self.scan(scan_from.into(), policy, recursive, options, false)
+    }
+
+    /// Scans the whole subtree like `scan_for_changes_with`, reusing the directory listings
+    /// saved by the previous incremental scan for directories that have not changed.
+    ///
+    /// A directory's modified time changes whenever an entry is added to, removed from, or
+    /// renamed inside it, so directories whose time matches the saved state are not read
+    /// again; only the directories themselves are checked. This turns a rescan of a large tree
+    /// with few directories, such as a media library, into a fraction of the work. The state is
+    /// kept in `SCAN_STATE_FILE_NAME` in the database root and survives restarts. Listings
+    /// saved within two seconds of their directory's last change are re-read, so changes on
+    /// filesystems with coarse timestamps are not missed. Changes to file contents are not
+    /// detected, just as with a full scan.
+    ///
+    /// # Parameters
+    /// - `scan_from`: root **`ItemId`** to scan from (`ItemId::database_id()` scans the full database).
+    /// - `policy`: change handling policy.
+    /// - `options`: ignore patterns and the hidden-item policy.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `scan_for_changes_with` would fail,
+    /// - the scan state cannot be written.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId, ScanOptions, ScanPolicy};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "media")?;
+    ///     let report = manager.scan_for_changes_incremental(
+    ///         ItemId::database_id(),
+    ///         ScanPolicy::AddNew,
+    ///         ScanOptions::new(),
+    ///     )?;
+    ///     println!("{} changes", report.get_total_changed_count());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn scan_for_changes_incremental(
+        &mut self,
+        scan_from: impl Into<ItemId>,
+        policy: ScanPolicy,
+        options: ScanOptions,
+    ) -> Result<ScanReport, DatabaseError> {
+        self.scan(scan_from.into(), policy, true, options, true)
+    }
+
+    /// Moves the entire database directory to a new parent directory.
+    ///
+    /// Existing destination database directory with the same name is removed first. With
+    /// `set_free_space_check(true)`, the destination filesystem is checked for room first.
+    /// The manager's lock moves to the new root before the old one is removed.
+    ///
+    /// # Parameters
+    /// - `to`: destination parent directory.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - current database path is invalid,
+    /// - destination cleanup fails,
+    /// - the new root cannot be locked (including `DatabaseLocked`),
+    /// - recursive copy or source removal fails.
+    ///
+    /// # Examples
+    /// ```
+    /// use file_database::{DatabaseError, DatabaseManager};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let parent = std::env::temp_dir().join("file_database_migrate_lock");
+    ///     let _ = std::fs::remove_dir_all(&parent);
+    ///     std::fs::create_dir_all(parent.join("new_parent"))?;
+    ///     let mut manager = DatabaseManager::create_database(&parent, "database")?;
+    ///     manager.migrate_database(parent.join("new_parent"))?;
+    ///
+    ///     let second = DatabaseManager::try_open(parent.join("new_parent"), "database");
+    ///     assert!(matches!(second, Err(DatabaseError::DatabaseLocked(_))));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn migrate_database(&mut self, to: impl AsRef<Path>) -> Result<(), DatabaseError> {
+        self.flush()?;
+
+        let destination = to.as_ref().to_path_buf();
+        let name = self
+            .path
+            .file_name()
+            .ok_or_else(|| DatabaseError::NotADirectory(self.path.clone()))?;
+        let destination_database_path = destination.join(name);
+
+        if self.free_space_check {
+            self.preflight_free_space(&destination, directory_size(&self.path)?)?;
+        }
+        if destination_database_path.exists() {
+            remove_dir_all(&destination_database_path)?;
+        }
+
+        let mut tracker = self.start_progress(ProgressOperation::Migrate, &[&self.path]);
+        self.copy_directory_recursive(&self.path, &destination_database_path, &mut tracker)?;
+        self.sync_parent_directory(&destination_database_path)?;
+        self.lock.relocate(&destination_database_path)?;
+        remove_dir_all(&self.path)?;
+        self.sync_parent_directory(&self.path)?;
+        tracker.finish();
+
+        self.path = destination_database_path;
+
+        // Copying gives every file a new modified time, so every manifest is rewritten.
+        self.after_mutation(&[])?;
+        if self.uses_manifests() {
+            self.refresh_manifests()?;
+        }
+
+        Ok(())
+    }
+
+    /// Renames the database root directory in place, keeping the same parent directory.
+    ///
+    /// This is a single filesystem rename, so nothing is copied. Every **`ItemId`** stays valid
+    /// because the `index` stores paths relative to the root.
+    ///
+    /// # Parameters
+    /// - `new_name`: new folder name for the database root.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `new_name` is not a valid single folder name,
+    /// - current database path is invalid,
+    /// - a file or folder named `new_name` already exists next to the database,
+    /// - the filesystem rename fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.rename_database("archive")?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn rename_database(&mut self, new_name: impl AsRef<str>) -> Result<(), DatabaseError> {
+        let new_name = new_name.as_ref();
+        ItemId::try_new(new_name)?;
+        self.flush()?;
+
+        let parent = self
+            .path
+            .parent()
+            .ok_or_else(|| DatabaseError::NotADirectory(self.path.clone()))?;
+        let destination = parent.join(new_name);
+
+        if destination == self.path {
+            return Ok(());
+        }
+        if fs::symlink_metadata(&destination).is_ok() {
+            return Err(DatabaseError::IdAlreadyExists(new_name.to_string()));
+        }
+
+        fs::rename(&self.path, &destination)?;
+        self.sync_parent_directory(&destination)?;
+        self.path = destination;
+
+        self.after_mutation(&[])?;
+
+        Ok(())
+    }
+
+    /// Returns `true` while the database root directory still exists.
+    ///
+    /// If the folder is deleted or moved by something outside this manager, methods that touch
+    /// the filesystem return `DatabaseError::DatabaseRootMissing` until `reattach` points the
+    /// manager at the folder's new location.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let manager = DatabaseManager::create_database(".", "database")?;
+    ///     assert!(manager.is_root_alive());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn is_root_alive(&self) -> bool {
+        self.path.is_dir()
+    }
+
+    /// Points this manager at a new root directory, for example after the user moved the folder.
+    ///
+    /// The `index` is kept as is, since it stores paths relative to the root, and queued
+    /// write-behind data follows the new root. The manager's lock moves to the new root too.
+    /// Returns how many tracked items are missing under the new root, so `0` means the folder
+    /// matches the `index`. Nothing on disk is changed apart from the lock file.
+    ///
+    /// # Parameters
+    /// - `path`: new database root directory.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `path` is not an existing directory,
+    /// - another manager holds the lock on `path` (`DatabaseLocked`).
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     if !manager.is_root_alive() {
+    ///         let missing = manager.reattach("./moved/database")?;
+    ///         println!("{missing} items missing after reattach");
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn reattach(&mut self, path: impl AsRef<Path>) -> Result<usize, DatabaseError> {
+        let path = path.as_ref().to_path_buf();
+        if !path.is_dir() {
+            return Err(DatabaseError::NotADirectory(path));
+        }
+        self.lock.relocate(&path)?;
+
+        {
+            let mut pending = self.write_behind.lock();
+            let entries = std::mem::take(&mut *pending);
+            for (absolute, entry) in entries {
+                let moved = match absolute.strip_prefix(&self.path) {
+                    Ok(relative) => path.join(relative),
+                    Err(_) => absolute,
+                };
+                pending.insert(moved, entry);
+            }
+        }
+        self.path = path;
+
+        let missing = self
+            .all_paths()
+            .into_iter()
+            .filter(|(_, relative)| fs::symlink_metadata(self.path.join(relative)).is_err())
+            .count();
+
+        self.after_mutation(&[])?;
+
+        Ok(missing)
+    }
+
+    /// Moves a managed item to another directory inside the same database.
+    ///
+    /// # Parameters
+    /// - `id`: source item to move.
+    /// - `to`: destination directory item (or `ItemId::database_id()`).
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` is root or cannot be found,
+    /// - destination is not a directory,
+    /// - source and destination are identical,
+    /// - filesystem move fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("folder"), ItemId::database_id())?;
+    ///     manager.write_new(ItemId::id("a.txt"), ItemId::database_id())?;
+    ///     manager.migrate_item(ItemId::id("a.txt"), ItemId::id("folder"))?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn migrate_item(
+        &mut self,
+        id: impl Into<ItemId>,
+        to: impl Into<ItemId>,
+    ) -> Result<(), DatabaseError> {
+        self.flush()?;
+
+        let id = id.into();
+        let to = to.into();
+        let mut timer = self.time_operation(LatencyOperation::Move, &id);
+
+        if id.get_name().is_empty() {
+            return Err(DatabaseError::RootIdUnsupported);
+        }
+
+        let destination_dir = self.locate_absolute(&to)?;
+        if !destination_dir.is_dir() {
+            return Err(DatabaseError::NotADirectory(destination_dir));
+        }
+
+        let source_absolute = self.locate_absolute(&id)?;
+        timer.set_path(&source_absolute);
+        let source_name = source_absolute
+            .file_name()
+            .ok_or_else(|| DatabaseError::NoMatchingID(id.as_string()))?;
+        let destination_absolute = destination_dir.join(source_name);
+
+        if destination_absolute == source_absolute {
+            return Err(DatabaseError::IdenticalSourceDestination(
+                destination_absolute,
+            ));
+        }
+
+        if destination_absolute.exists() {
+            if destination_absolute.is_dir() {
+                remove_dir_all(&destination_absolute)?;
+            } else {
+                remove_file(&destination_absolute)?;
+            }
+        }
+
+        self.journal_begin(&source_absolute, &destination_absolute)?;
+        fs::rename(&source_absolute, &destination_absolute)?;
+        self.journal_end(&source_absolute, &destination_absolute)?;
+
+        let relative_destination = destination_absolute.strip_prefix(&self.path)?.to_path_buf();
+        let source_name = relative_destination
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or(DatabaseError::OsStringConversion)?
+            .to_string();
+        let migrated_id = ItemId::with_index(source_name, id.get_index());
+        let previous_relative = self.locate_relative(&id)?;
+
+        let record = self.records.get(previous_relative.as_path()).cloned();
+        self.remove_id_from_index(&id)?;
+        self.relocate_index_paths(&previous_relative, &relative_destination);
+        self.relocate_access(&previous_relative, &relative_destination);
+        self.relocate_subtree_configs(&previous_relative, &relative_destination);
+        self.relocate_reservations(&previous_relative, &relative_destination);
+        self.insert_path_for_id(&migrated_id, relative_destination.clone())?;
+        self.restore_record(relative_destination.clone(), record);
+        self.sync_parent_directory(&source_absolute)?;
+        self.sync_parent_directory(&destination_absolute)?;
+        self.emit_renamed(&id, &previous_relative, &migrated_id, &relative_destination);
+        if self.undo_enabled() {
+            self.record_undo(UndoAction::Moved {
+                from: previous_relative.clone().into_path_buf(),
+                to: relative_destination.clone(),
+            })?;
+        }
+
+        self.after_mutation(&[&previous_relative, &relative_destination])?;
+
+        Ok(())
+    }
+
+    /// Exports a managed file or directory to an external destination directory.
+    ///
+    /// `Copy` keeps the item in the `index`. `Move` removes the moved entry from the `index`.
+    /// Returns an **`ExportSummary`** with the final destination path, bytes copied, and
+    /// number of items exported.
+    ///
+    /// # Parameters
+    /// - `id`: source item to export.
+    /// - `to`: external destination directory path.
+    /// - `mode`: copy or move behavior.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` is root or cannot be found,
+    /// - destination is inside the database,
+    /// - destination path cannot be created or used as a directory,
+    /// - filesystem copy/move operations fail.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ExportMode, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("a.txt"), ItemId::database_id())?;
+    ///     let summary = manager.export_item(ItemId::id("a.txt"), "./exports", ExportMode::Copy)?;
+    ///     println!("Exported to {}", summary.get_destination().display());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn export_item(
+        &mut self,
+        id: impl Into<ItemId>,
+        to: impl AsRef<Path>,
+        mode: ExportMode,
+    ) -> Result<ExportSummary, DatabaseError> {
+        self.flush()?;
+
+        let id = id.into();
+        let mut timer = self.time_operation(LatencyOperation::Export, &id);
+        let destination_dir = {
+            let to = to.as_ref();
+            if to.is_absolute() {
+                to.to_path_buf()
+            } else {
+                current_dir()?.join(to)
+            }
+        };
+
+        if id.get_name().is_empty() {
+            return Err(DatabaseError::RootIdUnsupported);
+        }
+
+        if destination_dir.starts_with(&self.path) {
+            return Err(DatabaseError::ExportDestinationInsideDatabase(
+                destination_dir,
+            ));
+        }
+
+        fs::create_dir_all(&destination_dir)?;
+        if !destination_dir.is_dir() {
+            return Err(DatabaseError::NotADirectory(destination_dir));
+        }
+
+        let source_absolute = self.locate_absolute(&id)?;
+        timer.set_path(&source_absolute);
+        let source_name = source_absolute
+            .file_name()
+            .ok_or_else(|| DatabaseError::NoMatchingID(id.as_string()))?;
+        let destination_absolute = destination_dir.join(source_name);
+
+        let mut tracker = self.start_progress(ProgressOperation::Export, &[&source_absolute]);
+        let summary = self.export_to_path(
+            &id,
+            &source_absolute,
+            destination_absolute,
+            &mode,
+            &mut tracker,
+        )?;
+        tracker.finish();
+
+        Ok(summary)
+    }
+
+    /// Imports an external file or directory into a database destination directory.
+    ///
+    /// The imported item keeps its original `name`. Returns the **`ItemId`** it was
+    /// registered under, including its resolved index.
+    ///
+    /// Atomic overwrites of a linked item replace the link with a regular file and leave the
+    /// original source untouched.
+    ///
+    /// # Parameters
+    /// - `from`: source path outside the database.
+    /// - `to`: destination directory item in the database.
+    /// - `mode`: how the source is brought in (**`ImportMode`**).
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - source path points inside the database,
+    /// - destination is not a directory,
+    /// - destination `path`/`name` already exists,
+    /// - source does not exist as file or directory,
+    /// - filesystem copy, move, or link operations fail,
+    /// - `ImportMode::Symlink` is used on a platform without symbolic links.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ImportMode, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("imports"), ItemId::database_id())?;
+    ///     let id = manager.import_item(
+    ///         "./outside/example.txt",
+    ///         ItemId::id("imports"),
+    ///         ImportMode::Hardlink,
+    ///     )?;
+    ///     let _data = manager.read_existing(id)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn import_item(
+        &mut self,
+        from: impl AsRef<Path>,
+        to: impl Into<ItemId>,
+        mode: ImportMode,
+    ) -> Result<ItemId, DatabaseError> {
+        let source_path = {
+            let from = from.as_ref();
+            if from.is_absolute() {
+                from.to_path_buf()
+            } else {
+                current_dir()?.join(from)
+            }
+        };
+        let to = to.into();
+
+        if source_path.starts_with(&self.path) {
+            return Err(DatabaseError::ImportSourceInsideDatabase(source_path));
+        }
+
+        let destination_parent = self.locate_absolute(&to)?;
+        if !destination_parent.is_dir() {
+            return Err(DatabaseError::NotADirectory(destination_parent));
+        }
+
+        let item_name = source_path
+            .file_name()
+            .ok_or_else(|| DatabaseError::NotAFile(source_path.clone()))?
+            .to_string_lossy()
+            .to_string();
+        let item_name = self.index_key(&item_name).into_owned();
+
+        let destination_absolute = destination_parent.join(&item_name);
+        let destination_relative = self.locate_relative(&to)?.join(&item_name);
+
+        if destination_absolute.exists()
+            || self.path_exists_in_index(&destination_relative)
+        {
+            return Err(DatabaseError::IdAlreadyExists(item_name));
+        }
+
+        if !source_path.is_dir() && !source_path.is_file() {
+            return Err(DatabaseError::NoMatchingID(
+                source_path.display().to_string(),
+            ));
+        }
+        if mode != ImportMode::Symlink && (self.has_quotas() || self.free_space_check) {
+            let (bytes, _) = tree_totals(&source_path)?;
+            self.check_quotas(&destination_relative, bytes, 0)?;
+            if mode != ImportMode::Hardlink {
+                self.preflight_free_space(&destination_parent, bytes)?;
+            }
+        }
+
+        let mut tracker = self.start_progress(ProgressOperation::Import, &[&source_path]);
+        match mode {
+            ImportMode::Copy => {
+                self.copy_tree(&source_path, &destination_absolute, &mut tracker)?
+            }
+            ImportMode::Move => {
+                self.move_path(&source_path, &destination_absolute, &mut tracker)?
+            }
+            ImportMode::Hardlink => {
+                if source_path.is_dir() {
+                    self.hard_link_directory_recursive(&source_path, &destination_absolute)?;
+                } else {
+                    fs::hard_link(&source_path, &destination_absolute)?;
+                }
+            }
+            ImportMode::Symlink => create_symlink(&source_path, &destination_absolute)?,
+        }
+        tracker.finish();
+
+        let id = self.insert_generated_path(item_name, destination_relative.clone());
+        if let Some(record) = self.records.get_mut(&destination_relative) {
+            record.import_mode = Some(mode);
+        }
+        self.sync_parent_directory(&destination_absolute)?;
+        self.emit_created(&id, &destination_relative);
+
+        self.after_mutation(&[&destination_relative])?;
+
+        Ok(id)
+    }
+
+    /// Copies or moves an item (file or whole subtree) from this database into `other`.
+    ///
+    /// The item keeps its `name`. It and every descendant are added to the `index` of `other`,
+    /// and the **`ItemId`** of the top-level item in `other` is returned. With
+    /// `ExportMode::Move`, the item and its descendants are also removed from this database.
+    ///
+    /// # Parameters
+    /// - `other`: destination database manager.
+    /// - `id`: item in this database to transfer.
+    /// - `parent`: destination directory item in `other` (or `ItemId::database_id()`).
+    /// - `mode`: copy or move behavior.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` is root or cannot be found,
+    /// - `parent` cannot be found in `other` or is not a directory,
+    /// - an item with the same `name` already exists under `parent`,
+    /// - filesystem copy, move, or delete operations fail.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ExportMode, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut source = DatabaseManager::create_database(".", "source")?;
+    ///     let mut archive = DatabaseManager::create_database(".", "archive")?;
+    ///     source.write_new(ItemId::id("report.txt"), ItemId::database_id())?;
+    ///     let id = source.copy_to(
+    ///         &mut archive,
+    ///         ItemId::id("report.txt"),
+    ///         ItemId::database_id(),
+    ///         ExportMode::Move,
+    ///     )?;
+    ///     let _data = archive.read_existing(id)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn copy_to(
+        &mut self,
+        other: &mut DatabaseManager,
+        id: impl Into<ItemId>,
+        parent: impl Into<ItemId>,
+        mode: ExportMode,
+    ) -> Result<ItemId, DatabaseError> {
+        self.flush()?;
+
+        let id = id.into();
+        let parent = parent.into();
+        let mut timer = self.time_operation(LatencyOperation::Transfer, &id);
+
+        if id.get_name().is_empty() {
+            return Err(DatabaseError::RootIdUnsupported);
+        }
+
+        let source_absolute = self.locate_absolute(&id)?;
+        timer.set_path(&source_absolute);
+        let source_relative = self.locate_relative(&id)?.into_path_buf();
+        let parent_absolute = other.locate_absolute(&parent)?;
+        if !parent_absolute.is_dir() {
+            return Err(DatabaseError::NotADirectory(parent_absolute));
+        }
+
+        let item_name = os_str_to_string(source_absolute.file_name())?;
+        let item_name = other.index_key(&item_name).into_owned();
+        let destination_absolute = parent_absolute.join(&item_name);
+        let destination_relative = other.locate_relative(&parent)?.join(&item_name);
+
+        if source_absolute == destination_absolute {
+            return Err(DatabaseError::IdenticalSourceDestination(source_absolute));
+        }
+        if destination_absolute.exists() || other.path_exists_in_index(&destination_relative) {
+            return Err(DatabaseError::IdAlreadyExists(item_name));
+        }
+
+        let mut tracker = self.start_progress(ProgressOperation::Transfer, &[&source_absolute]);
+        if mode == ExportMode::Move {
+            self.journal_begin(&source_absolute, &destination_absolute)?;
+        }
+        let moved =
+            mode == ExportMode::Move && fs::rename(&source_absolute, &destination_absolute).is_ok();
+        if !moved {
+            if source_absolute.is_dir() {
+                fs::create_dir_all(&destination_absolute)?;
+                tracker.advance(&source_absolute, 0);
+                self.duplicate_directory_recursive(
+                    &source_absolute,
+                    &destination_absolute,
+                    Path::new(""),
+                    &mut DuplicateOptions::new(),
+                    &mut Vec::new(),
+                    &mut tracker,
+                )?;
+            } else {
+                let bytes = fs::copy(&source_absolute, &destination_absolute)?;
+                tracker.advance(&source_absolute, bytes);
+            }
+        }
+        tracker.finish();
+
+        let new_id = other.insert_generated_path(item_name, destination_relative.clone());
+        if destination_absolute.is_dir() {
+            for path in other.collect_paths_in_scope(&destination_absolute, true)? {
+                let name = os_str_to_string(path.file_name())?;
+                other.insert_generated_path(name, path);
+            }
+        }
+        other.sync_parent_directory(&destination_absolute)?;
+        other.emit_created(&new_id, &destination_relative);
+        other.after_mutation(&[&destination_relative])?;
+
+        if mode == ExportMode::Move {
+            if !moved {
+                self.journal_mark_copied(&source_absolute, &destination_absolute)?;
+                if source_absolute.is_dir() {
+                    remove_dir_all(&source_absolute)?;
+                } else {
+                    remove_file(&source_absolute)?;
+                }
+            }
+            self.journal_end(&source_absolute, &destination_absolute)?;
+            self.forget_subtree(&id, &source_relative)?;
+            self.sync_parent_directory(&source_absolute)?;
+            self.emit_deleted(&id, &source_relative);
+            self.after_mutation(&[&source_relative])?;
+        }
+
+        Ok(new_id)
+    }
+
+    /// Duplicates a managed item into `parent` using a caller-provided `name`.
+    ///
+    /// Duplicating a directory copies its whole subtree and adds every copied descendant to the
+    /// `index`. Use `duplicate_item_with` for progress, cancellation, and exclude filters.
+    ///
+    /// # Parameters
+    /// - `id`: source item to duplicate.
+    /// - `parent`: destination parent directory item (or `ItemId::database_id()`).
+    /// - `name`: new name for the duplicate.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` is root or cannot be found,
+    /// - destination parent is not a directory,
+    /// - destination `name` already exists in the target directory,
+    /// - filesystem copy fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("a.txt"), ItemId::database_id())?;
+    ///     manager.duplicate_item(ItemId::id("a.txt"), ItemId::database_id(), "copy.txt")?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn duplicate_item(
+        &mut self,
+        id: impl Into<ItemId>,
+        parent: impl Into<ItemId>,
+        name: impl AsRef<str>,
+    ) -> Result<(), DatabaseError> {
+        self.duplicate_item_with(id, parent, name, DuplicateOptions::new())
+    }
+
+    /// Same as `duplicate_item`, with progress, cancellation, and exclude options.
+    ///
+    /// # Parameters
+    /// - `id`: source item to duplicate.
+    /// - `parent`: destination parent directory item (or `ItemId::database_id()`).
+    /// - `name`: new name for the duplicate.
+    /// - `options`: **`DuplicateOptions`** for the copy.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - any `duplicate_item` error occurs,
+    /// - the cancel flag is set (`DatabaseError::Cancelled`); the partial copy is removed.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, DuplicateOptions, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("project"), ItemId::database_id())?;
+    ///     manager.duplicate_item_with(
+    ///         ItemId::id("project"),
+    ///         ItemId::database_id(),
+    ///         "project-copy",
+    ///         DuplicateOptions::new().with_exclude(|path| path.ends_with(".git")),
+    ///     )?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn duplicate_item_with(
+        &mut self,
+        id: impl Into<ItemId>,
+        parent: impl Into<ItemId>,
+        name: impl AsRef<str>,
+        mut options: DuplicateOptions<'_>,
+    ) -> Result<(), DatabaseError> {
+        let id = id.into();
+        let parent = parent.into();
+        let name = self.index_key(name.as_ref()).into_owned();
+
+        if id.get_name().is_empty() {
+            return Err(DatabaseError::RootIdUnsupported);
+        }
+        validate_item_name(&name)?;
+
+        let source_absolute = self.locate_absolute(&id)?;
+        let parent_absolute = self.locate_absolute(&parent)?;
+        if !parent_absolute.is_dir() {
+            return Err(DatabaseError::NotADirectory(parent_absolute));
+        }
+
+        let destination_absolute = parent_absolute.join(&name);
+        let destination_relative = self.locate_relative(&parent)?.join(&name);
+
+        if destination_absolute.exists()
+            || self.path_exists_in_index(&destination_relative)
+        {
+            return Err(DatabaseError::IdAlreadyExists(name));
+        }
+        if self.has_quotas() || self.free_space_check {
+            let (bytes, _) = tree_totals(&source_absolute)?;
+            self.check_quotas(&destination_relative, bytes, 0)?;
+            self.preflight_free_space(&parent_absolute, bytes)?;
+        }
+
+        let mut copied = Vec::new();
+        let mut tracker = self.start_progress(ProgressOperation::Duplicate, &[&source_absolute]);
+        let result = if source_absolute.is_dir() {
+            fs::create_dir_all(&destination_absolute)
+                .map_err(DatabaseError::from)
+                .and_then(|_| {
+                    tracker.advance(&source_absolute, 0);
+                    self.duplicate_directory_recursive(
+                        &source_absolute,
+                        &destination_absolute,
+                        Path::new(""),
+                        &mut options,
+                        &mut copied,
+                        &mut tracker,
+                    )
+                })
+        } else if options.is_cancelled() {
+            Err(DatabaseError::Cancelled(id.as_string()))
+        } else {
+            fs::copy(&source_absolute, &destination_absolute)
+                .map(|bytes| {
+                    tracker.advance(&source_absolute, bytes);
+                    if let Some(progress) = options.progress.as_mut() {
+                        progress(1, bytes);
+                    }
+                })
+                .map_err(DatabaseError::from)
+        };
+
+        if let Err(error) = result {
+            if destination_absolute.is_dir() {
+                let _ = remove_dir_all(&destination_absolute);
+            } else if destination_absolute.is_file() {
+                let _ = remove_file(&destination_absolute);
+            }
+            return Err(match error {
+                DatabaseError::Cancelled(_) => DatabaseError::Cancelled(id.as_string()),
+                error => error,
+            });
+        }
+
+        let duplicate_name = destination_relative
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let duplicate_id = self.insert_generated_path(duplicate_name, destination_relative.clone());
+
+        copied.sort();
+        for relative in copied {
+            let name = os_str_to_string(relative.file_name())?;
+            self.insert_generated_path(name, destination_relative.join(relative));
+        }
+
+        tracker.finish();
+        self.sync_parent_directory(&destination_absolute)?;
+        self.emit_created(&duplicate_id, &destination_relative);
+        self.after_mutation(&[&destination_relative])?;
+
+        Ok(())
+    }
+
+    /// Sets the last accessed and last modified times of a file or directory to now.
+    ///
+    /// Contents are not changed.
+    ///
+    /// # Parameters
+    /// - `id`: target **`ItemId`** (or `ItemId::database_id()`).
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` cannot be found,
+    /// - opening the item or updating its times fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("a.txt"), ItemId::database_id())?;
+    ///     manager.touch(ItemId::id("a.txt"))?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn touch(&self, id: impl Into<ItemId>) -> Result<(), DatabaseError> {
+        let now = SystemTime::now();
+        self.set_times(id, Some(now), Some(now))
+    }
+
+    /// Sets the last accessed and/or last modified times of a file or directory.
+    ///
+    /// A `None` time is left unchanged. The new times show up in `get_file_information`.
+    ///
+    /// # Parameters
+    /// - `id`: target **`ItemId`** (or `ItemId::database_id()`).
+    /// - `accessed`: new last accessed time.
+    /// - `modified`: new last modified time.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` cannot be found,
+    /// - opening the item or updating its times fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use std::time::{Duration, SystemTime};
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("a.txt"), ItemId::database_id())?;
+    ///     let last_week = SystemTime::now() - Duration::from_secs(7 * 24 * 3600);
+    ///     manager.set_times(ItemId::id("a.txt"), None, Some(last_week))?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_times(
+        &self,
+        id: impl Into<ItemId>,
+        accessed: Option<SystemTime>,
+        modified: Option<SystemTime>,
+    ) -> Result<(), DatabaseError> {
+        let path = self.locate_absolute(id)?;
+        self.flush_pending_writes(|pending, _| pending == path)?;
+
+        let mut times = fs::FileTimes::new();
+        if let Some(accessed) = accessed {
+            times = times.set_accessed(accessed);
+        }
+        if let Some(modified) = modified {
+            times = times.set_modified(modified);
+        }
+
+        File::open(&path)?.set_times(times)?;
+        self.after_mutation_at(&path)?;
+
+        Ok(())
+    }
+
+    /// Returns filesystem metadata summary for a managed file or directory.
+    ///
+    /// Includes:
+    /// - `name`/`extension`,
+    /// - normalized size,
+    /// - Unix timestamps and "time since" timestamps where available.
+    ///
+    /// # Parameters
+    /// - `id`: item to inspect.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` cannot be found,
+    /// - metadata lookup fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("a.txt"), ItemId::database_id())?;
+    ///     let _info = manager.get_file_information(ItemId::id("a.txt"))?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn get_file_information(
+        &self,
+        id: impl Into<ItemId>,
+    ) -> Result<FileInformation, DatabaseError> {
+        let id = id.into();
+
+        let path = self.locate_absolute(id)?;
+
+        let metadata = fs::metadata(&path)?;
+
+        Ok(file_information(&path, Some(&metadata)))
+    }
+
+    /// Returns filesystem metadata for a managed item, falling back to what the `index` knows
+    /// when the metadata lookup fails.
+    ///
+    /// On success this matches `get_file_information` and the error is `None`. When the lookup
+    /// fails, for example on a flaky network mount, the returned **`FileInformation`** still has
+    /// `name` and `extension` taken from the tracked path (an item with an extension is treated
+    /// as a file), its size is zero, every timestamp is `None`, and the lookup error is returned
+    /// next to it.
+    ///
+    /// # Parameters
+    /// - `id`: item to inspect.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` cannot be found.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let manager = DatabaseManager::create_database(".", "database")?;
+    ///     for id in manager.get_all(true) {
+    ///         let (info, error) = manager.get_file_information_lenient(&id)?;
+    ///         if let Some(error) = error {
+    ///             eprintln!("{:?}: metadata unavailable ({error})", info.get_name());
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn get_file_information_lenient(
+        &self,
+        id: impl Into<ItemId>,
+    ) -> Result<(FileInformation, Option<DatabaseError>), DatabaseError> {
+        let path = self.locate_absolute(id)?;
+
+        Ok(match fs::metadata(&path) {
+            Ok(metadata) => (file_information(&path, Some(&metadata)), None),
+            Err(error) => (file_information(&path, None), Some(error.into())),
+        })
+    }
+
+    /// Returns an iterator over the tracked items whose metadata matches `predicate`, in path
+    /// order.
+    ///
+    /// Metadata is read lazily: each item is looked up only when the iterator reaches it, and
+    /// stopping early skips the rest. An item whose metadata lookup fails yields the error in
+    /// its place, and iteration can continue past it.
+    ///
+    /// # Parameters
+    /// - `predicate`: called with each item's **`ItemId`** and **`FileInformation`**; return
+    ///   `true` to yield it.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, FileSizeUnit};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let manager = DatabaseManager::create_database(".", "database")?;
+    ///     let ninety_days = 90 * 24 * 60 * 60;
+    ///     let stale_and_large = manager.find_where(|_id, info| {
+    ///         info.get_size().as_unit(FileSizeUnit::Megabyte).get_size() > 100
+    ///             && info
+    ///                 .get_time_since_last_modified()
+    ///                 .is_some_and(|seconds| *seconds > ninety_days)
+    ///     });
+    ///     for id in stale_and_large {
+    ///         println!("{:?}", id?);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn find_where<F>(
+        &self,
+        mut predicate: F,
+    ) -> impl Iterator<Item = Result<ItemId, DatabaseError>>
+    where
+        F: FnMut(&ItemId, &FileInformation) -> bool,
+    {
+        let mut paths: Vec<(ItemId, PathBuf)> = self
+            .all_paths()
+            .into_iter()
+            .map(|(id, path)| (id, path.clone()))
+            .collect();
+        paths.sort_by(|(a_id, a_path), (b_id, b_path)| {
+            a_path.cmp(b_path).then_with(|| a_id.cmp(b_id))
+        });
+
+        paths.into_iter().filter_map(move |(id, relative)| {
+            let absolute = self.path.join(relative);
+            match fs::metadata(&absolute) {
+                Ok(metadata) => {
+                    predicate(&id, &file_information(&absolute, Some(&metadata))).then_some(Ok(id))
+                }
+                Err(error) => Some(Err(error.into())),
+            }
+        })
+    }
+
+    /// Returns the tracked files below `scope` that have not been read or modified for at least
+    /// `older_than`, in path order.
+    ///
+    /// A file's last use is the later of its accessed and modified times. Many filesystems
+    /// update access times lazily or not at all (`relatime`, `noatime`), in which case this
+    /// falls back to the modified time. Files with neither time, and files missing on disk, are
+    /// not reported. Directories are left out, since their times do not change when the files
+    /// inside them do.
+    ///
+    /// # Parameters
+    /// - `older_than`: how long a file must have gone unused.
+    /// - `scope`: directory to search (or `ItemId::database_id()` for the whole database).
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `scope` cannot be found,
+    /// - `scope` points to a file instead of a directory.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ForceDeletion, ItemId};
+    /// use std::time::Duration;
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     let thirty_days = Duration::from_secs(30 * 24 * 60 * 60);
+    ///     for id in manager.stale_items(thirty_days, ItemId::database_id())? {
+    ///         manager.delete(id, ForceDeletion::NoForce)?;
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn stale_items(
+        &self,
+        older_than: Duration,
+        scope: impl Into<ItemId>,
+    ) -> Result<Vec<ItemId>, DatabaseError> {
+        let scope_path = self.directory_relative_path(&scope.into())?;
+        let Some(cutoff) = SystemTime::now().checked_sub(older_than) else {
+            return Ok(Vec::new());
+        };
+
+        let stale = self
+            .descendant_paths(&scope_path)
+            .into_iter()
+            .filter(|(_, path)| {
+                let Ok(metadata) = fs::metadata(self.path.join(path)) else {
+                    return false;
+                };
+                let last_used = metadata.accessed().ok().max(metadata.modified().ok());
+                !metadata.is_dir() && last_used.is_some_and(|last_used| last_used <= cutoff)
+            })
+            .collect();
+
+        Ok(sort_listing(stale, SortBy::Path, &self.path))
+    }
+
+    /// Deletes the coldest tracked files until the tracked files fit in `limit` bytes.
+    ///
+    /// Only regular files are counted and evicted; directories emptied along the way are kept.
+    /// Files are ordered by `policy`, then by path. If the files already fit, nothing is
+    /// deleted. A failed delete stops the run; files deleted before it stay deleted.
+    ///
+    /// # Parameters
+    /// - `limit`: size budget in bytes for all tracked files.
+    /// - `policy`: **`EvictionPolicy`** deciding which files go first.
+    ///
+    /// # Errors
+    /// Returns an error if deleting a file fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, EvictionPolicy};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "cache")?;
+    ///     let report =
+    ///         manager.evict_until_under(500_000_000, EvictionPolicy::LeastRecentlyAccessed)?;
+    ///     println!("evicted {} files", report.get_evicted().len());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn evict_until_under(
+        &mut self,
+        limit: u64,
+        policy: EvictionPolicy,
+    ) -> Result<EvictionReport, DatabaseError> {
+        self.evict(limit, policy, false)
+    }
+
+    /// Moves the coldest tracked files to the trash until the tracked files fit in `limit`
+    /// bytes.
+    ///
+    /// Works like `evict_until_under`, but each file goes through `delete_to_trash`. Trashed
+    /// files no longer count toward `limit`, but their space is only freed by `empty_trash` or
+    /// `gc`.
+    ///
+    /// # Parameters
+    /// - `limit`: size budget in bytes for all tracked files.
+    /// - `policy`: **`EvictionPolicy`** deciding which files go first.
+    ///
+    /// # Errors
+    /// Returns an error if moving a file to the trash fails.
+    pub fn trash_until_under(
+        &mut self,
+        limit: u64,
+        policy: EvictionPolicy,
+    ) -> Result<EvictionReport, DatabaseError> {
+        self.evict(limit, policy, true)
+    }
+
+    /// Shared implementation of `evict_until_under` and `trash_until_under`.
+    fn evict(
+        &mut self,
+        limit: u64,
+        policy: EvictionPolicy,
+        to_trash: bool,
+    ) -> Result<EvictionReport, DatabaseError> {
+        let mut candidates: Vec<(Option<SystemTime>, PathBuf, ItemId, u64)> = self
+            .all_paths()
+            .into_iter()
+            .filter_map(|(id, path)| {
+                let metadata = fs::symlink_metadata(self.path.join(path)).ok()?;
+                if !metadata.is_file() {
+                    return None;
+                }
+                let last_used = match policy {
+                    EvictionPolicy::LeastRecentlyAccessed => {
+                        metadata.accessed().ok().max(metadata.modified().ok())
+                    }
+                    EvictionPolicy::LeastRecentlyModified => metadata.modified().ok(),
+                };
+                Some((last_used, path.clone(), id, metadata.len()))
+            })
+            .collect();
+
+        // Files without a usable time go last.
+        candidates.sort_by(|a, b| {
+            a.0.is_none()
+                .cmp(&b.0.is_none())
+                .then_with(|| a.0.cmp(&b.0))
+                .then_with(|| a.1.cmp(&b.1))
+                .then_with(|| a.2.cmp(&b.2))
+        });
+
+        let mut size: u64 = candidates.iter().map(|candidate| candidate.3).sum();
+        let mut evicted = Vec::new();
+        let mut bytes_freed = 0;
+        for (_, _, id, bytes) in candidates {
+            if size <= limit {
+                break;
+            }
+            if to_trash {
+                self.delete_to_trash(id.clone())?;
+            } else {
+                self.delete(id.clone(), ForceDeletion::NoForce)?;
+            }
+            size -= bytes;
+            bytes_freed += bytes;
+            evicted.push(id);
+        }
+
+        Ok(EvictionReport {
+            evicted,
+            bytes_freed,
+            size,
+        })
+    }
+
+    /// Runs a line-based three-way merge of tracked text files and writes the result to `id_out`.
+    ///
+    /// Changes made on only one side are taken as-is. When both sides change the same region
+    /// differently, the region is written with conflict markers labelled with the `ours` and
+    /// `theirs` names, and counted in the returned **`MergeReport`**.
+    ///
+    /// # Parameters
+    /// - `base`: common ancestor file **`ItemId`**.
+    /// - `ours`: first changed file **`ItemId`**.
+    /// - `theirs`: second changed file **`ItemId`**.
+    /// - `id_out`: existing file **`ItemId`** that receives the merged text.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - any input or `id_out` cannot be found or is not a file,
+    /// - an input is not valid UTF-8 text,
+    /// - overwriting `id_out` fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     for name in ["base.txt", "ours.txt", "theirs.txt", "merged.txt"] {
+    ///         manager.write_new(ItemId::id(name), ItemId::database_id())?;
+    ///     }
+    ///     manager.overwrite_existing(ItemId::id("base.txt"), "a\nb\nc\n")?;
+    ///     manager.overwrite_existing(ItemId::id("ours.txt"), "A\nb\nc\n")?;
+    ///     manager.overwrite_existing(ItemId::id("theirs.txt"), "a\nb\nC\n")?;
+    ///
+    ///     let report = manager.merge_text(
+    ///         ItemId::id("base.txt"),
+    ///         ItemId::id("ours.txt"),
+    ///         ItemId::id("theirs.txt"),
+    ///         ItemId::id("merged.txt"),
+    ///     )?;
+    ///     assert!(!report.has_conflicts());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn merge_text(
+        &self,
+        base: impl Into<ItemId>,
+        ours: impl Into<ItemId>,
+        theirs: impl Into<ItemId>,
+        id_out: impl Into<ItemId>,
+    ) -> Result<MergeReport, DatabaseError> {
+        let ours = ours.into();
+        let theirs = theirs.into();
+        let id_out = id_out.into();
+
+        let base_text = self.read_existing_string(base)?;
+        let ours_text = self.read_existing_string(&ours)?;
+        let theirs_text = self.read_existing_string(&theirs)?;
+
+        let (merged, conflict_count) = merge_lines(
+            &base_text,
+            &ours_text,
+            &theirs_text,
+            ours.get_name(),
+            theirs.get_name(),
+        );
+
+        self.overwrite_existing(&id_out, merged)?;
+
+        Ok(MergeReport {
+            output: id_out,
+            conflict_count,
+        })
+    }
+
+    /// Allows `principal` to perform `operations` on an item and everything below it.
+    ///
+    /// Entries are resolved by nearest ancestor, so an entry on a directory applies to its whole
+    /// subtree unless a deeper entry for the same principal replaces it. Calling this again for the
+    /// same item and principal replaces the previous entry.
+    ///
+    /// # Parameters
+    /// - `id`: item or subtree root. Use `ItemId::database_id()` for the whole database.
+    /// - `principal`: identity the entry applies to.
+    /// - `operations`: operations the principal may perform.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` cannot be found.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{AccessOperation, DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("plugin_a"), ItemId::database_id())?;
+    ///     manager.set_access(
+    ///         ItemId::id("plugin_a"),
+    ///         "plugin_a",
+    ///         [AccessOperation::Read, AccessOperation::Write, AccessOperation::Create],
+    ///     )?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_access(
+        &mut self,
+        id: impl Into<ItemId>,
+        principal: impl Into<String>,
+        operations: impl IntoIterator<Item = AccessOperation>,
+    ) -> Result<(), DatabaseError> {
+        let relative_path = self.relative_path_of(&id.into())?;
+
+        self.access
+            .entry(relative_path)
+            .or_default()
+            .insert(principal.into(), operations.into_iter().collect());
+
+        Ok(())
+    }
+
+    /// Removes the access control entry for `principal` on exactly this item.
+    ///
+    /// Entries inherited from ancestors are not affected.
+    ///
+    /// # Parameters
+    /// - `id`: item the entry was attached to.
+    /// - `principal`: identity whose entry should be removed.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` cannot be found.
+    pub fn remove_access(
+        &mut self,
+        id: impl Into<ItemId>,
+        principal: impl AsRef<str>,
+    ) -> Result<(), DatabaseError> {
+        let relative_path = self.relative_path_of(&id.into())?;
+
+        let is_empty = match self.access.get_mut(&relative_path) {
+            Some(entries) => {
+                entries.remove(principal.as_ref());
+                entries.is_empty()
+            }
+            None => false,
+        };
+
+        if is_empty {
+            self.access.remove(&relative_path);
+        }
+
+        Ok(())
+    }
+
+    /// Returns whether `principal` may perform `operation` on an item.
+    ///
+    /// Principals without an entry on the item or any of its ancestors are denied.
+    ///
+    /// # Parameters
+    /// - `id`: item to check.
+    /// - `principal`: identity to check.
+    /// - `operation`: operation to check.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` cannot be found.
+    pub fn is_allowed(
+        &self,
+        id: impl Into<ItemId>,
+        principal: impl AsRef<str>,
+        operation: AccessOperation,
+    ) -> Result<bool, DatabaseError> {
+        let relative_path = self.relative_path_of(&id.into())?;
+
+        Ok(self
+            .resolve_access(&relative_path, principal.as_ref())
+            .is_some_and(|operations| operations.contains(&operation)))
+    }
+
+    /// Returns a **`ScopedManager`** that enforces access control entries for `principal`.
+    ///
+    /// # Parameters
+    /// - `principal`: identity every operation on the returned manager is checked against.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{AccessOperation, DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("plugin_a"), ItemId::database_id())?;
+    ///     manager.set_access(ItemId::id("plugin_a"), "plugin_a", [AccessOperation::Create])?;
+    ///
+    ///     let mut scoped = manager.scoped("plugin_a");
+    ///     scoped.write_new(ItemId::id("state.json"), ItemId::id("plugin_a"))?;
+    ///     assert!(scoped.write_new(ItemId::id("escape.json"), ItemId::database_id()).is_err());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn scoped(&mut self, principal: impl Into<String>) -> ScopedManager<'_> {
+        ScopedManager {
+            manager: self,
+            principal: principal.into(),
+        }
+    }
+
+    /// Returns a **`NamespaceView`** rooted at the top-level directory `name`.
+    ///
+    /// The directory is created and indexed when it does not exist yet. Inside the view,
+    /// `ItemId::database_id()` refers to the namespace directory, and IDs that point outside it
+    /// behave as if they did not exist.
+    ///
+    /// # Parameters
+    /// - `name`: top-level directory name used as the namespace root.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `name` is not a valid item name (`DatabaseError::InvalidItemName`, see `ItemId::try_new`),
+    /// - `name` exists but is not a directory,
+    /// - creating the directory fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     let mut tenant = manager.namespace("tenant_42")?;
+    ///     tenant.write_new(ItemId::id("orders.json"), ItemId::database_id())?;
+    ///     let _relative = tenant.locate_relative(ItemId::id("orders.json"))?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn namespace(&mut self, name: impl AsRef<str>) -> Result<NamespaceView<'_>, DatabaseError> {
+        let name = name.as_ref();
+        validate_item_name(name)?;
+        let root = PathBuf::from(name);
+
+        let absolute = self.path.join(&root);
+        if absolute.exists() && !absolute.is_dir() {
+            return Err(DatabaseError::NotADirectory(absolute));
+        }
+
+        if !absolute.exists() {
+            create_dir(&absolute)?;
+        }
+
+        if !self.path_exists_in_index(&root) {
+            let id = self.insert_generated_path(name.to_string(), root.clone());
+            self.emit_created(&id, &root);
+            self.after_mutation(&[&root])?;
+        }
+
+        Ok(NamespaceView {
+            manager: self,
+            root,
+        })
+    }
+
+    /// Creates a new file or directory under `parent`, or returns the item already there.
+    ///
+    /// This is the idempotent form of `write_new`. When an item with the same name is already
+    /// tracked under `parent` and is the same kind (file or directory) that `write_new` would
+    /// create, its **`ItemId`** is returned instead of `DatabaseError::IdAlreadyExists`.
+    ///
+    /// # Parameters
+    /// - `id`: name key for the item. Root **`ItemId`** is not allowed.
+    /// - `parent`: destination parent item. Use `ItemId::database_id()` for database root.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` is the `ItemId::database_id()`,
+    /// - `parent` cannot be found,
+    /// - the existing item is a different kind than the one requested,
+    /// - filesystem create operations fail.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     let first = manager.write_new_or_existing(ItemId::id("config"), ItemId::database_id())?;
+    ///     let second = manager.write_new_or_existing(ItemId::id("config"), ItemId::database_id())?;
+    ///     assert_eq!(first, second);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn write_new_or_existing(
+        &mut self,
+        id: impl Into<ItemId>,
+        parent: impl Into<ItemId>,
+    ) -> Result<ItemId, DatabaseError> {
+        let id = self.normalize_id(id.into());
+        let parent = parent.into();
+
+        if id.get_name().is_empty() {
+            return Err(DatabaseError::RootIdUnsupported);
+        }
+        validate_item_name(id.get_name())?;
+
+        let relative_path = self.relative_path_of(&parent)?.join(id.get_name());
+
+        if let Some(existing) = self.id_for_path(&relative_path) {
+            let wants_directory = relative_path.extension().is_none();
+            if self.path.join(&relative_path).is_dir() == wants_directory {
+                return Ok(existing);
+            }
+
+            return Err(DatabaseError::IdAlreadyExists(id.as_string()));
+        }
+
+        self.write_new(&id, parent)?;
+        Ok(id)
+    }
+
+    /// Returns owned **`RelativePath`** values for every stored item that shares `name`.
+    ///
+    /// Paths are ordered by stable index, matching `get_ids_by_name`. The result does not
+    /// borrow the manager, so it can be kept across later mutations.
+    ///
+    /// # Parameters
+    /// - `name`: shared `name` key to look up.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("a.txt"), ItemId::database_id())?;
+    ///     let relative = manager.get_paths_for_name("a.txt");
+    ///     let absolute = manager.get_absolute_paths_for_name("a.txt");
+    ///     assert_eq!(relative.len(), absolute.len());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn get_paths_for_name(&self, name: impl AsRef<str>) -> Vec<RelativePath> {
+        self.items
+            .get(self.index_key(name.as_ref()).as_ref())
+            .map(|paths| {
+                paths
+                    .iter()
+                    .map(|(_, path)| RelativePath(path.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns owned absolute paths for every stored item that shares `name`.
+    ///
+    /// Paths are ordered by stable index, matching `get_ids_by_name`.
+    ///
+    /// # Parameters
+    /// - `name`: shared `name` key to look up.
+    pub fn get_absolute_paths_for_name(&self, name: impl AsRef<str>) -> Vec<PathBuf> {
+        self.items
+            .get(self.index_key(name.as_ref()).as_ref())
+            .map(|paths| paths.iter().map(|(_, path)| self.path.join(path)).collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns a **`FileHandle`** for a managed file, checking once that it is a file.
+    ///
+    /// # Parameters
+    /// - `id`: file **`ItemId`**.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` cannot be found,
+    /// - `id` does not point to a file.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("log.txt"), ItemId::database_id())?;
+    ///
+    ///     let file = manager.file(ItemId::id("log.txt"))?;
+    ///     file.write(b"first\n")?;
+    ///     file.append(b"second\n")?;
+    ///     assert_eq!(file.read()?, b"first\nsecond\n");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn file(&self, id: impl Into<ItemId>) -> Result<FileHandle<'_>, DatabaseError> {
+        let id = id.into();
+        let path = self.locate_absolute(&id)?;
+
+        if !path.is_file() {
+            return Err(DatabaseError::NotAFile(path));
+        }
+
+        Ok(FileHandle {
+            manager: self,
+            id,
+            path,
+        })
+    }
+
+    /// Returns a **`DirHandle`** for a managed directory, checking once that it is a directory.
+    ///
+    /// # Parameters
+    /// - `id`: directory **`ItemId`**. Use `ItemId::database_id()` for the database root.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` cannot be found,
+    /// - `id` does not point to a directory.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("folder"), ItemId::database_id())?;
+    ///
+    ///     let mut folder = manager.dir(ItemId::id("folder"))?;
+    ///     folder.create_child(ItemId::id("a.txt"))?;
+    ///     assert_eq!(folder.list(true)?.len(), 1);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn dir(&mut self, id: impl Into<ItemId>) -> Result<DirHandle<'_>, DatabaseError> {
+        let id = id.into();
+        let path = self.locate_absolute(&id)?;
+
+        if !path.is_dir() {
+            return Err(DatabaseError::NotADirectory(path));
+        }
+
+        Ok(DirHandle {
+            manager: self,
+            id,
+            path,
+        })
+    }
+
+    /// Creates a new item like `write_new` and returns a **`TempItem`** guard that deletes it
+    /// again (and removes it from the `index`) when dropped.
+    ///
+    /// # Parameters
+    /// - `id`: name key for the new item. Root **`ItemId`** is not allowed.
+    /// - `parent`: destination parent item. Use `ItemId::database_id()` for database root.
+    ///
+    /// # Errors
+    /// Returns an error if `write_new` fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     {
+    ///         let scratch = manager.write_temp(ItemId::id("scratch.bin"), ItemId::database_id())?;
+    ///         scratch.overwrite_existing(scratch.get_id(), b"intermediate")?;
+    ///     }
+    ///     assert!(manager.locate_absolute(ItemId::id("scratch.bin")).is_err());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn write_temp(
+        &mut self,
+        id: impl Into<ItemId>,
+        parent: impl Into<ItemId>,
+    ) -> Result<TempItem<'_>, DatabaseError> {
+        let created = self.write_new(id, parent)?;
+        Ok(TempItem {
+            manager: self,
+            id: created.id,
+            handle: created.handle,
+            kept: false,
+        })
+    }
+
+    /// Starts a **`Batch`** of operations that is validated as a whole before anything runs.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager
+    ///         .batch()
+    ///         .create_dir("assets")
+    ///         .create_file_in("assets", "readme.txt", b"hello")
+    ///         .rename("assets", "static")
+    ///         .transactional(true)
+    ///         .apply()?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn batch(&mut self) -> Batch<'_> {
+        Batch {
+            manager: self,
+            operations: Vec::new(),
+            transactional: false,
+        }
+    }
+
+    /// Returns the **`ItemId`** tracked at a database-relative path.
+    ///
+    /// # Parameters
+    /// - `path`: path relative to the database root, for example `"config/settings.json"`.
+    ///   An empty path returns `ItemId::database_id()`.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - no tracked item uses `path`.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("config"), ItemId::database_id())?;
+    ///     manager.write_new(ItemId::id("settings.json"), ItemId::id("config"))?;
+    ///     let id = manager.get_id_by_path("config/settings.json")?;
+    ///     assert_eq!(id, ItemId::id("settings.json"));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn get_id_by_path(&self, path: impl AsRef<Path>) -> Result<ItemId, DatabaseError> {
+        let path = path.as_ref();
+        if path.as_os_str().is_empty() {
+            return Ok(ItemId::database_id());
+        }
+
+        self.id_for_path(path)
+            .ok_or_else(|| DatabaseError::NoMatchingID(path.display().to_string()))
+    }
+
+    /// Reads a managed file and deserializes it using `format`.
+    ///
+    /// `FileFormat::Json` and `FileFormat::JsonPretty` both read JSON.
+    ///
+    /// # Parameters
+    /// - `id`: target file **`ItemId`**.
+    /// - `format`: serialization format of the file.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - finding `id` or reading the file fails,
+    /// - deserialization fails.
+    pub fn read_existing_as<T: serde::de::DeserializeOwned>(
+        &self,
+        id: impl Into<ItemId>,
+        format: FileFormat,
+    ) -> Result<T, DatabaseError> {
+        match format {
+            FileFormat::Json | FileFormat::JsonPretty => self.read_existing_json(id),
+            FileFormat::Binary => self.read_existing_binary(id),
+        }
+    }
+
+    /// Serializes `value` using `format` and overwrites the target file.
+    ///
+    /// # Parameters
+    /// - `id`: target file **`ItemId`**.
+    /// - `value`: serializable value.
+    /// - `format`: serialization format to write.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - serialization fails,
+    /// - finding `id` or overwriting the file fails.
+    pub fn overwrite_existing_as<T: serde::Serialize>(
+        &self,
+        id: impl Into<ItemId>,
+        value: &T,
+        format: FileFormat,
+    ) -> Result<(), DatabaseError> {
+        match format {
+            FileFormat::Json => self.overwrite_existing_json(id, value, false),
+            FileFormat::JsonPretty => self.overwrite_existing_json(id, value, true),
+            FileFormat::Binary => self.overwrite_existing_binary(id, value),
+        }
+    }
+
+    /// Appends raw bytes to the end of a managed file.
+    ///
+    /// The file is opened in append mode, so only `data` is written instead of rewriting the
+    /// whole file. This suits log-like files that grow one record at a time. Unlike
+    /// `overwrite_existing`, a crash mid-write can leave a partial record at the end; use
+    /// `append_existing_locked` when several processes append to the same file.
+    ///
+    /// # Parameters
+    /// - `id`: target file **`ItemId`**.
+    /// - `data`: raw bytes to append.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` cannot be found,
+    /// - `id` points to a directory,
+    /// - opening, writing, or syncing the file fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("events.bin"), ItemId::database_id())?;
+    ///     manager.append_existing(ItemId::id("events.bin"), [1_u8, 2, 3])?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn append_existing<T>(&self, id: impl Into<ItemId>, data: T) -> Result<(), DatabaseError>
+    where
+        T: AsRef<[u8]>,
+    {
+        let path = self.locate_absolute(id)?;
+
+        if path.is_dir() {
+            return Err(DatabaseError::NotAFile(path));
+        }
+        self.materialize_external(&path)?;
+        self.flush_pending_writes(|pending, _| pending == path)?;
+
+        let mut file = fs::OpenOptions::new().append(true).open(&path)?;
+        file.write_all(data.as_ref())?;
+        if self.durability != Durability::None {
+            file.sync_data()?;
+        }
+        self.emit_overwritten(&path);
+        self.after_mutation_at(&path)?;
+
+        Ok(())
+    }
+
+    /// Appends `line` followed by a newline to the end of a managed file.
+    ///
+    /// Works like `append_existing`. `line` should not contain its own trailing newline.
+    ///
+    /// # Parameters
+    /// - `id`: target file **`ItemId`**.
+    /// - `line`: text to append.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` cannot be found,
+    /// - `id` points to a directory,
+    /// - opening, writing, or syncing the file fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("app.log"), ItemId::database_id())?;
+    ///     manager.append_existing_line(ItemId::id("app.log"), "started")?;
+    ///     manager.append_existing_line(ItemId::id("app.log"), "ready")?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn append_existing_line(
+        &self,
+        id: impl Into<ItemId>,
+        line: impl AsRef<str>,
+    ) -> Result<(), DatabaseError> {
+        let line = line.as_ref();
+        let mut data = Vec::with_capacity(line.len() + 1);
+        data.extend_from_slice(line.as_bytes());
+        data.push(b'\n');
+        self.append_existing(id, data)
+    }
+
+    /// Shrinks or extends a managed file to `new_len` bytes in place.
+    ///
+    /// Bytes past `new_len` are discarded; extending fills the new space with zeros. Existing
+    /// contents are not rewritten, so this is cheap even for large files.
+    ///
+    /// # Parameters
+    /// - `id`: target file **`ItemId`**.
+    /// - `new_len`: new file length in bytes.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` cannot be found,
+    /// - `id` points to a directory,
+    /// - opening, resizing, or syncing the file fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("disk.img"), ItemId::database_id())?;
+    ///     manager.truncate_existing(ItemId::id("disk.img"), 64 * 1024 * 1024)?;
+    ///     manager.truncate_existing(ItemId::id("disk.img"), 0)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn truncate_existing(
+        &self,
+        id: impl Into<ItemId>,
+        new_len: u64,
+    ) -> Result<(), DatabaseError> {
+        let path = self.locate_absolute(id)?;
+
+        if path.is_dir() {
+            return Err(DatabaseError::NotAFile(path));
+        }
+        self.set_path_len(&path, new_len)
+    }
+
+    /// Appends raw bytes to a managed file while holding an exclusive advisory lock on it.
+    ///
+    /// The lock is held for the whole write, so several processes appending through this method
+    /// never interleave partial writes. Advisory locks only coordinate callers that also lock, so
+    /// plain writers are not blocked.
+    ///
+    /// # Parameters
+    /// - `id`: target file **`ItemId`**.
+    /// - `data`: raw bytes to append.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` cannot be found,
+    /// - `id` points to a directory,
+    /// - opening, locking, writing, or syncing the file fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("shared.log"), ItemId::database_id())?;
+    ///     manager.append_existing_locked(ItemId::id("shared.log"), b"worker 1 started\n")?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn append_existing_locked<T>(
+        &self,
+        id: impl Into<ItemId>,
+        data: T,
+    ) -> Result<(), DatabaseError>
+    where
+        T: AsRef<[u8]>,
+    {
+        let path = self.locate_absolute(id)?;
+
+        if path.is_dir() {
+            return Err(DatabaseError::NotAFile(path));
+        }
+        self.materialize_external(&path)?;
+        self.flush_pending_writes(|pending, _| pending == path)?;
+
+        let mut file = fs::OpenOptions::new().append(true).open(&path)?;
+        file.lock()?;
+
+        let result = file
+            .write_all(data.as_ref())
+            .and_then(|_| match self.durability {
+                Durability::None => Ok(()),
+                Durability::File | Durability::FileAndDirectory => file.sync_data(),
+            });
+        let unlocked = file.unlock();
+
+        result?;
+        unlocked?;
+        self.emit_overwritten(&path);
+        self.after_mutation_at(&path)?;
+
+        Ok(())
+    }
+
+    /// Returns an immutable **`Snapshot`** of the current index.
+    ///
+    /// The snapshot keeps its own copy of every tracked `(ItemId, path)` pair, so listings stay
+    /// consistent while this manager keeps changing. Reads still go to the live files; use
+    /// `freeze_with_contents` when file contents must be frozen as well.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("a.txt"), ItemId::database_id())?;
+    ///     let snapshot = manager.freeze();
+    ///     manager.write_new(ItemId::id("b.txt"), ItemId::database_id())?;
+    ///     assert_eq!(snapshot.get_all(true).len(), 1);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn freeze(&self) -> Snapshot {
+        Snapshot {
+            root: self.path.clone(),
+            items: self.items.clone(),
+            contents: None,
+            normalize_extensions: self.normalize_extensions,
+        }
+    }
+
+    /// Returns a **`Snapshot`** of the index and of every tracked file's contents.
+    ///
+    /// Files are hard-linked into `staging_dir` (or copied when hard links are not possible,
+    /// such as across filesystems). Because overwrites replace files instead of editing them in
+    /// place, the linked copies keep their contents while writers continue. In-place appends do
+    /// show through hard links. The captured tree is removed when the snapshot is dropped.
+    ///
+    /// # Parameters
+    /// - `staging_dir`: directory outside the database to capture contents into. It is created
+    ///   if missing and must be empty.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `staging_dir` is inside the database,
+    /// - `staging_dir` exists and is not an empty directory,
+    /// - creating directories, links, or copies fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("a.txt"), ItemId::database_id())?;
+    ///     manager.overwrite_existing(ItemId::id("a.txt"), b"before")?;
+    ///
+    ///     let snapshot = manager.freeze_with_contents("./snapshot_staging")?;
+    ///     manager.overwrite_existing(ItemId::id("a.txt"), b"after")?;
+    ///     assert_eq!(snapshot.read_existing(ItemId::id("a.txt"))?, b"before");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn freeze_with_contents(
+        &self,
+        staging_dir: impl AsRef<Path>,
+    ) -> Result<Snapshot, DatabaseError> {
+        let staging_dir = {
+            let staging_dir = staging_dir.as_ref();
+            if staging_dir.is_absolute() {
+                staging_dir.to_path_buf()
+            } else {
+                current_dir()?.join(staging_dir)
+            }
+        };
+
+        if staging_dir.starts_with(&self.path) {
+            return Err(DatabaseError::ExportDestinationInsideDatabase(staging_dir));
+        }
+
+        fs::create_dir_all(&staging_dir)?;
+        if fs::read_dir(&staging_dir)?.next().is_some() {
+            return Err(DatabaseError::IdAlreadyExists(
+                staging_dir.display().to_string(),
+            ));
+        }
+
+        let mut paths: Vec<&PathBuf> = self.all_paths().into_iter().map(|(_, path)| path).collect();
+        paths.sort_by_key(|path| path.components().count());
+        let mut tracker =
+            self.start_progress_with_totals(ProgressOperation::Snapshot, Some(paths.len()), None);
+
+        for relative_path in paths {
+            let source = self.path.join(relative_path);
+            let destination = staging_dir.join(relative_path);
+
+            let mut bytes = 0;
+            if source.is_dir() {
+                fs::create_dir_all(&destination)?;
+            } else if source.is_file() {
+                if let Some(parent) = destination.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                if fs::hard_link(&source, &destination).is_err() {
+                    fs::copy(&source, &destination)?;
+                }
+                bytes = fs::metadata(&destination)?.len();
+            }
+            tracker.advance(relative_path, bytes);
+        }
+
+        Ok(Snapshot {
+            root: self.path.clone(),
+            items: self.items.clone(),
+            contents: Some(staging_dir),
+            normalize_extensions: self.normalize_extensions,
+        })
+    }
+
+    /// Registers a callback that fires when the database size crosses a usage threshold.
+    ///
+    /// Usage is checked after mutating operations, at most once per
+    /// `set_usage_watchdog_interval` (one second by default), because each check walks the
+    /// whole database. The callback runs once each time usage rises past a threshold it was
+    /// below at the previous check; dropping back below a threshold re-arms it. Replaces any
+    /// previously registered watchdog.
+    ///
+    /// # Parameters
+    /// - `limit_bytes`: size the thresholds are measured against, such as a quota.
+    /// - `thresholds`: fractions of `limit_bytes`, for example `[0.8, 0.95]`.
+    /// - `callback`: called with a **`UsageAlert`** for the highest newly crossed threshold.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.set_usage_watchdog(10_000_000, [0.8, 0.95], |alert| {
+    ///         eprintln!("database is above {}% of its budget", alert.get_threshold() * 100.0);
+    ///     });
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_usage_watchdog<F>(
+        &mut self,
+        limit_bytes: u64,
+        thresholds: impl IntoIterator<Item = f64>,
+        callback: F,
+    ) where
+        F: Fn(&UsageAlert) + Send + Sync + 'static,
+    {
+        self.usage_watchdog = Some(UsageWatchdog::new(
+            UsageLimit::Bytes(limit_bytes),
+            thresholds,
+            callback,
+        ));
+    }
+
+    /// Registers a callback that fires when the database fills a share of the disk space it
+    /// could grow into.
+    ///
+    /// Works like `set_usage_watchdog`, but each threshold is a fraction of the database size
+    /// plus the free space left on its filesystem, so `0.95` fires when only 5% of that space is
+    /// still free. The callback never fires on platforms where `get_free_space` is unsupported.
+    /// Replaces any previously registered watchdog.
+    ///
+    /// # Parameters
+    /// - `thresholds`: fractions of the space available to the database, for example `[0.8, 0.95]`.
+    /// - `callback`: called with a **`UsageAlert`** for the highest newly crossed threshold.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.set_disk_usage_watchdog([0.8, 0.95], |alert| {
+    ///         eprintln!("{} bytes left on disk", alert.get_limit_bytes() - alert.get_used_bytes());
+    ///     });
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_disk_usage_watchdog<F>(
+        &mut self,
+        thresholds: impl IntoIterator<Item = f64>,
+        callback: F,
+    ) where
+        F: Fn(&UsageAlert) + Send + Sync + 'static,
+    {
+        self.usage_watchdog = Some(UsageWatchdog::new(UsageLimit::Disk, thresholds, callback));
+    }
+
+    /// Removes the callback registered with `set_usage_watchdog` or `set_disk_usage_watchdog`.
+    pub fn clear_usage_watchdog(&mut self) {
+        self.usage_watchdog = None;
+    }
+
+    /// Sets the minimum time between two usage watchdog checks.
+    ///
+    /// Mutations within `interval` of the previous check skip it, so a threshold crossed during
+    /// a burst of writes is reported by the first mutation after the interval. Use
+    /// `Duration::ZERO` to check after every mutating operation.
+    ///
+    /// # Parameters
+    /// - `interval`: minimum time between checks.
+    pub fn set_usage_watchdog_interval(&mut self, interval: Duration) {
+        self.usage_watchdog_interval = interval;
+    }
+
+    /// Returns the minimum time between two usage watchdog checks.
+    pub fn get_usage_watchdog_interval(&self) -> Duration {
+        self.usage_watchdog_interval
+    }
+
+    /// Returns the total size in bytes of all files under the database root.
+    ///
+    /// # Errors
+    /// Returns an error if reading directories or file metadata fails.
+    pub fn get_database_size(&self) -> Result<u64, DatabaseError> {
+        directory_size(&self.path)
+    }
+
+    /// Sets the maximum total size of the database in bytes, or removes it with `None`.
+    ///
+    /// While a quota is set, `overwrite_existing` (and the writes built on it), `ItemWriter`
+    /// commits, `write_new_with_contents` (and its typed forms), batch file creates,
+    /// `import_item`, and `duplicate_item` check the size the database would reach and fail with
+    /// `DatabaseError::QuotaExceeded` instead of writing. Per-directory quotas are set with
+    /// `SubtreeConfig::with_quota` and checked the same way. Sizes are measured like
+    /// `get_database_size`, so internal files such as the trash count too, except staging files
+    /// of writes in progress and data parked for `undo`.
+    ///
+    /// A directory is measured at the first check after a change and then kept up to date by the
+    /// checked writes themselves, so a run of overwrites does not walk the tree each time.
+    /// Changes made outside the manager are picked up after the next mutation.
+    ///
+    /// # Parameters
+    /// - `bytes`: maximum size, or `None` for no limit.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId, SubtreeConfig};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.set_quota(Some(1_000_000_000));
+    ///     manager.write_new(ItemId::id("uploads"), ItemId::database_id())?;
+    ///     let uploads = SubtreeConfig::new().with_quota(50_000_000);
+    ///     manager.set_subtree_config(ItemId::id("uploads"), uploads)?;
+    ///     println!("uploads uses {} bytes", manager.get_usage(ItemId::id("uploads"))?);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_quota(&mut self, bytes: Option<u64>) {
+        self.quota = bytes;
+    }
+
+    /// Returns the database-wide quota in bytes, if one is set.
+    pub fn get_quota(&self) -> Option<u64> {
+        self.quota
+    }
+
+    /// Returns the total size in bytes of `id`: a file's length, or the size of every file
+    /// below a directory.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` cannot be found,
+    /// - reading directories or file metadata fails.
+    pub fn get_usage(&self, id: impl Into<ItemId>) -> Result<u64, DatabaseError> {
+        Ok(tree_totals(&self.locate_absolute(id)?)?.0)
+    }
+
+    /// Returns the bytes available to this process on the filesystem holding the database.
+    ///
+    /// # Errors
+    /// Returns an error if the filesystem cannot be queried. Free space can only be queried on
+    /// 64-bit Linux and on Windows; every other platform returns an `io::ErrorKind::Unsupported`
+    /// error.
+    pub fn get_free_space(&self) -> Result<u64, DatabaseError> {
+        Ok(available_space(&self.path)?)
+    }
+
+    /// Checks that the filesystem holding the database has at least `required_bytes` free and
+    /// returns the bytes available.
+    ///
+    /// # Parameters
+    /// - `required_bytes`: space the caller is about to use.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - less than `required_bytes` is available (`DatabaseError::InsufficientSpace`),
+    /// - the filesystem cannot be queried (see `get_free_space`).
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let manager = DatabaseManager::create_database(".", "database")?;
+    ///     let available = manager.check_free_space(500_000_000)?;
+    ///     println!("{available} bytes free");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn check_free_space(&self, required_bytes: u64) -> Result<u64, DatabaseError> {
+        let available = available_space(&self.path)?;
+        if available < required_bytes {
+            return Err(DatabaseError::InsufficientSpace(
+                self.path.clone(),
+                required_bytes,
+                available,
+            ));
+        }
+        Ok(available)
+    }
+
+    /// Enables or disables the free-space check before large copies.
+    ///
+    /// When enabled, `import_item` (copy and move modes), `duplicate_item`, and
+    /// `migrate_database` measure what they are about to copy and fail with
+    /// `DatabaseError::InsufficientSpace` before writing anything if the destination
+    /// filesystem is too full, instead of failing halfway through.
+    ///
+    /// The check only runs where `get_free_space` works (64-bit Linux and Windows). On every
+    /// other platform enabling it is a no-op: operations run as if it were disabled and can
+    /// still fail partway through on a full disk.
+    ///
+    /// # Parameters
+    /// - `enabled`: whether large operations check free space first.
+    pub fn set_free_space_check(&mut self, enabled: bool) {
+        self.free_space_check = enabled;
+    }
+
+    /// Returns `true` when large operations check free space first.
+    pub fn get_free_space_check(&self) -> bool {
+        self.free_space_check
+    }
+
+    /// Reads several managed files and returns their bytes in the same order as `ids`.
+    ///
+    /// Each entry is read independently, so one missing item does not stop the others.
+    ///
+    /// # Parameters
+    /// - `ids`: target file **`ItemId`** values.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("a.txt"), ItemId::database_id())?;
+    ///     manager.write_new(ItemId::id("b.txt"), ItemId::database_id())?;
+    ///     let results = manager.read_many(["a.txt", "b.txt", "missing.txt"]);
+    ///     assert!(results[2].is_err());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn read_many<I>(&self, ids: I) -> Vec<Result<Vec<u8>, DatabaseError>>
+    where
+        I: IntoIterator,
+        I::Item: Into<ItemId>,
+    {
+        let ids: Vec<ItemId> = ids.into_iter().map(Into::into).collect();
+        let mut tracker =
+            self.start_progress_with_totals(ProgressOperation::ReadMany, Some(ids.len()), None);
+
+        ids.into_iter()
+            .map(|id| {
+                let result = self.read_existing(&id);
+                let bytes = result.as_ref().map_or(0, |data| data.len() as u64);
+                let path = self
+                    .locate_relative(&id)
+                    .map(RelativePath::into_path_buf)
+                    .unwrap_or_else(|_| PathBuf::from(id.get_name()));
+                tracker.advance(&path, bytes);
+                result
+            })
+            .collect()
+    }
+
+    /// Reads several managed files on up to `threads` worker threads.
+    ///
+    /// Results keep the same order as `ids`. A `threads` value of `0` uses the
+    /// available parallelism reported by the OS.
+    ///
+    /// # Parameters
+    /// - `ids`: target file **`ItemId`** values.
+    /// - `threads`: maximum number of worker threads.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let manager = DatabaseManager::create_database(".", "database")?;
+    ///     let ids: Vec<ItemId> = manager.get_by_parent(ItemId::database_id(), false)?;
+    ///     let _results = manager.read_many_parallel(ids, 4);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn read_many_parallel<I>(
+        &self,
+        ids: I,
+        threads: usize,
+    ) -> Vec<Result<Vec<u8>, DatabaseError>>
+    where
+        I: IntoIterator,
+        I::Item: Into<ItemId>,
+    {
+        let ids: Vec<ItemId> = ids.into_iter().map(Into::into).collect();
+        let threads = match threads {
+            0 => thread::available_parallelism().map_or(1, |count| count.get()),
+            threads => threads,
+        };
+        if threads <= 1 || ids.len() <= 1 {
+            return self.read_many(ids);
+        }
+
+        let chunk_size = ids.len().div_ceil(threads);
+        thread::scope(|scope| {
+            let workers: Vec<_> = ids
+                .chunks(chunk_size)
+                .map(|chunk| scope.spawn(move || self.read_many(chunk.iter().cloned())))
+                .collect();
+
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().expect("read worker panicked"))
+                .collect()
+        })
+    }
+
+    /// Reads several managed files and turns JSON into `T`, in the same order as `ids`.
+    ///
+    /// # Parameters
+    /// - `ids`: target file **`ItemId`** values.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let manager = DatabaseManager::create_database(".", "database")?;
+    ///     let _values = manager.read_many_json::<u32, _>(["a.json", "b.json"]);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn read_many_json<T, I>(&self, ids: I) -> Vec<Result<T, DatabaseError>>
+    where
+        T: serde::de::DeserializeOwned,
+        I: IntoIterator,
+        I::Item: Into<ItemId>,
+    {
+        ids.into_iter()
+            .map(|id| self.read_existing_json(id))
+            .collect()
+    }
+
+    /// Claims `id` under `parent` by creating an empty placeholder item.
+    ///
+    /// The placeholder is created with the same rules as `write_new`, and creation fails if
+    /// anything already exists at the target path, so only one worker can claim a name.
+    /// Complete the reservation with `fulfill` or abandon it with `release`.
+    ///
+    /// # Parameters
+    /// - `id`: name key for the reserved item. Root **`ItemId`** is not allowed.
+    /// - `parent`: destination parent item. Use `ItemId::database_id()` for database root.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `write_new` fails for `id` and `parent`.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.reserve(ItemId::id("download.bin"), ItemId::database_id())?;
+    ///     // ... long transfer ...
+    ///     manager.fulfill(ItemId::id("download.bin"), [1_u8, 2, 3])?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn reserve(
+        &mut self,
+        id: impl Into<ItemId>,
+        parent: impl Into<ItemId>,
+    ) -> Result<(), DatabaseError> {
+        let id = id.into();
+        self.write_new(&id, parent)?;
+
+        let relative_path = self.relative_path_of(&id)?;
+        self.reservations.insert(relative_path);
+
+        Ok(())
+    }
+
+    /// Completes a reservation by writing `data` to the reserved file.
+    ///
+    /// # Parameters
+    /// - `id`: reserved file **`ItemId`**.
+    /// - `data`: raw bytes to write.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` cannot be found,
+    /// - `id` is not reserved,
+    /// - overwriting the file fails.
+    pub fn fulfill<T>(&mut self, id: impl Into<ItemId>, data: T) -> Result<(), DatabaseError>
+    where
+        T: AsRef<[u8]>,
+    {
+        let id = id.into();
+        let relative_path = self.reserved_path(&id)?;
+
+        self.overwrite_existing(&id, data)?;
+        self.reservations.remove(&relative_path);
+
+        Ok(())
+    }
+
+    /// Abandons a reservation and deletes its placeholder.
+    ///
+    /// # Parameters
+    /// - `id`: reserved **`ItemId`**.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` cannot be found,
+    /// - `id` is not reserved,
+    /// - deleting the placeholder fails.
+    pub fn release(&mut self, id: impl Into<ItemId>) -> Result<(), DatabaseError> {
+        let id = id.into();
+        self.reserved_path(&id)?;
+
+        self.delete(id, true)?;
+        Ok(())
+    }
+
+    /// Returns `true` when `id` is reserved and not yet fulfilled or released.
+    pub fn is_reserved(&self, id: impl Into<ItemId>) -> bool {
+        self.relative_path_of(&id.into())
+            .is_ok_and(|path| self.reservations.contains(&path))
+    }
+
+    /// Enables or disables lowercase extension normalization.
+    ///
+    /// When enabled, extensions are lowercased whenever a name is inserted into or looked up in
+    /// the `index`, so `Photo.JPG` and `Photo.jpg` resolve to the same shared name. New items
+    /// are also created on disk with the lowercase extension.
+    ///
+    /// The setting decides how names are keyed in the `index`, so it can only change while the
+    /// index is empty. Re-keying tracked items would give them new **`ItemId`**s. It is stored
+    /// by `save_index` and restored when the index is loaded.
+    ///
+    /// # Parameters
+    /// - `enabled`: whether extensions should be normalized.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - the setting changes while the index tracks items (`DatabaseError::IndexNotEmpty`).
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.set_extension_normalization(true)?;
+    ///     manager.write_new(ItemId::id("Photo.JPG"), ItemId::database_id())?;
+    ///     let _path = manager.locate_absolute(ItemId::id("Photo.jpg"))?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_extension_normalization(&mut self, enabled: bool) -> Result<(), DatabaseError> {
+        if self.normalize_extensions == enabled {
+            return Ok(());
+        }
+
+        let tracked = self.all_paths().len();
+        if tracked > 0 {
+            return Err(DatabaseError::IndexNotEmpty(tracked));
+        }
+
+        self.normalize_extensions = enabled;
+        Ok(())
+    }
+
+    /// Returns `true` when extensions are lowercased on insert and lookup.
+    pub fn get_extension_normalization(&self) -> bool {
+        self.normalize_extensions
+    }
+
+    /// Enables or disables per-directory manifest files.
+    ///
+    /// When enabled, every tracked directory (including the root) gets a `MANIFEST_FILE_NAME`
+    /// file listing its tracked children with sizes, content hashes, and modified times.
+    /// After a mutating operation, only the manifests of the directories it touched are
+    /// refreshed; when that fails, the operation returns the error even though its change has
+    /// already been applied. Disabling removes them, except in subtrees whose
+    /// **`SubtreeConfig`** enables them.
+    ///
+    /// # Parameters
+    /// - `enabled`: whether manifests should be maintained.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - writing or removing a manifest file fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.set_manifests(true)?;
+    ///     manager.write_new(ItemId::id("a.txt"), ItemId::database_id())?;
+    ///     let manifest = manager.read_manifest(ItemId::database_id())?;
+    ///     assert!(manifest.get_entry("a.txt").is_some());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_manifests(&mut self, enabled: bool) -> Result<(), DatabaseError> {
+        self.manifests = enabled;
+        self.refresh_manifests()
+    }
+
+    /// Returns `true` when per-directory manifests are maintained.
+    pub fn get_manifests(&self) -> bool {
+        self.manifests
+    }
+
+    /// Rewrites the manifest of every tracked directory where manifests are enabled, and
+    /// removes the manifest of every directory where they are disabled.
+    ///
+    /// Whether a directory keeps a manifest follows `set_manifests`, unless a
+    /// **`SubtreeConfig`** on the directory or an ancestor overrides it. Hashes are reused for
+    /// files whose size and modified time have not changed.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - reading item metadata, hashing a file, or writing or removing a manifest fails.
+    pub fn refresh_manifests(&self) -> Result<(), DatabaseError> {
+        for directory in self.tracked_directories() {
+            self.refresh_manifest(&directory)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads the manifest stored in directory `id`.
+    ///
+    /// This reads the file on disk, so it also works for manifests written by another process.
+    ///
+    /// # Parameters
+    /// - `id`: directory **`ItemId`** (or `ItemId::database_id()`).
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` cannot be found or is not a directory,
+    /// - the manifest file is missing or cannot be parsed.
+    pub fn read_manifest(&self, id: impl Into<ItemId>) -> Result<DirectoryManifest, DatabaseError> {
+        let absolute = self.locate_absolute(id)?;
+        if !absolute.is_dir() {
+            return Err(DatabaseError::NotADirectory(absolute));
+        }
+
+        let bytes = fs::read(absolute.join(MANIFEST_FILE_NAME))?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    /// Sets the policy used by `run_due_backups`.
+    ///
+    /// # Parameters
+    /// - `policy`: backup schedule and destination.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - the destination is inside the database.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use file_database::{BackupPolicy, DatabaseError, DatabaseManager};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.set_backup_policy(BackupPolicy::new(
+    ///         Duration::from_secs(60 * 60),
+    ///         "./backups",
+    ///         5,
+    ///     ))?;
+    ///     if let Some(path) = manager.run_due_backups()? {
+    ///         println!("Backed up to {}", path.display());
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_backup_policy(&mut self, policy: BackupPolicy) -> Result<(), DatabaseError> {
+        let destination = if policy.destination.is_absolute() {
+            policy.destination.clone()
+        } else {
+            current_dir()?.join(&policy.destination)
+        };
+
+        if destination.starts_with(&self.path) {
+            return Err(DatabaseError::ExportDestinationInsideDatabase(destination));
+        }
+
+        self.backup_policy = Some(BackupPolicy {
+            destination,
+            ..policy
+        });
+        Ok(())
+    }
+
+    /// Removes the backup policy. Existing backups are left in place.
+    pub fn clear_backup_policy(&mut self) {
+        self.backup_policy = None;
+    }
+
+    /// Returns the current backup policy, if any.
+    pub fn get_backup_policy(&self) -> Option<&BackupPolicy> {
+        self.backup_policy.as_ref()
+    }
+
+    /// Creates a backup when the newest one in the policy destination is older than the
+    /// policy interval, then prunes backups beyond `keep_last`.
+    ///
+    /// Due-ness is worked out from the backups already in the destination, so the schedule
+    /// survives restarts. Call this periodically, for example from a timer in your app.
+    ///
+    /// Returns the path of the new backup, or `None` when no policy is set, none is due, or
+    /// maintenance is not allowed right now (see `is_maintenance_allowed`).
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - the destination cannot be created or read,
+    /// - copying the database or removing old backups fails.
+    pub fn run_due_backups(&self) -> Result<Option<PathBuf>, DatabaseError> {
+        if !self.is_maintenance_allowed() {
+            return Ok(None);
+        }
+
+        self.backup_if_due()
+    }
+
+    /// Restricts maintenance work to the given daily windows.
+    ///
+    /// With windows or an idle check set, `run_maintenance` and `run_due_backups` only do work
+    /// inside a window or while the idle check returns `true`. An empty list removes the
+    /// windows.
+    ///
+    /// # Parameters
+    /// - `windows`: allowed **`MaintenanceWindow`** ranges.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use file_database::{DatabaseError, DatabaseManager, MaintenanceWindow, ScanPolicy};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.set_maintenance_windows(vec![MaintenanceWindow::new(
+    ///         Duration::from_secs(2 * 3600),
+    ///         Duration::from_secs(4 * 3600),
+    ///     )]);
+    ///     manager.set_idle_check(|| false);
+    ///     let _report = manager.run_maintenance(ScanPolicy::AddNew)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_maintenance_windows(&mut self, windows: Vec<MaintenanceWindow>) {
+        self.maintenance.windows = windows;
+    }
+
+    /// Returns the configured maintenance windows.
+    pub fn get_maintenance_windows(&self) -> &[MaintenanceWindow] {
+        &self.maintenance.windows
+    }
+
+    /// Sets a callback that allows maintenance outside the windows while it returns `true`.
+    ///
+    /// # Parameters
+    /// - `check`: returns `true` when the application is idle.
+    pub fn set_idle_check(&mut self, check: impl Fn() -> bool + Send + Sync + 'static) {
+        self.maintenance.idle_check = Some(Arc::new(check));
+    }
+
+    /// Removes the maintenance windows and the idle check, so maintenance may always run.
+    pub fn clear_maintenance_schedule(&mut self) {
+        self.maintenance = MaintenanceSchedule::default();
+    }
+
+    /// Returns `true` when maintenance work may run now.
+    ///
+    /// This is always `true` when no windows and no idle check are set. Otherwise the current
+    /// time must fall inside a window, or the idle check must return `true`.
+    pub fn is_maintenance_allowed(&self) -> bool {
+        let schedule = &self.maintenance;
+        if schedule.windows.is_empty() && schedule.idle_check.is_none() {
+            return true;
+        }
+
+        let now = SystemTime::now();
+        schedule.windows.iter().any(|window| window.contains(now))
+            || schedule.idle_check.as_ref().is_some_and(|check| check())
+    }
+
+    /// Runs all maintenance work when `is_maintenance_allowed` says so.
+    ///
+    /// Scans the whole database with `policy`, refreshes manifests when they are enabled,
+    /// applies the tier rule when one is set, and creates a backup when one is due. Returns `None` without doing anything outside the
+    /// configured windows while the application is not idle.
+    ///
+    /// # Parameters
+    /// - `policy`: **`ScanPolicy`** used for the scan.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - the scan, manifest refresh, or backup fails.
+    pub fn run_maintenance(
+        &mut self,
+        policy: ScanPolicy,
+    ) -> Result<Option<MaintenanceReport>, DatabaseError> {
+        if !self.is_maintenance_allowed() {
+            return Ok(None);
+        }
+
+        let scan = self.scan_for_changes(ItemId::database_id(), policy, true)?;
+        if self.uses_manifests() {
+            self.refresh_manifests()?;
+        }
+        let tiered = self.apply_tiering()?;
+        let backup = self.backup_if_due()?;
+
+        Ok(Some(MaintenanceReport {
+            scan,
+            tiered,
+            backup,
+        }))
+    }
+
+    /// Creates a backup if the policy says one is due, ignoring the maintenance schedule.
+    fn backup_if_due(&self) -> Result<Option<PathBuf>, DatabaseError> {
+        let Some(policy) = &self.backup_policy else {
+            return Ok(None);
+        };
+
+        fs::create_dir_all(&policy.destination)?;
+        let prefix = format!("{}-backup-", os_str_to_string(self.path.file_name())?);
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        let mut backups = self.list_backups(&policy.destination, &prefix)?;
+        let is_due = backups.last().is_none_or(|(millis, _)| {
+            now.saturating_sub(Duration::from_millis(*millis)) >= policy.interval
+        });
+        if !is_due {
+            return Ok(None);
+        }
+
+        let backup = policy
+            .destination
+            .join(format!("{prefix}{}", now.as_millis()));
+        let mut tracker = self.start_progress(ProgressOperation::Backup, &[&self.path]);
+        self.copy_directory_recursive(&self.path, &backup, &mut tracker)?;
+        tracker.finish();
+        backups.push((now.as_millis() as u64, backup.clone()));
+
+        let excess = backups.len().saturating_sub(policy.keep_last);
+        for (_, old) in backups.drain(..excess) {
+            remove_dir_all(old)?;
+        }
+
+        Ok(Some(backup))
+    }
+
+    /// Returns a **`ReadOnlyView`** restricted to the directory `parent` and its descendants.
+    ///
+    /// The view borrows the manager immutably, so it can be handed to untrusted code without
+    /// giving it any way to change the database.
+    ///
+    /// # Parameters
+    /// - `parent`: directory item used as the view root (or `ItemId::database_id()`).
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `parent` cannot be found,
+    /// - `parent` points to a file instead of a directory.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("plugins"), ItemId::database_id())?;
+    ///     manager.write_new(ItemId::id("markdown"), ItemId::id("plugins"))?;
+    ///     let view = manager.read_only_view(ItemId::id("markdown"))?;
+    ///     let _files = view.get_all(true);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn read_only_view(
+        &self,
+        parent: impl Into<ItemId>,
+    ) -> Result<ReadOnlyView<'_>, DatabaseError> {
+        let root = self
+            .directory_relative_path(&parent.into())?
+            .into_path_buf();
+
+        Ok(ReadOnlyView {
+            manager: self,
+            root,
+        })
+    }
+
+    /// Exports many items, each to its own exact destination path.
+    ///
+    /// Every entry is checked before anything is exported: items must exist and not be the
+    /// root, destinations must be outside the database, and no two destinations may be the same
+    /// path or nested inside each other. After that, items are exported in order and each one
+    /// gets its own result. Existing files at a destination are replaced, like `export_item`.
+    ///
+    /// # Parameters
+    /// - `entries`: `(id, destination path)` pairs. The destination includes the final name.
+    /// - `mode`: copy or move behavior.
+    ///
+    /// # Errors
+    /// Returns an error (and exports nothing) if:
+    /// - any `id` is root or cannot be found,
+    /// - any destination is inside the database,
+    /// - two destinations collide (`DatabaseError::ExportDestinationCollision`).
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use std::path::PathBuf;
+    /// use file_database::{DatabaseError, DatabaseManager, ExportMode, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("a.txt"), ItemId::database_id())?;
+    ///     manager.write_new(ItemId::id("b.txt"), ItemId::database_id())?;
+    ///     let results = manager.export_map(
+    ///         &[
+    ///             (ItemId::id("a.txt"), PathBuf::from("./exports/first.txt")),
+    ///             (ItemId::id("b.txt"), PathBuf::from("./exports/second.txt")),
+    ///         ],
+    ///         ExportMode::Copy,
+    ///     )?;
+    ///     for result in results {
+    ///         println!("{:?}", result.map(|summary| summary.get_destination().to_path_buf()));
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn export_map(
+        &mut self,
+        entries: &[(ItemId, PathBuf)],
+        mode: ExportMode,
+    ) -> Result<Vec<Result<ExportSummary, DatabaseError>>, DatabaseError> {
+        let working_dir = current_dir()?;
+        let mut planned: Vec<(&ItemId, PathBuf)> = Vec::with_capacity(entries.len());
+
+        for (id, destination) in entries {
+            if id.get_name().is_empty() {
+                return Err(DatabaseError::RootIdUnsupported);
+            }
+            self.resolve_path_by_id(id)?;
+
+            let destination = if destination.is_absolute() {
+                destination.clone()
+            } else {
+                working_dir.join(destination)
+            };
+
+            if destination.starts_with(&self.path) {
+                return Err(DatabaseError::ExportDestinationInsideDatabase(destination));
+            }
+
+            if planned
+                .iter()
+                .any(|(_, other)| destination.starts_with(other) || other.starts_with(&destination))
+            {
+                return Err(DatabaseError::ExportDestinationCollision(destination));
+            }
+
+            planned.push((id, destination));
+        }
+
+        let sources: Vec<PathBuf> = planned
+            .iter()
+            .filter_map(|(id, _)| self.locate_absolute(*id).ok())
+            .collect();
+        let roots: Vec<&Path> = sources.iter().map(PathBuf::as_path).collect();
+        let mut tracker = self.start_progress(ProgressOperation::Export, &roots);
+
+        let mut results = Vec::with_capacity(planned.len());
+        for (id, destination) in planned {
+            let result = self.locate_absolute(id).and_then(|source| {
+                if let Some(parent) = destination.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                self.export_to_path(id, &source, destination, &mode, &mut tracker)
+            });
+            results.push(result);
+        }
+        tracker.finish();
+
+        Ok(results)
+    }
+
+    /// Updates the `index` after a file or directory was moved outside the manager.
+    ///
+    /// Every tracked entry at or below `from` is rewritten to sit at the same place below `to`
+    /// in one pass, so a watcher that notices a folder rename can fix the index without a full
+    /// scan. Access rules and reservations move with it. Nothing on disk is touched.
+    ///
+    /// The item that was at `from` keeps its index when its name is unchanged; otherwise it is
+    /// registered under the next free index of its new name.
+    ///
+    /// # Parameters
+    /// - `from`: previous path relative to the database root.
+    /// - `to`: new path relative to the database root.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `from` is the database root,
+    /// - no tracked item uses `from`,
+    /// - another tracked item already uses `to`,
+    /// - `to` has no valid file name.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("photos"), ItemId::database_id())?;
+    ///     // ... another process renames `photos` to `pictures` ...
+    ///     let moved = manager.relocate_subtree("photos", "pictures")?;
+    ///     println!("Updated {moved} index entries");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn relocate_subtree(
+        &mut self,
+        from: impl AsRef<Path>,
+        to: impl AsRef<Path>,
+    ) -> Result<usize, DatabaseError> {
+        let from = from.as_ref();
+        let to = to.as_ref();
+
+        if from.as_os_str().is_empty() {
+            return Err(DatabaseError::RootIdUnsupported);
+        }
+
+        let id = self
+            .id_for_path(from)
+            .ok_or_else(|| DatabaseError::NoMatchingID(from.display().to_string()))?;
+        let name = os_str_to_string(to.file_name())?;
+
+        if from != to && self.path_exists_in_index(to) {
+            return Err(DatabaseError::IdAlreadyExists(to.display().to_string()));
+        }
+
+        let record = self.records.get(from).cloned();
+        self.remove_id_from_index(&id)?;
+        let relocated = self.relocate_index_paths(from, to) + 1;
+        self.relocate_access(from, to);
+        self.relocate_subtree_configs(from, to);
+        self.relocate_reservations(from, to);
+
+        let new_id = ItemId::with_index(name, id.get_index());
+        let new_id = if self.index_key(new_id.get_name()) == self.index_key(id.get_name()) {
+            self.insert_path_for_id(&new_id, to.to_path_buf())?;
+            new_id
+        } else {
+            self.insert_generated_path(new_id.get_name().to_string(), to.to_path_buf())
+        };
+        self.restore_record(to.to_path_buf(), record);
+        self.emit_renamed(&id, from, &new_id, to);
+
+        self.after_mutation(&[from, to])?;
+
+        Ok(relocated)
+    }
+
+    /// Enables or disables strict mode.
+    ///
+    /// In strict mode, `locate_absolute` and `locate_relative` check the filesystem before
+    /// returning: the item must still exist, and an item with tracked children must still be a
+    /// directory. Otherwise they return `DatabaseError::StaleIndexEntry`, instead of letting a
+    /// later operation fail with a less helpful error. Every method that resolves IDs through
+    /// them gets the same check.
+    ///
+    /// # Parameters
+    /// - `enabled`: whether strict mode should be on.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.set_strict_mode(true);
+    ///     manager.write_new(ItemId::id("a.txt"), ItemId::database_id())?;
+    ///     std::fs::remove_file(manager.locate_absolute(ItemId::id("a.txt"))?)?;
+    ///     assert!(manager.locate_absolute(ItemId::id("a.txt")).is_err());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_strict_mode(&mut self, enabled: bool) {
+        self.strict = enabled;
+    }
+
+    /// Returns `true` when strict mode is on.
+    pub fn get_strict_mode(&self) -> bool {
+        self.strict
+    }
+
+    /// Overrides database-wide settings for the directory `id` and everything below it.
+    ///
+    /// Settings left as `None` in `config` are inherited from the nearest ancestor with an
+    /// override, and finally from the database-wide value. Overrides follow their directory
+    /// when it is renamed or moved, are dropped when it is deleted, and are stored in the index
+    /// file by `save_index`.
+    ///
+    /// # Parameters
+    /// - `id`: directory item (or `ItemId::database_id()`).
+    /// - `config`: overrides for the subtree; replaces any previous config for `id`.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` cannot be found or is not a directory,
+    /// - updating manifests for the new settings fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId, SubtreeConfig};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("cache"), ItemId::database_id())?;
+    ///     manager.write_new(ItemId::id("documents"), ItemId::database_id())?;
+    ///     manager.set_subtree_config(
+    ///         ItemId::id("documents"),
+    ///         SubtreeConfig::new().with_manifests(true).with_strict_mode(true),
+    ///     )?;
+    ///     manager.set_subtree_config(ItemId::id("cache"), SubtreeConfig::new().with_manifests(false))?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_subtree_config(
+        &mut self,
+        id: impl Into<ItemId>,
+        config: SubtreeConfig,
+    ) -> Result<(), DatabaseError> {
+        let relative = self.directory_relative_path(&id.into())?.into_path_buf();
+        self.subtree_configs.insert(relative, config);
+        self.refresh_manifests()
+    }
+
+    /// Returns the config set directly on directory `id`, without inherited settings.
+    ///
+    /// # Errors
+    /// Returns an error if `id` cannot be found.
+    pub fn get_subtree_config(
+        &self,
+        id: impl Into<ItemId>,
+    ) -> Result<Option<&SubtreeConfig>, DatabaseError> {
+        let relative = self.locate_relative(id)?;
+        Ok(self.subtree_configs.get(relative.as_path()))
+    }
+
+    /// Removes the config set directly on directory `id` and returns it.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` cannot be found,
+    /// - updating manifests for the inherited settings fails.
+    pub fn clear_subtree_config(
+        &mut self,
+        id: impl Into<ItemId>,
+    ) -> Result<Option<SubtreeConfig>, DatabaseError> {
+        let relative = self.locate_relative(id)?;
+        let removed = self.subtree_configs.remove(relative.as_path());
+        if removed.is_some() {
+            self.refresh_manifests()?;
+        }
+        Ok(removed)
+    }
+
+    /// Returns the settings that apply to `id`, with every setting resolved.
+    ///
+    /// Each setting comes from the nearest enclosing **`SubtreeConfig`** that sets it, or from
+    /// the database-wide value, so every field of the result is `Some`. The quota is not
+    /// inherited, so it is the one set on `id` itself, if any.
+    ///
+    /// # Errors
+    /// Returns an error if `id` cannot be found.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let manager = DatabaseManager::create_database(".", "database")?;
+    ///     let config = manager.get_effective_config(ItemId::id("report.txt"))?;
+    ///     println!("strict: {:?}", config.get_strict_mode());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn get_effective_config(
+        &self,
+        id: impl Into<ItemId>,
+    ) -> Result<SubtreeConfig, DatabaseError> {
+        let relative = self.locate_relative(id)?;
+        Ok(SubtreeConfig {
+            manifests: Some(self.manifests_enabled_for(&relative)),
+            strict: Some(
+                self.subtree_setting(&relative, |config| config.strict)
+                    .unwrap_or(self.strict),
+            ),
+            quota: self
+                .subtree_configs
+                .get(relative.as_path())
+                .and_then(|config| config.quota),
+        })
+    }
+
+    /// Returns every tracked item, oldest insertion first.
+    ///
+    /// Unlike modification times, insertion order is not reset by imports or external edits.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("b.txt"), ItemId::database_id())?;
+    ///     manager.write_new(ItemId::id("a.txt"), ItemId::database_id())?;
+    ///     let latest = manager.get_all_by_insertion_order().pop();
+    ///     assert_eq!(latest, Some(ItemId::id("a.txt")));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn get_all_by_insertion_order(&self) -> Vec<ItemId> {
+        let mut list: Vec<(u64, ItemId)> = self
+            .all_paths()
+            .into_iter()
+            .map(|(id, path)| {
+                let sequence = self
+                    .records
+                    .get(path)
+                    .map_or(u64::MAX, |record| record.insertion.sequence);
+                (sequence, id)
+            })
+            .collect();
+
+        list.sort();
+        list.into_iter().map(|(_, id)| id).collect()
+    }
+
+    /// Returns items inserted at or after `from` and before `to`, oldest first.
+    ///
+    /// # Parameters
+    /// - `from`: inclusive start time.
+    /// - `to`: exclusive end time.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use std::time::{Duration, SystemTime};
+    /// use file_database::{DatabaseError, DatabaseManager};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let manager = DatabaseManager::create_database(".", "database")?;
+    ///     let now = SystemTime::now();
+    ///     let _recent = manager.get_created_between(now - Duration::from_secs(3600), now);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn get_created_between(&self, from: SystemTime, to: SystemTime) -> Vec<ItemId> {
+        self.get_all_by_insertion_order()
+            .into_iter()
+            .filter(|id| {
+                self.get_insertion_record(id)
+                    .is_ok_and(|record| record.inserted_at >= from && record.inserted_at < to)
+            })
+            .collect()
+    }
+
+    /// Returns the **`InsertionRecord`** for `id`.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` is root or cannot be found.
+    pub fn get_insertion_record(
+        &self,
+        id: impl Into<ItemId>,
+    ) -> Result<InsertionRecord, DatabaseError> {
+        let id = id.into();
+        if id.get_name().is_empty() {
+            return Err(DatabaseError::RootIdUnsupported);
+        }
+
+        let path = self.resolve_path_by_id(&id)?;
+        self.records
+            .get(path)
+            .map(|record| record.insertion)
+            .ok_or_else(|| DatabaseError::NoMatchingID(id.as_string()))
+    }
+
+    /// Returns the **`ImportMode`** `id` was brought in with, or `None` if it was not imported.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` is root or cannot be found.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ImportMode, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     let id = manager.import_item(
+    ///         "./outside/big.bin",
+    ///         ItemId::database_id(),
+    ///         ImportMode::Symlink,
+    ///     )?;
+    ///     assert_eq!(manager.get_import_mode(id)?, Some(ImportMode::Symlink));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn get_import_mode(
+        &self,
+        id: impl Into<ItemId>,
+    ) -> Result<Option<ImportMode>, DatabaseError> {
+        let id = id.into();
+        if id.get_name().is_empty() {
+            return Err(DatabaseError::RootIdUnsupported);
+        }
+
+        let path = self.resolve_path_by_id(&id)?;
+        Ok(self.records.get(path).and_then(|record| record.import_mode))
+    }
+
+    /// Creates a new item like `write_new` that expires after `ttl`.
+    ///
+    /// # Parameters
+    /// - `id`: name key for the new item. Root **`ItemId`** is not allowed.
+    /// - `parent`: destination parent item. Use `ItemId::database_id()` for database root.
+    /// - `ttl`: how long the item lives (see `set_ttl`).
+    ///
+    /// # Errors
+    /// Returns an error if `write_new` fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    /// use std::time::Duration;
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "cache")?;
+    ///     let one_hour = Duration::from_secs(60 * 60);
+    ///     manager.write_new_with_ttl(ItemId::id("page.html"), ItemId::database_id(), one_hour)?;
+    ///     manager.save_index()?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn write_new_with_ttl(
+        &mut self,
+        id: impl Into<ItemId>,
+        parent: impl Into<ItemId>,
+        ttl: Duration,
+    ) -> Result<CreatedItem, DatabaseError> {
+        let created = self.write_new(id, parent)?;
+        self.set_ttl(created.get_id(), Some(ttl))?;
+        Ok(created)
+    }
+
+    /// Imports an external item like `import_item` that expires after `ttl`.
+    ///
+    /// # Parameters
+    /// - `from`: source path outside the database.
+    /// - `to`: destination directory item in the database.
+    /// - `mode`: how the source is brought in (**`ImportMode`**).
+    /// - `ttl`: how long the item lives (see `set_ttl`).
+    ///
+    /// # Errors
+    /// Returns an error if `import_item` fails.
+    pub fn import_item_with_ttl(
+        &mut self,
+        from: impl AsRef<Path>,
+        to: impl Into<ItemId>,
+        mode: ImportMode,
+        ttl: Duration,
+    ) -> Result<ItemId, DatabaseError> {
+        let id = self.import_item(from, to, mode)?;
+        self.set_ttl(&id, Some(ttl))?;
+        Ok(id)
+    }
+
+    /// Makes `id` expire `ttl` from now, or never with `None`.
+    ///
+    /// Expired items are deleted by `purge_expired`, including everything inside an expired
+    /// directory. The expiry follows the item through renames and moves, and is stored by
+    /// `save_index` so it survives restarts once the index is loaded again.
+    ///
+    /// # Parameters
+    /// - `id`: item to set the expiry of. Root **`ItemId`** is not allowed.
+    /// - `ttl`: time to live from now, or `None` to keep the item indefinitely.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` is root or cannot be found.
+    pub fn set_ttl(
+        &mut self,
+        id: impl Into<ItemId>,
+        ttl: Option<Duration>,
+    ) -> Result<(), DatabaseError> {
+        let id = id.into();
+        if id.get_name().is_empty() {
+            return Err(DatabaseError::RootIdUnsupported);
+        }
+
+        let path = self.resolve_path_by_id(&id)?.clone();
+        if let Some(record) = self.records.get_mut(&path) {
+            record.expires_at = ttl.and_then(|ttl| SystemTime::now().checked_add(ttl));
+        }
+        Ok(())
+    }
+
+    /// Returns when `id` expires, or `None` if it never does.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` is root or cannot be found.
+    pub fn get_expiry(&self, id: impl Into<ItemId>) -> Result<Option<SystemTime>, DatabaseError> {
+        let id = id.into();
+        if id.get_name().is_empty() {
+            return Err(DatabaseError::RootIdUnsupported);
+        }
+
+        let path = self.resolve_path_by_id(&id)?;
+        Ok(self.records.get(path).and_then(|record| record.expires_at))
+    }
+
+    /// Deletes every item whose expiry has passed and returns their **`ItemId`** values, in
+    /// path order.
+    ///
+    /// Expired directories are deleted with everything inside them. A failed delete stops the
+    /// run; items deleted before it stay deleted.
+    ///
+    /// # Errors
+    /// Returns an error if deleting an expired item fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "cache")?;
+    ///     manager.load_index()?;
+    ///     let purged = manager.purge_expired()?;
+    ///     println!("purged {} items", purged.len());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn purge_expired(&mut self) -> Result<Vec<ItemId>, DatabaseError> {
+        let now = SystemTime::now();
+        let mut expired: Vec<PathBuf> = self
+            .records
+            .iter()
+            .filter(|(_, record)| {
+                record
+                    .expires_at
+                    .is_some_and(|expires_at| expires_at <= now)
+            })
+            .map(|(path, _)| path.clone())
+            .collect();
+        expired.sort();
+
+        let mut purged = Vec::new();
+        for path in expired {
+            // Skips items already deleted with an expired ancestor.
+            let Some(id) = self.id_for_path(&path) else {
+                continue;
+            };
+            self.delete(id.clone(), ForceDeletion::Force)?;
+            purged.push(id);
+        }
+
+        Ok(purged)
+    }
+
+    /// Enables or disables purging expired items when the database is opened.
+    ///
+    /// The setting is stored by `save_index`. When `create_database_with_report` or
+    /// `open_with_report` loads an index with it enabled, `purge_expired` runs right after and
+    /// the deleted items are listed in `OpenReport::get_purged_expired`.
+    ///
+    /// # Parameters
+    /// - `enabled`: whether opening the database purges expired items.
+    pub fn set_purge_expired_on_open(&mut self, enabled: bool) {
+        self.purge_expired_on_open = enabled;
+    }
+
+    /// Returns `true` when opening the database purges expired items.
+    pub fn get_purge_expired_on_open(&self) -> bool {
+        self.purge_expired_on_open
+    }
+
+    /// Sets where atomic writes stage new contents before renaming them into place.
+    ///
+    /// Applies to `overwrite_existing` and the writes built on it, `write_new_with_contents`,
+    /// and `open_writer`. Internal files such as the saved index always stage next to
+    /// themselves.
+    ///
+    /// # Parameters
+    /// - `strategy`: **`StagingStrategy`** to use from now on.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, StagingStrategy};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.set_staging_strategy(StagingStrategy::Directory);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_staging_strategy(&mut self, strategy: StagingStrategy) {
+        self.staging = strategy;
+    }
+
+    /// Returns the **`StagingStrategy`** used by atomic writes.
+    pub fn get_staging_strategy(&self) -> StagingStrategy {
+        self.staging
+    }
+
+    /// Sets how far writes are flushed to disk before they return.
+    ///
+    /// Applies to `overwrite_existing` and the writes built on it, `write_new_with_contents`,
+    /// appends, truncation, `ItemWriter` commits, and batch file creation. The default,
+    /// `Durability::File`, syncs file contents but not the directory entry created by the
+    /// rename, which some filesystems can lose in a crash. `Durability::FileAndDirectory` also
+    /// syncs the parent directory after creates, renames, moves, and deletes. The move journal is
+    /// always synced.
+    ///
+    /// # Parameters
+    /// - `durability`: **`Durability`** to use from now on.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, Durability};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.set_durability(Durability::FileAndDirectory);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_durability(&mut self, durability: Durability) {
+        self.durability = durability;
+    }
+
+    /// Returns the **`Durability`** used by writes.
+    pub fn get_durability(&self) -> Durability {
+        self.durability
+    }
+
+    /// Returns the **`ItemHandle`** for `id`.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` is root or cannot be found.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ImportMode, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     let id = manager.import_item("./outside/a.txt", ItemId::database_id(), ImportMode::Copy)?;
+    ///     let handle = manager.get_handle(&id)?;
+    ///     assert_eq!(manager.resolve_handle(handle)?, id);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn get_handle(&self, id: impl Into<ItemId>) -> Result<ItemHandle, DatabaseError> {
+        self.get_insertion_record(id)
+            .map(|record| ItemHandle(record.sequence))
+    }
+
+    /// Returns the current **`ItemId`** of the item behind `handle`.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - the item was deleted or the handle comes from another manager.
+    pub fn resolve_handle(&self, handle: ItemHandle) -> Result<ItemId, DatabaseError> {
+        self.records
+            .iter()
+            .find(|(_, record)| record.insertion.sequence == handle.0)
+            .and_then(|(path, _)| self.id_for_path(path))
+            .ok_or_else(|| DatabaseError::NoMatchingID(handle.to_string()))
+    }
+
+    /// Returns a counter that increases after every successful mutation.
+    ///
+    /// Creating, writing, renaming, moving, deleting, importing, exporting, and scans that find
+    /// changes all bump it. Store the value and compare later to cheaply detect that something
+    /// changed. It starts at `0` when the manager is created and is not persisted.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     let seen = manager.generation();
+    ///     manager.write_new(ItemId::id("a.txt"), ItemId::database_id())?;
+    ///     assert!(manager.generation() > seen);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn generation(&self) -> u64 {
+        self.generation.get()
+    }
+
+    /// Discards and rebuilds the `index` entries below directory `parent` from disk.
+    ///
+    /// Only entries inside `parent` are touched, so this is much cheaper than rebuilding the
+    /// whole manager when you know which folder an external tool rewrote. Paths that still
+    /// exist keep their **`ItemId`** (and insertion record); new paths get generated IDs; paths
+    /// that vanished are dropped. `parent` itself stays in the `index`.
+    ///
+    /// Returns how many entries are tracked below `parent` afterwards.
+    ///
+    /// # Parameters
+    /// - `parent`: directory item to reload (or `ItemId::database_id()`).
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `parent` cannot be found or is not a directory,
+    /// - reading directories or converting names fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("generated"), ItemId::database_id())?;
+    ///     // ... an external tool rewrites `generated/` ...
+    ///     let tracked = manager.reload_subtree(ItemId::id("generated"))?;
+    ///     println!("{tracked} items under generated/");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn reload_subtree(&mut self, parent: impl Into<ItemId>) -> Result<usize, DatabaseError> {
+        let parent = parent.into();
+        let root = self.directory_relative_path(&parent)?.into_path_buf();
+        let discovered = self.collect_paths_in_scope(&self.path.join(&root), true)?;
+
+        let mut previous: HashMap<PathBuf, ItemId> = HashMap::new();
+        for (name, paths) in self.items.iter_mut() {
+            let inside: Vec<usize> = paths
+                .iter()
+                .filter(|(_, path)| path.starts_with(&root) && **path != root)
+                .map(|(index, _)| index)
+                .collect();
+
+            for index in inside {
+                if let Some(path) = paths.get(index).cloned() {
+                    paths.remove(index);
+                    previous.insert(path, ItemId::with_index(name.clone(), index));
+                }
+            }
+        }
+        self.items.retain(|_, paths| !paths.is_empty());
+
+        let discovered_set: HashSet<&PathBuf> = discovered.iter().collect();
+        self.records
+            .retain(|path, _| !previous.contains_key(path) || discovered_set.contains(path));
+
+        let (kept, mut added): (Vec<PathBuf>, Vec<PathBuf>) = discovered
+            .into_iter()
+            .partition(|path| previous.contains_key(path));
+        let tracked = kept.len() + added.len();
+
+        for path in kept {
+            if let Some(id) = previous.remove(&path) {
+                self.insert_path_for_id(&id, path)?;
+            }
+        }
+
+        added.sort();
+        for path in added {
+            let name = os_str_to_string(path.file_name())?;
+            self.insert_generated_path(name, path);
+        }
+
+        self.after_mutation(&[&root])?;
+
+        Ok(tracked)
+    }
+
+    /// Writes the `index` to `INDEX_FILE_NAME` in the database root.
+    ///
+    /// Every entry is stored as its `name`, `index`, and relative `path`, so `load_index` can
+    /// restore the exact same **`ItemId`** values in a later run. Once this manager has saved or
+    /// loaded the index, it is saved again automatically on drop (see `set_flush_on_drop`).
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - serializing or writing the index file fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("a.txt"), ItemId::database_id())?;
+    ///     manager.save_index()?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn save_index(&mut self) -> Result<(), DatabaseError> {
+        let mut entries: Vec<IndexFileEntry> = self
+            .items
+            .iter()
+            .flat_map(|(name, paths)| {
+                paths.iter().map(|(index, path)| IndexFileEntry {
+                    name: name.clone(),
+                    index,
+                    path: path.clone(),
+                    expires_at: self.records.get(path).and_then(|record| record.expires_at),
+                })
+            })
+            .collect();
+        entries.sort_by(|a, b| (&a.name, a.index).cmp(&(&b.name, b.index)));
+
+        let index_path = self.path.join(INDEX_FILE_NAME);
+        let buffer = staging_path(&index_path);
+        let file = IndexFile {
+            entries,
+            subtree_configs: self.subtree_configs.clone(),
+            purge_expired_on_open: self.purge_expired_on_open,
+            normalize_extensions: self.normalize_extensions,
+        };
+        fs::write(&buffer, serde_json::to_vec_pretty(&file)?)?;
+        fs::rename(&buffer, index_path)?;
+        self.index_saved = true;
+
+        Ok(())
+    }
+
+    /// Enables or disables saving the index when the manager is dropped.
+    ///
+    /// Enabled by default. The flush only happens after this manager has called `save_index` or
+    /// `load_index`, so a manager that never opted into index persistence does not overwrite
+    /// an index file it did not load. Errors while flushing are ignored.
+    ///
+    /// # Parameters
+    /// - `enabled`: whether dropping the manager should call `save_index`.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.load_index()?;
+    ///     manager.set_flush_on_drop(false);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_flush_on_drop(&mut self, enabled: bool) {
+        self.flush_on_drop = enabled;
+    }
+
+    /// Returns `true` when the index is saved on drop.
+    pub fn get_flush_on_drop(&self) -> bool {
+        self.flush_on_drop
+    }
+
+    /// Enables or disables write-behind coalescing for `overwrite_existing`.
+    ///
+    /// When enabled, `overwrite_existing` (and the JSON, bincode, and `_as` variants built on it)
+    /// only queues the new contents in memory. Successive writes to the same file replace the
+    /// queued data, so a burst of small updates costs one atomic replace instead of one per call.
+    /// A queued file is written once no new data has arrived for `debounce`; this is checked on
+    /// each `overwrite_existing` call and by `flush_due`. `flush` writes everything right away, and
+    /// dropping the manager flushes too. `read_existing` returns queued data; other readers see
+    /// the file on disk until it is flushed. Renames, deletes, moves, exports, and tiering flush
+    /// first so queued data never targets a stale path.
+    ///
+    /// Disabled by default. Disabling flushes all queued writes.
+    ///
+    /// # Parameters
+    /// - `debounce`: quiet period before a queued file is written, or `None` to disable.
+    ///
+    /// # Errors
+    /// Returns an error if flushing queued writes fails while disabling.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("draft.txt"), ItemId::database_id())?;
+    ///     manager.set_write_behind(Some(Duration::from_millis(500)))?;
+    ///     for draft in ["H", "He", "Hel", "Hell", "Hello"] {
+    ///         manager.overwrite_existing(ItemId::id("draft.txt"), draft)?;
+    ///     }
+    ///     assert_eq!(manager.flush()?, 1);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_write_behind(&mut self, debounce: Option<Duration>) -> Result<(), DatabaseError> {
+        if debounce.is_none() {
+            self.flush()?;
+        }
+        self.write_behind.debounce = debounce;
+        Ok(())
+    }
+
+    /// Returns the write-behind debounce interval, or `None` when writes go straight to disk.
+    pub fn get_write_behind(&self) -> Option<Duration> {
+        self.write_behind.debounce
+    }
+
+    /// Returns how many files have queued write-behind data.
+    pub fn get_pending_write_count(&self) -> usize {
+        self.write_behind.lock().len()
+    }
+
+    /// Writes every queued write-behind file now and returns how many were written.
+    ///
+    /// Each file is replaced atomically, the same way `overwrite_existing` does without
+    /// write-behind.
+    ///
+    /// # Errors
+    /// Returns the first write error. The failed file and any files not yet written stay queued.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let manager = DatabaseManager::create_database(".", "database")?;
+    ///     let _written = manager.flush()?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn flush(&self) -> Result<usize, DatabaseError> {
+        self.flush_pending_writes(|_, _| true)
+    }
+
+    /// Writes queued write-behind files whose debounce interval has passed.
+    ///
+    /// Call it from a timer or event loop so quiet files reach disk without waiting for the next
+    /// `overwrite_existing` call.
+    ///
+    /// # Errors
+    /// Returns the first write error. The failed file and any files not yet written stay queued.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let manager = DatabaseManager::create_database(".", "database")?;
+    ///     let _written = manager.flush_due()?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn flush_due(&self) -> Result<usize, DatabaseError> {
+        let Some(debounce) = self.write_behind.debounce else {
+            return self.flush();
+        };
+        self.flush_pending_writes(|_, pending| pending.queued_at.elapsed() >= debounce)
+    }
+
+    /// Sets the callback that receives **`Progress`** events from every long-running operation.
+    ///
+    /// Migration, export, import, `copy_to`, duplication, snapshots with contents, scans,
+    /// backups, and `read_many` all report through this one handler, so a UI needs only one
+    /// progress dialog. The handler runs on the thread doing the work and should return quickly.
+    /// Totals are computed up front only while a handler is set.
+    ///
+    /// # Parameters
+    /// - `handler`: called with each event.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.set_progress_handler(|progress| {
+    ///         let percent = progress.get_fraction().unwrap_or(0.0) * 100.0;
+    ///         println!("{:?}: {percent:.0}%", progress.get_operation());
+    ///     });
+    ///     manager.migrate_database("./new_parent")?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_progress_handler(&mut self, handler: impl Fn(&Progress) + Send + Sync + 'static) {
+        self.progress = ProgressHandler(Some(Arc::new(handler)));
+    }
+
+    /// Sends every **`Progress`** event to `sender`, for UIs that consume events on another
+    /// thread.
+    ///
+    /// This replaces any handler set with `set_progress_handler`. Events are dropped once the
+    /// receiver is gone.
+    ///
+    /// # Parameters
+    /// - `sender`: channel that receives a copy of each event.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use std::sync::mpsc;
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId, ScanPolicy};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     let (sender, receiver) = mpsc::channel();
+    ///     manager.set_progress_channel(sender);
+    ///     manager.scan_for_changes(ItemId::database_id(), ScanPolicy::AddNew, true)?;
+    ///     for progress in receiver.try_iter() {
+    ///         println!("{} items", progress.get_items_done());
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_progress_channel(&mut self, sender: std::sync::mpsc::Sender<Progress>) {
+        self.set_progress_handler(move |progress| {
+            let _ = sender.send(progress.clone());
+        });
+    }
+
+    /// Sends every progress event to a **`ProgressReporter`** as item counts.
+    ///
+    /// This replaces any handler set with `set_progress_handler` or `set_progress_channel`.
+    ///
+    /// # Parameters
+    /// - `reporter`: receives `on_progress(done, total, current_path)` for each event.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use std::path::Path;
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId, ProgressReporter, ScanPolicy};
+    ///
+    /// struct Bar;
+    ///
+    /// impl ProgressReporter for Bar {
+    ///     fn on_progress(&self, done: usize, total: Option<usize>, _path: Option<&Path>) {
+    ///         if let Some(total) = total {
+    ///             println!("{done}/{total}");
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.set_progress_reporter(Bar);
+    ///     manager.scan_for_changes(ItemId::database_id(), ScanPolicy::AddNew, true)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_progress_reporter(&mut self, reporter: impl ProgressReporter + 'static) {
+        self.set_progress_handler(move |progress| {
+            reporter.on_progress(
+                progress.get_items_done(),
+                progress.get_items_total(),
+                progress.get_current_path(),
+            );
+        });
+    }
+
+    /// Removes the progress handler, channel, or reporter.
+    pub fn clear_progress_handler(&mut self) {
+        self.progress = ProgressHandler::default();
+    }
+
+    /// Sets a callback that provides the contents of tracked files that are missing on disk.
+    ///
+    /// When `read_existing` (and the reads built on it), `open_reader`, or `read_existing_mmap`
+    /// target a file that is in the `index` but not on disk, for example after a selective
+    /// sync, `fetch` is called with its **`ItemId`** and relative path. The returned bytes are
+    /// stored with the usual atomic write, creating missing parent folders, and the read then
+    /// completes as if the file had been there. Errors from `fetch` are returned by the read.
+    ///
+    /// # Parameters
+    /// - `fetch`: returns the contents for a missing item.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn download(path: &std::path::Path) -> std::io::Result<Vec<u8>> {
+    ///     std::fs::read(std::path::Path::new("/mnt/server").join(path))
+    /// }
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.load_index()?;
+    ///     manager.set_fetch_handler(|_id, path| Ok(download(path)?));
+    ///     let _data = manager.read_existing(ItemId::id("report.pdf"))?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_fetch_handler(
+        &mut self,
+        fetch: impl Fn(&ItemId, &Path) -> Result<Vec<u8>, DatabaseError> + Send + Sync + 'static,
+    ) {
+        self.fetch = FetchHandler(Some(Arc::new(fetch)));
+    }
+
+    /// Removes the fetch handler, so reads of missing files fail again.
+    pub fn clear_fetch_handler(&mut self) {
+        self.fetch = FetchHandler::default();
+    }
+
+    /// Registers a callback that runs after an item is added to the `index`.
+    ///
+    /// It fires for items created through this manager (`write_new`, `write_new_all`,
+    /// `write_new_with_contents`, `namespace`, `import_item`, `duplicate_item`,
+    /// `attach_external`, `import_index_json`, `restore_from_trash`, `copy_to` on the
+    /// destination manager, `Batch::apply`, undo and redo) and for each new item that
+    /// `scan_for_changes` adds with `ScanPolicy::AddNew`. A copied, imported, or restored
+    /// directory fires once for the directory itself, which stands for its whole subtree.
+    ///
+    /// Hooks run in the order they were registered, on the thread that made the change, after
+    /// the disk and the `index` are updated. Loading an `index` (`load_index`,
+    /// `reload_subtree`) and moving or deleting the database root do not fire hooks.
+    ///
+    /// # Parameters
+    /// - `on_create`: called with the new item's **`ItemId`** and relative path.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.on_create(|id, path| println!("created {id:?} at {}", path.display()));
+    ///     manager.write_new(ItemId::id("notes.txt"), ItemId::database_id())?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn on_create(&mut self, on_create: impl Fn(&ItemId, &Path) + Send + Sync + 'static) {
+        self.hooks.create.push(Arc::new(on_create));
+    }
+
+    /// Registers a callback that runs after an item is removed from the database.
+    ///
+    /// It fires for `delete`, `delete_to_trash`, `copy_to` and `export_item` with
+    /// `ExportMode::Move`, `Batch::apply`, undo and redo, and for each tracked item that
+    /// `scan_for_changes` finds missing on disk. Deleting a directory fires once for the directory, which stands for its
+    /// whole subtree. See `on_create` for ordering.
+    ///
+    /// # Parameters
+    /// - `on_delete`: called with the removed item's **`ItemId`** and former relative path.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.on_delete(|id, path| println!("deleted {id:?} from {}", path.display()));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn on_delete(&mut self, on_delete: impl Fn(&ItemId, &Path) + Send + Sync + 'static) {
+        self.hooks.delete.push(Arc::new(on_delete));
+    }
+
+    /// Registers a callback that runs after an item is renamed or moved.
+    ///
+    /// It fires for `rename`, `migrate_item`, `relocate_subtree`, and moves made by undo and
+    /// redo. Moving a directory fires once for the directory, which stands for its whole
+    /// subtree. See `on_create` for ordering.
+    ///
+    /// # Parameters
+    /// - `on_rename`: called with the old **`ItemId`** and relative path, then the new ones.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.on_rename(|_old_id, old_path, _new_id, new_path| {
+    ///         println!("{} -> {}", old_path.display(), new_path.display());
+    ///     });
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn on_rename(
+        &mut self,
+        on_rename: impl Fn(&ItemId, &Path, &ItemId, &Path) + Send + Sync + 'static,
+    ) {
+        self.hooks.rename.push(Arc::new(on_rename));
+    }
+
+    /// Registers a callback that runs after a tracked file's contents change.
+    ///
+    /// It fires for `overwrite_existing` and the writes built on it, `append_existing`,
+    /// `append_existing_locked`, `truncate_existing`, `ItemWriter::commit`, `FileHandle::append`,
+    /// and contents restored by undo and redo. See `on_create` for ordering.
+    ///
+    /// # Parameters
+    /// - `on_overwrite`: called with the file's **`ItemId`** and relative path.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.on_overwrite(|id, _path| println!("{id:?} changed"));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn on_overwrite(&mut self, on_overwrite: impl Fn(&ItemId, &Path) + Send + Sync + 'static) {
+        self.hooks.overwrite.push(Arc::new(on_overwrite));
+    }
+
+    /// Removes every hook registered with `on_create`, `on_delete`, `on_rename`, and
+    /// `on_overwrite`.
+    pub fn clear_event_hooks(&mut self) {
+        self.hooks = EventHooks::default();
+    }
+
+    /// Replaces the `index` with the one stored in `INDEX_FILE_NAME` by `save_index`.
+    ///
+    /// Entries whose path no longer exists on disk are skipped. Items on disk that are not in
+    /// the file stay untracked; run `scan_for_changes` to pick them up. Returns how many entries
+    /// were loaded.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - the index file is missing or cannot be parsed,
+    /// - two entries claim the same **`ItemId`** (the current `index` is kept).
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     let loaded = manager.load_index()?;
+    ///     println!("{loaded} entries restored");
+    ///     let _path = manager.locate_absolute(ItemId::with_index("a.txt", 1))?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn load_index(&mut self) -> Result<usize, DatabaseError> {
+        self.load_index_file().map(|(loaded, _)| loaded)
+    }
+
+    /// Runs consistency checks over the database and returns a **`HealthReport`**.
+    ///
+    /// Nothing is changed on disk or in the `index`. The checks are:
+    /// - every `index` entry still exists, and items with tracked children are directories,
+    /// - every file and directory on disk is tracked,
+    /// - no staging files from interrupted writes are left (`TEMP_FILE_SUFFIX`),
+    /// - file hashes still match any manifest (`MANIFEST_FILE_NAME`) on disk. A mismatch with
+    ///   unchanged size and modified time means the data changed underneath the filesystem.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - walking the database directory fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, HealthSeverity};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let manager = DatabaseManager::create_database(".", "database")?;
+    ///     let report = manager.self_check()?;
+    ///     for issue in report.get_issues() {
+    ///         if issue.get_severity() >= HealthSeverity::Warning {
+    ///             println!("{:?}: {}", issue.get_check(), issue.get_path().display());
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn self_check(&self) -> Result<HealthReport, DatabaseError> {
+        let mut issues = Vec::new();
+        let tracked: HashSet<&PathBuf> =
+            self.all_paths().into_iter().map(|(_, path)| path).collect();
+
+        for path in &tracked {
+            let absolute = self.path.join(path);
+            if fs::symlink_metadata(&absolute).is_err() {
+                issues.push(HealthIssue {
+                    severity: HealthSeverity::Error,
+                    check: HealthCheck::MissingEntry,
+                    path: path.to_path_buf(),
+                });
+                continue;
+            }
+
+            let has_children = tracked
+                .iter()
+                .any(|other| other.parent() == Some(path.as_path()));
+            if has_children && !absolute.is_dir() {
+                issues.push(HealthIssue {
+                    severity: HealthSeverity::Error,
+                    check: HealthCheck::KindMismatch,
+                    path: path.to_path_buf(),
+                });
+            }
+        }
+
+        for path in self.collect_paths_in_scope(&self.path, true)? {
+            if !tracked.contains(&path) {
+                issues.push(HealthIssue {
+                    severity: HealthSeverity::Warning,
+                    check: HealthCheck::UntrackedItem,
+                    path,
+                });
+            }
+        }
+
+        for temp_file in find_temp_files(&self.path)? {
+            issues.push(HealthIssue {
+                severity: HealthSeverity::Warning,
+                check: HealthCheck::OrphanedTempFile,
+                path: temp_file.strip_prefix(&self.path)?.to_path_buf(),
+            });
+        }
+
+        for directory in self.tracked_directories() {
+            let manifest_path = self.path.join(&directory).join(MANIFEST_FILE_NAME);
+            let Ok(bytes) = fs::read(&manifest_path) else {
+                continue;
+            };
+            let Ok(manifest) = serde_json::from_slice::<DirectoryManifest>(&bytes) else {
+                issues.push(HealthIssue {
+                    severity: HealthSeverity::Warning,
+                    check: HealthCheck::UnreadableManifest,
+                    path: directory.join(MANIFEST_FILE_NAME),
+                });
+                continue;
+            };
+
+            for entry in manifest.get_entries() {
+                let Some(hash) = &entry.hash else {
+                    continue;
+                };
+                let path = directory.join(&entry.name);
+                let absolute = self.path.join(&path);
+                let Ok(metadata) = fs::metadata(&absolute) else {
+                    continue;
+                };
+                if !metadata.is_file() || format!("{:016x}", fnv1a_file(&absolute)?) == *hash {
+                    continue;
+                }
+
+                let modified = metadata
+                    .modified()
+                    .ok()
+                    .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                    .map(|duration| duration.as_nanos() as u64);
+                let unchanged = entry.size == metadata.len()
+                    && entry.modified.is_some()
+                    && entry.modified == modified;
+
+                issues.push(if unchanged {
+                    HealthIssue {
+                        severity: HealthSeverity::Error,
+                        check: HealthCheck::ChecksumMismatch,
+                        path,
+                    }
+                } else {
+                    HealthIssue {
+                        severity: HealthSeverity::Info,
+                        check: HealthCheck::StaleManifest,
+                        path,
+                    }
+                });
+            }
+        }
+
+        issues.sort_by(|a, b| {
+            b.severity
+                .cmp(&a.severity)
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        Ok(HealthReport { issues })
+    }
+
+    /// Sets the rule used by `apply_tiering` to move files to secondary storage.
+    ///
+    /// # Parameters
+    /// - `rule`: **`TierRule`** with the secondary location and what to move there.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - the secondary location is inside the database.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, TierRule};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.set_tier_rule(
+    ///         TierRule::new("/mnt/hdd/fdb")
+    ///             .with_min_size(512 * 1024 * 1024)
+    ///             .with_extensions(["iso", "mkv"]),
+    ///     )?;
+    ///     let moved = manager.apply_tiering()?;
+    ///     println!("{moved} files moved to the HDD");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_tier_rule(&mut self, rule: TierRule) -> Result<(), DatabaseError> {
+        let location = if rule.location.is_absolute() {
+            rule.location.clone()
+        } else {
+            current_dir()?.join(&rule.location)
+        };
+
+        if location.starts_with(&self.path) {
+            return Err(DatabaseError::ExportDestinationInsideDatabase(location));
+        }
+
+        self.tier_rule = Some(TierRule { location, ..rule });
+        Ok(())
+    }
+
+    /// Removes the tier rule.
+    ///
+    /// Files already in secondary storage stay there, but are no longer recognized as tiered,
+    /// so `delete` only removes their links. Clear the rule only after moving them back.
+    pub fn clear_tier_rule(&mut self) {
+        self.tier_rule = None;
+    }
+
+    /// Returns the current tier rule, if any.
+    pub fn get_tier_rule(&self) -> Option<&TierRule> {
+        self.tier_rule.as_ref()
+    }
+
+    /// Moves every tracked file matching the tier rule to secondary storage.
+    ///
+    /// Each file is moved to `<location>/<database name>/<relative path>` and replaced by a
+    /// symbolic link, so its **`ItemId`** and path stay the same. Deleting a tiered item also
+    /// deletes its data in secondary storage. Atomic overwrites replace the link with a regular
+    /// file, which brings the item back to primary storage until the next `apply_tiering`.
+    ///
+    /// Returns how many files were moved. Does nothing when no rule is set.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - reading metadata, moving a file, or creating a link fails.
+    pub fn apply_tiering(&mut self) -> Result<usize, DatabaseError> {
+        self.flush()?;
+
+        let Some(rule) = &self.tier_rule else {
+            return Ok(0);
+        };
+        let tier_root = rule.location.join(os_str_to_string(self.path.file_name())?);
+
+        let mut moved = Vec::new();
+        for (_, relative) in self.all_paths() {
+            let absolute = self.path.join(relative);
+            let Ok(metadata) = fs::symlink_metadata(&absolute) else {
+                continue;
+            };
+            if !metadata.is_file() || !rule.matches(relative, metadata.len()) {
+                continue;
+            }
+
+            let target = tier_root.join(relative);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            if fs::rename(&absolute, &target).is_err() {
+                fs::copy(&absolute, &target)?;
+                remove_file(&absolute)?;
+            }
+            if let Err(error) = create_symlink(&target, &absolute) {
+                if fs::rename(&target, &absolute).is_err() {
+                    fs::copy(&target, &absolute)?;
+                    remove_file(&target)?;
+                }
+                return Err(error);
+            }
+            moved.push(relative.clone());
+        }
+
+        if !moved.is_empty() {
+            let touched: Vec<&Path> = moved.iter().map(|path| path.as_path()).collect();
+            self.after_mutation(&touched)?;
+        }
+
+        Ok(moved.len())
+    }
+
+    /// Returns which **`StorageTier`** holds the data of `id`.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` cannot be found.
+    pub fn get_storage_tier(&self, id: impl Into<ItemId>) -> Result<StorageTier, DatabaseError> {
+        let absolute = self.locate_absolute(id)?;
+        Ok(match self.tiered_target(&absolute) {
+            Some(_) => StorageTier::Secondary,
+            None => StorageTier::Primary,
+        })
+    }
+
+    /// Exposes an external directory inside the database without copying it.
+    ///
+    /// Creates the directory `mount_id` under `parent`, recreates the external directory tree
+    /// inside it, and links every file to its external original. Every created item is added
+    /// to the `index`, so it resolves like any other item. The external directory is treated
+    /// as read-only: writing to a linked file first replaces the link with a local copy, and
+    /// deleting only removes the link. The list of attached directories is kept in memory, so
+    /// in-place appends only materialize files for mounts attached by this manager.
+    ///
+    /// Returns how many items were attached below `mount_id`.
+    ///
+    /// # Parameters
+    /// - `path`: external directory to attach.
+    /// - `mount_id`: **`ItemId`** for the new mount directory.
+    /// - `parent`: destination parent directory item (or `ItemId::database_id()`).
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `path` points inside the database or is not a directory,
+    /// - `mount_id` is root or already exists under `parent`,
+    /// - `parent` cannot be found or is not a directory,
+    /// - creating directories or links fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.attach_external(
+    ///         "/srv/shared/assets",
+    ///         ItemId::id("assets"),
+    ///         ItemId::database_id(),
+    ///     )?;
+    ///     // Reads go to the shared library; this write creates a local copy first.
+    ///     manager.overwrite_existing(ItemId::id("logo.png"), [0_u8; 4])?;
+    ///     assert!(!manager.is_external(ItemId::id("logo.png"))?);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn attach_external(
+        &mut self,
+        path: impl AsRef<Path>,
+        mount_id: impl Into<ItemId>,
+        parent: impl Into<ItemId>,
+    ) -> Result<usize, DatabaseError> {
+        let source = {
+            let path = path.as_ref();
+            if path.is_absolute() {
+                path.to_path_buf()
+            } else {
+                current_dir()?.join(path)
+            }
+        };
+        let mount_id = self.normalize_id(mount_id.into());
+        let parent = parent.into();
+
+        if source.starts_with(&self.path) {
+            return Err(DatabaseError::ImportSourceInsideDatabase(source));
+        }
+        if !source.is_dir() {
+            return Err(DatabaseError::NotADirectory(source));
+        }
+        if mount_id.get_name().is_empty() {
+            return Err(DatabaseError::RootIdUnsupported);
+        }
+        validate_item_name(mount_id.get_name())?;
+
+        let parent_absolute = self.locate_absolute(&parent)?;
+        if !parent_absolute.is_dir() {
+            return Err(DatabaseError::NotADirectory(parent_absolute));
+        }
+        let mount_absolute = parent_absolute.join(mount_id.get_name());
+        let mount_relative = self.locate_relative(&parent)?.join(mount_id.get_name());
+        if mount_absolute.exists() || self.path_exists_in_index(&mount_relative) {
+            return Err(DatabaseError::IdAlreadyExists(mount_id.as_string()));
+        }
+
+        create_dir(&mount_absolute)?;
+        let mut attached = Vec::new();
+        let mut stack = vec![PathBuf::new()];
+        while let Some(relative) = stack.pop() {
+            for entry in fs::read_dir(source.join(&relative))? {
+                let entry = entry?;
+                let entry_relative = relative.join(entry.file_name());
+                let local = mount_absolute.join(&entry_relative);
+
+                if entry.path().is_dir() {
+                    create_dir(&local)?;
+                    stack.push(entry_relative.clone());
+                } else {
+                    create_symlink(&entry.path(), &local)?;
+                }
+                attached.push(entry_relative);
+            }
+        }
+
+        self.insert_path_for_id(&mount_id, mount_relative.clone())?;
+        attached.sort();
+        for relative in &attached {
+            let name = os_str_to_string(relative.file_name())?;
+            self.insert_generated_path(name, mount_relative.join(relative));
+        }
+        self.mounts.push(source);
+        self.emit_created(&mount_id, &mount_relative);
+
+        self.after_mutation(&[&mount_relative])?;
+
+        Ok(attached.len())
+    }
+
+    /// Returns `true` when the data of `id` still lives in an attached external directory.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` cannot be found.
+    pub fn is_external(&self, id: impl Into<ItemId>) -> Result<bool, DatabaseError> {
+        let absolute = self.locate_absolute(id)?;
+        Ok(self.external_target(&absolute).is_some())
+    }
+
+    /// Reads a managed file and decodes it by content type.
+    ///
+    /// The extension decides first: `.json` files are parsed as JSON and common binary extensions
+    /// (`bin`, `png`, `zip`, and similar) stay raw bytes. Anything else is sniffed: data without
+    /// NUL bytes that is valid UTF-8 is text, and extensionless text that parses as a JSON object
+    /// or array is JSON. A `.json` file that does not parse falls back to sniffing, so this never
+    /// fails on decoding.
+    ///
+    /// # Parameters
+    /// - `id`: target file **`ItemId`**.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` cannot be found,
+    /// - `id` points to a directory,
+    /// - file reading fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{AutoContents, DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let manager = DatabaseManager::create_database(".", "database")?;
+    ///     match manager.read_auto(ItemId::id("config.json"))? {
+    ///         AutoContents::Json(value) => println!("{value:#}"),
+    ///         AutoContents::Text(text) => println!("{text}"),
+    ///         AutoContents::Binary(bytes) => println!("{} bytes", bytes.len()),
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn read_auto(&self, id: impl Into<ItemId>) -> Result<AutoContents, DatabaseError> {
+        let id = id.into();
+        let path = self.locate_absolute(&id)?;
+        let bytes = self.read_existing(id)?;
+
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+
+        match extension.as_deref() {
+            Some("json") => {
+                if let Ok(value) = serde_json::from_slice(&bytes) {
+                    return Ok(AutoContents::Json(value));
+                }
+            }
+            Some(extension) if BINARY_EXTENSIONS.contains(&extension) => {
+                return Ok(AutoContents::Binary(bytes));
+            }
+            _ => {}
+        }
+
+        if bytes.contains(&0) {
+            return Ok(AutoContents::Binary(bytes));
+        }
+        match String::from_utf8(bytes) {
+            Ok(text) => {
+                let looks_like_json = extension.is_none()
+                    && matches!(text.trim_start().as_bytes().first(), Some(b'{' | b'['));
+                if looks_like_json && let Ok(value) = serde_json::from_str(&text) {
+                    return Ok(AutoContents::Json(value));
+                }
+                Ok(AutoContents::Text(text))
+            }
+            Err(error) => Ok(AutoContents::Binary(error.into_bytes())),
+        }
+    }
+
+    /// Overwrites a managed file with `contents` decoded by `read_auto`.
+    ///
+    /// JSON is written pretty-printed, text as UTF-8, and binary data as is. Each write is an
+    /// atomic replace through `overwrite_existing`.
+    ///
+    /// # Parameters
+    /// - `id`: target file **`ItemId`**.
+    /// - `contents`: data to write.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - JSON serialization fails,
+    /// - finding `id` or overwriting the file fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{AutoContents, DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("notes.txt"), ItemId::database_id())?;
+    ///     manager.write_auto(ItemId::id("notes.txt"), &AutoContents::Text("hello".into()))?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn write_auto(
+        &self,
+        id: impl Into<ItemId>,
+        contents: &AutoContents,
+    ) -> Result<(), DatabaseError> {
+        match contents {
+            AutoContents::Json(value) => self.overwrite_existing_json(id, value, true),
+            AutoContents::Text(text) => self.overwrite_existing(id, text),
+            AutoContents::Binary(bytes) => self.overwrite_existing(id, bytes),
+        }
+    }
+
+    /// Deletes staging files left behind by interrupted atomic writes and returns their absolute
+    /// paths.
+    ///
+    /// Staging files end in `TEMP_FILE_SUFFIX`, are never indexed, and are skipped by scans.
+    /// Taking `&mut self` guarantees no `ItemWriter` or other write of this manager is still
+    /// using one. This is the one temp file cleanup: `create_database_with_report` and
+    /// `open_with_report` run it on open with `TempFilePolicy::Delete`, and `gc` runs it as its
+    /// first step.
+    ///
+    /// # Errors
+    /// Returns an error if walking the database or deleting a file fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     let removed = manager.cleanup_temp_files()?;
+    ///     println!("removed {} temp files", removed.len());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn cleanup_temp_files(&mut self) -> Result<Vec<PathBuf>, DatabaseError> {
+        Ok(self
+            .sweep_temp_files(false, None)?
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect())
+    }
+
+    /// Deletes data nothing in the live `index` refers to, and reports the space reclaimed.
+    ///
+    /// Collected are:
+    /// - staging files left by interrupted atomic writes, as `cleanup_temp_files` does,
+    /// - manifest files (`MANIFEST_FILE_NAME`) in directories that are no longer tracked or
+    ///   where manifests are disabled,
+    /// - backups beyond `keep_last` when a **`BackupPolicy`** is set,
+    /// - trashed items older than `GcOptions::with_trash_older_than`, when set.
+    ///
+    /// Tracked items are never touched.
+    ///
+    /// # Parameters
+    /// - `options`: **`GcOptions`** with dry-run mode and a cancel flag.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - walking the database or backup directory fails,
+    /// - deleting an entry fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, GcOptions};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     let preview = manager.gc(GcOptions::new().with_dry_run(true))?;
+    ///     println!("{} bytes can be reclaimed", preview.get_reclaimed_bytes());
+    ///     manager.gc(GcOptions::new())?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn gc(&mut self, options: GcOptions) -> Result<GcReport, DatabaseError> {
+        let mut report = GcReport {
+            dry_run: options.dry_run,
+            ..GcReport::default()
+        };
+
+        for (temp_file, size) in self.sweep_temp_files(options.dry_run, Some(&options))? {
+            report.reclaimed_bytes += size;
+            report.temp_files.push(temp_file);
+        }
+        if options.is_cancelled() {
+            return Ok(report);
+        }
+
+        let tracked = self.tracked_directories();
+        for manifest in find_manifest_files(&self.path)? {
+            if options.is_cancelled() {
+                return Ok(report);
+            }
+            let directory = manifest
+                .parent()
+                .unwrap_or(&self.path)
+                .strip_prefix(&self.path)?;
+            if tracked.iter().any(|tracked| tracked == directory)
+                && self.manifests_enabled_for(directory)
+            {
+                continue;
+            }
+            report.reclaimed_bytes += self.collect_garbage(&manifest, options.dry_run)?;
+            report.manifests.push(manifest);
+        }
+
+        if let Some(policy) = &self.backup_policy
+            && policy.destination.is_dir()
+        {
+            let prefix = format!("{}-backup-", os_str_to_string(self.path.file_name())?);
+            let mut backups = self.list_backups(&policy.destination, &prefix)?;
+            let excess = backups.len().saturating_sub(policy.keep_last);
+            for (_, backup) in backups.drain(..excess) {
+                if options.is_cancelled() {
+                    return Ok(report);
+                }
+                report.reclaimed_bytes += self.collect_garbage(&backup, options.dry_run)?;
+                report.backups.push(backup);
+            }
+        }
+
+        if let Some(older_than) = options.trash_older_than {
+            for (path, size) in self.purge_trash(older_than, options.dry_run, Some(&options))? {
+                report.reclaimed_bytes += size;
+                report.trash.push(path);
+            }
+            if options.is_cancelled() {
+                return Ok(report);
+            }
+        }
+
+        report.complete = true;
+        Ok(report)
+    }
+
+    /// Returns every tracked item matched by `selector`, in path order.
+    ///
+    /// Use this to preview what `delete_matching` or `export_matching` will touch.
+    ///
+    /// # Parameters
+    /// - `selector`: **`Selector`** (or a pattern string) to resolve against the `index`.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - the selector parent cannot be found,
+    /// - the selector parent points to a file instead of a directory.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("a.log"), ItemId::database_id())?;
+    ///     for id in manager.select("*.log")? {
+    ///         println!("would delete {id:?}");
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn select(&self, selector: impl Into<Selector>) -> Result<Vec<ItemId>, DatabaseError> {
+        Ok(self
+            .matching_paths(&selector.into())?
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect())
+    }
+
+    /// Returns every tracked item whose relative path matches the glob `pattern`, in path order.
+    ///
+    /// Unlike `select`, the pattern is always matched against the whole path from the database
+    /// root, component by component: `*` and `?` stay inside one component and a `**` component
+    /// matches any number of components. So `"*.csv"` only matches files at the top level, while
+    /// `"**/*.csv"` matches them at any depth.
+    ///
+    /// # Parameters
+    /// - `pattern`: `/`-separated glob such as `"reports/**/*.csv"`.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new_all("reports/2024/q1.csv", ItemId::database_id())?;
+    ///     for id in manager.glob("reports/**/*.csv") {
+    ///         println!("{id:?}");
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn glob(&self, pattern: impl AsRef<str>) -> Vec<ItemId> {
+        let pattern = pattern.as_ref();
+        let mut matches: Vec<(ItemId, &PathBuf)> = self
+            .all_paths()
+            .into_iter()
+            .filter(|(_, path)| path_matches(pattern, path))
+            .collect();
+
+        matches.sort_by(|(a_id, a_path), (b_id, b_path)| {
+            a_path.cmp(b_path).then_with(|| a_id.cmp(b_id))
+        });
+        matches.into_iter().map(|(id, _)| id).collect()
+    }
+
+    /// Deletes every tracked item matched by `selector`.
+    ///
+    /// Matches are resolved once up front, then deleted deepest first, so a matched directory
+    /// is removed after its matched children. Returns one **`DeleteSummary`** per match, in
+    /// deletion order. A failed delete stops the run; items deleted before it stay deleted.
+    ///
+    /// # Parameters
+    /// - `selector`: **`Selector`** (or a pattern string) to resolve against the `index`.
+    /// - `force`: when deleting directories, controls recursive vs empty-only behavior.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `select` fails,
+    /// - deleting one of the matches fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ForceDeletion, ItemId, Selector};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("logs"), ItemId::database_id())?;
+    ///     manager.write_new(ItemId::id("a.log"), ItemId::id("logs"))?;
+    ///     let selector = Selector::new("*.log").with_parent(ItemId::id("logs"));
+    ///     let deleted = manager.delete_matching(selector, ForceDeletion::NoForce)?;
+    ///     println!("deleted {} items", deleted.len());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn delete_matching(
+        &mut self,
+        selector: impl Into<Selector>,
+        force: impl Into<bool>,
+    ) -> Result<Vec<DeleteSummary>, DatabaseError> {
+        let force = force.into();
+        let matches = self.matching_paths(&selector.into())?;
+
+        let mut summaries = Vec::with_capacity(matches.len());
+        for (_, path) in matches.into_iter().rev() {
+            if let Some(id) = self.id_for_path(&path) {
+                summaries.push(self.delete(id, force)?);
+            }
+        }
+
+        Ok(summaries)
+    }
+
+    /// Exports every tracked item matched by `selector` to an external destination directory.
+    ///
+    /// Matches inside another matched directory are exported as part of that directory.
+    /// Returns one **`ExportSummary`** per exported item, in path order. A failed export stops
+    /// the run; items exported before it stay exported.
+    ///
+    /// # Parameters
+    /// - `selector`: **`Selector`** (or a pattern string) to resolve against the `index`.
+    /// - `to`: external destination directory path.
+    /// - `mode`: copy or move behavior.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `select` fails,
+    /// - exporting one of the matches fails (see `export_item`).
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ExportMode, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("a.log"), ItemId::database_id())?;
+    ///     let exported = manager.export_matching("*.log", "./archive", ExportMode::Move)?;
+    ///     println!("exported {} items", exported.len());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn export_matching(
+        &mut self,
+        selector: impl Into<Selector>,
+        to: impl AsRef<Path>,
+        mode: ExportMode,
+    ) -> Result<Vec<ExportSummary>, DatabaseError> {
+        let to = to.as_ref();
+        let mut exported: Vec<PathBuf> = Vec::new();
+        for (_, path) in self.matching_paths(&selector.into())? {
+            if !exported.iter().any(|ancestor| path.starts_with(ancestor)) {
+                exported.push(path);
+            }
+        }
+
+        let mut summaries = Vec::with_capacity(exported.len());
+        for path in exported {
+            if let Some(id) = self.id_for_path(&path) {
+                summaries.push(self.export_item(id, to, mode.clone())?);
+            }
+        }
+
+        Ok(summaries)
+    }
+
+    /// Turns the move journal on or off.
+    ///
+    /// While enabled, `migrate_item`, `copy_to`, and the `Move` modes of `export_item`,
+    /// `export_map`, and `import_item` record each move in `JOURNAL_FILE_NAME` before touching
+    /// the filesystem. If the process dies part way through, the next `create_database` finishes
+    /// the move when the destination already holds a complete copy, and otherwise rolls it
+    /// back, so an item is never left duplicated or missing.
+    ///
+    /// Journals left by an earlier session are replayed on open whether or not this is enabled.
+    ///
+    /// # Parameters
+    /// - `enabled`: whether moves are journaled.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.set_journal(true);
+    ///     manager.write_new(ItemId::id("archive"), ItemId::database_id())?;
+    ///     manager.write_new(ItemId::id("a.txt"), ItemId::database_id())?;
+    ///     manager.migrate_item(ItemId::id("a.txt"), ItemId::id("archive"))?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_journal(&mut self, enabled: bool) {
+        self.journal = enabled;
+    }
+
+    /// Returns `true` when moves are journaled.
+    pub fn get_journal(&self) -> bool {
+        self.journal
+    }
+
+    /// Makes `delete` send items to the operating system's trash instead of removing them.
+    ///
+    /// Items then show up in the desktop trash (Freedesktop trash on Linux and BSD, the Finder
+    /// trash on macOS, the Recycle Bin on Windows) where users can restore them. The index
+    /// entries are dropped just like a normal delete. Tier symlinks and the database root are
+    /// still removed normally. Requires the `os-trash` feature.
+    ///
+    /// On Linux and BSD, items on a different filesystem than the home trash go to
+    /// `.Trash-<uid>` at the root of their own filesystem. On macOS, `delete` runs `osascript`
+    /// to ask Finder to trash the item, and on Windows it runs `powershell`; both must be on
+    /// `PATH`, and their failures are returned as `DatabaseError::Io`.
+    ///
+    /// # Parameters
+    /// - `enabled`: whether `delete` uses the OS trash.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # #[cfg(feature = "os-trash")]
+    /// # fn main() -> Result<(), file_database::DatabaseError> {
+    /// use file_database::{DatabaseManager, ForceDeletion, ItemId};
+    ///
+    /// let mut manager = DatabaseManager::create_database(".", "database")?;
+    /// manager.set_os_trash(true);
+    /// manager.write_new(ItemId::id("draft.txt"), ItemId::database_id())?;
+    /// manager.delete(ItemId::id("draft.txt"), ForceDeletion::Force)?;
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "os-trash"))]
+    /// # fn main() {}
+    /// ```
+    #[cfg(feature = "os-trash")]
+    pub fn set_os_trash(&mut self, enabled: bool) {
+        self.os_trash = enabled;
+    }
+
+    /// Returns `true` when `delete` sends items to the operating system's trash.
+    #[cfg(feature = "os-trash")]
+    pub fn get_os_trash(&self) -> bool {
+        self.os_trash
+    }
+
+    /// Sets how many worker threads recursive scans use to walk the tree.
+    ///
+    /// Applies to `scan_for_changes`, `reload_subtree`, `self_check`, and every other recursive
+    /// walk of the tree. Each level of the tree is split across the workers, so wide trees
+    /// benefit the most. A `threads` value of `0` (the default) uses the available parallelism reported by
+    /// the OS; `1` walks the tree on the calling thread. Requires the `parallel` feature, which
+    /// also makes `create_database` scan in parallel.
+    ///
+    /// # Parameters
+    /// - `threads`: maximum number of worker threads.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # #[cfg(feature = "parallel")]
+    /// # fn main() -> Result<(), file_database::DatabaseError> {
+    /// use file_database::{DatabaseManager, ItemId, ScanPolicy};
+    ///
+    /// let mut manager = DatabaseManager::create_database(".", "database")?;
+    /// manager.set_scan_threads(8);
+    /// manager.scan_for_changes(ItemId::database_id(), ScanPolicy::AddNew, true)?;
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "parallel"))]
+    /// # fn main() {}
+    /// ```
+    #[cfg(feature = "parallel")]
+    pub fn set_scan_threads(&mut self, threads: usize) {
+        self.scan_threads = threads;
+    }
+
+    /// Returns the scan thread setting (`0` means the available parallelism).
+    #[cfg(feature = "parallel")]
+    pub fn get_scan_threads(&self) -> usize {
+        self.scan_threads
+    }
+
+    /// Turns on the session undo history and sets how many operations it keeps.
+    ///
+    /// While the depth is above `0`, `write_new` and the other create methods (`write_new_all`,
+    /// `write_new_with_contents` and its typed forms, batch creates), `rename`, `migrate_item`,
+    /// `delete`, and `overwrite_existing` (including the typed helpers built on it) are recorded
+    /// so `undo` and `redo` can reverse and repeat them. `write_new_all` records one entry per
+    /// item it creates. A transactional batch that rolls back records nothing. Deleted items and previous file contents are parked
+    /// in `UNDO_DIR_NAME` inside the database until they fall out of the history, so deleting
+    /// frees no space until then. The parked data is removed when the manager is dropped.
+    ///
+    /// A depth of `0` turns the history off and discards it. Lowering the depth drops the
+    /// oldest entries.
+    ///
+    /// # Parameters
+    /// - `depth`: maximum number of operations that can be undone.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - removing parked data of dropped entries fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ForceDeletion, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.set_undo_depth(50)?;
+    ///     manager.write_new(ItemId::id("a.txt"), ItemId::database_id())?;
+    ///     manager.overwrite_existing(ItemId::id("a.txt"), b"draft")?;
+    ///     manager.delete(ItemId::id("a.txt"), ForceDeletion::Force)?;
+    ///     manager.undo()?;
+    ///     manager.undo()?;
+    ///     assert!(manager.read_existing(ItemId::id("a.txt"))?.is_empty());
+    ///     manager.redo()?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_undo_depth(&mut self, depth: usize) -> Result<(), DatabaseError> {
+        self.undo.depth = depth;
+        if depth == 0 {
+            return self.clear_undo_history();
+        }
+
+        let dropped: Vec<UndoAction> = {
+            let mut stacks = self.undo.lock();
+            let excess = stacks.undo.len().saturating_sub(depth);
+            stacks.undo.drain(..excess).collect()
+        };
+        for action in &dropped {
+            self.discard_stash(action)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns how many operations the undo history keeps. `0` means it is off.
+    pub fn get_undo_depth(&self) -> usize {
+        self.undo.depth
+    }
+
+    /// Returns how many operations `undo` can currently reverse.
+    pub fn get_undo_count(&self) -> usize {
+        self.undo.lock().undo.len()
+    }
+
+    /// Returns how many undone operations `redo` can currently repeat.
+    pub fn get_redo_count(&self) -> usize {
+        self.undo.lock().redo.len()
+    }
+
+    /// Reverses the most recent recorded operation.
+    ///
+    /// Returns `false` when there is nothing to undo. Restored items are indexed again under
+    /// their names; the index part of their **`ItemId`** may differ from before.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - the item the operation left behind is gone (`DatabaseError::NoMatchingID`),
+    /// - another item now occupies the path to restore (`DatabaseError::IdAlreadyExists`),
+    /// - filesystem operations fail.
+    ///
+    /// On error the operation stays on the undo stack.
+    ///
+    /// # Examples
+    /// ```
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let parent = std::env::temp_dir().join("file_database_undo_create");
+    ///     let _ = std::fs::remove_dir_all(&parent);
+    ///     std::fs::create_dir_all(&parent)?;
+    ///     let mut manager = DatabaseManager::create_database(&parent, "database")?;
+    ///     manager.set_undo_depth(10)?;
+    ///
+    ///     let created =
+    ///         manager.write_new_with_contents(ItemId::id("notes.txt"), ItemId::database_id(), "hi")?;
+    ///     assert!(manager.undo()?);
+    ///     assert!(!created.get_path().exists());
+    ///     assert!(manager.redo()?);
+    ///     assert_eq!(manager.read_existing(ItemId::id("notes.txt"))?, b"hi");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn undo(&mut self) -> Result<bool, DatabaseError> {
+        self.flush()?;
+
+        let Some(action) = self.undo.lock().undo.pop_back() else {
+            return Ok(false);
+        };
+
+        match self.apply_undo_action(&action, true) {
+            Ok(()) => {
+                self.undo.lock().redo.push(action);
+                Ok(true)
+            }
+            Err(error) => {
+                self.undo.lock().undo.push_back(action);
+                Err(error)
+            }
+        }
+    }
+
+    /// Repeats the most recently undone operation.
+    ///
+    /// Returns `false` when there is nothing to redo. Recording a new operation clears the
+    /// redo stack.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - the item to act on is gone (`DatabaseError::NoMatchingID`),
+    /// - another item now occupies the target path (`DatabaseError::IdAlreadyExists`),
+    /// - filesystem operations fail.
+    ///
+    /// On error the operation stays on the redo stack.
+    pub fn redo(&mut self) -> Result<bool, DatabaseError> {
+        self.flush()?;
+
+        let Some(action) = self.undo.lock().redo.pop() else {
+            return Ok(false);
+        };
+
+        match self.apply_undo_action(&action, false) {
+            Ok(()) => {
+                self.undo.lock().undo.push_back(action);
+                Ok(true)
+            }
+            Err(error) => {
+                self.undo.lock().redo.push(action);
+                Err(error)
+            }
+        }
+    }
+
+    /// Discards the undo and redo stacks along with all parked data. The depth is kept.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - removing the parked data fails.
+    pub fn clear_undo_history(&mut self) -> Result<(), DatabaseError> {
+        *self.undo.lock() = UndoStacks::default();
+
+        let stash_root = self.path.join(UNDO_DIR_NAME);
+        if stash_root.is_dir() {
+            remove_dir_all(stash_root)?;
+        }
+
+        Ok(())
+    }
+
+    /// Describes every tracked item as a JSON interchange document.
+    ///
+    /// Unlike `save_index`, the format is stable and meant for tools written in other
+    /// languages:
+    ///
+    /// ```json
+    /// {
+    ///   "format": "file_database.index",
+    ///   "version": 1,
+    ///   "items": [
+    ///     { "name": "docs", "index": 0, "path": "docs", "kind": "directory", "modified": 1700000000 },
+    ///     { "name": "a.txt", "index": 0, "path": "docs/a.txt", "kind": "file",
+    ///       "size": 12, "modified": 1700000000, "hash": "af63bd4c8601b7df" }
+    ///   ]
+    /// }
+    /// ```
+    ///
+    /// - `name` and `index` form the **`ItemId`**; `name` is always the last path component.
+    /// - `path` is relative to the database root and always uses `/` separators.
+    /// - `kind` is `"file"` or `"directory"`.
+    /// - `size` (bytes), `modified` (unix seconds), and `hash` (64-bit FNV-1a of the contents as
+    ///   16 lowercase hex digits, the same hash used by manifests) are left out when unknown;
+    ///   directories never have `size` or `hash`.
+    ///
+    /// Items are listed in path order, so parents come before their children.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - a tracked path is not valid UTF-8,
+    /// - hashing a file fails,
+    /// - JSON serialization fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("a.txt"), ItemId::database_id())?;
+    ///     std::fs::write("./layout.json", manager.export_index_json()?)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn export_index_json(&self) -> Result<String, DatabaseError> {
+        let mut paths = self.all_paths();
+        paths.sort_by(|(a_id, a_path), (b_id, b_path)| {
+            a_path.cmp(b_path).then_with(|| a_id.cmp(b_id))
+        });
+
+        let mut items = Vec::with_capacity(paths.len());
+        for (id, path) in paths {
+            let components = path
+                .components()
+                .map(|component| component.as_os_str().to_str())
+                .collect::<Option<Vec<&str>>>()
+                .ok_or(DatabaseError::OsStringConversion)?;
+
+            let absolute = self.path.join(path);
+            let metadata = fs::metadata(&absolute).ok();
+            let is_dir = metadata.as_ref().is_some_and(|metadata| metadata.is_dir());
+            let modified = metadata
+                .as_ref()
+                .and_then(|metadata| metadata.modified().ok())
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|duration| duration.as_secs());
+            let (size, hash) = match &metadata {
+                Some(metadata) if !is_dir => (
+                    Some(metadata.len()),
+                    Some(format!("{:016x}", fnv1a_file(&absolute)?)),
+                ),
+                _ => (None, None),
+            };
+
+            items.push(IndexDocumentItem {
+                name: id.get_name().to_string(),
+                index: id.get_index(),
+                path: components.join("/"),
+                kind: match is_dir {
+                    true => IndexDocumentKind::Directory,
+                    false => IndexDocumentKind::File,
+                },
+                size,
+                modified,
+                hash,
+            });
+        }
+
+        Ok(serde_json::to_string_pretty(&IndexDocument {
+            format: INDEX_DOCUMENT_FORMAT.to_string(),
+            version: INDEX_DOCUMENT_VERSION,
+            items,
+        })?)
+    }
+
+    /// Creates and indexes the layout described by a document in the `export_index_json`
+    /// format.
+    ///
+    /// Missing directories are created, missing files are created empty, and items that already
+    /// exist on disk are kept as they are. Each item is indexed under its `name` and `index` when
+    /// that **`ItemId`** is free, and under a generated index otherwise. Paths that are already
+    /// tracked keep their current **`ItemId`**. Parent directories that the document does not
+    /// list are created and indexed too. `size`, `modified`, and `hash` are informational and
+    /// may be left out.
+    ///
+    /// The whole document is validated before anything is created. Returns how many items were
+    /// newly indexed.
+    ///
+    /// # Parameters
+    /// - `json`: the document text.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - the document is not valid JSON,
+    /// - `format` or `version` is not supported, a `path` is empty, absolute, or contains an
+    ///   invalid component, or `name` is not the last path component
+    ///   (`DatabaseError::InvalidIndexDocument`),
+    /// - an item exists on disk with a different kind (`DatabaseError::NotADirectory` or
+    ///   `DatabaseError::NotAFile`),
+    /// - filesystem create operations fail.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     let layout = std::fs::read_to_string("./layout.json")?;
+    ///     let added = manager.import_index_json(&layout)?;
+    ///     println!("indexed {added} items");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn import_index_json(&mut self, json: impl AsRef<str>) -> Result<usize, DatabaseError> {
+        let document: IndexDocument = serde_json::from_str(json.as_ref())?;
+        if document.format != INDEX_DOCUMENT_FORMAT {
+            return Err(DatabaseError::InvalidIndexDocument(format!(
+                "unknown format '{}'",
+                document.format
+            )));
+        }
+        if document.version != INDEX_DOCUMENT_VERSION {
+            return Err(DatabaseError::InvalidIndexDocument(format!(
+                "unsupported version {}",
+                document.version
+            )));
+        }
+
+        let mut items = Vec::with_capacity(document.items.len());
+        for item in document.items {
+            let mut path = PathBuf::new();
+            for component in item.path.split('/') {
+                if component.is_empty() || component == "." || component == ".." {
+                    return Err(DatabaseError::InvalidIndexDocument(format!(
+                        "invalid path '{}'",
+                        item.path
+                    )));
+                }
+                ItemId::try_new(component)?;
+                path.push(component);
+            }
+            if path.file_name().and_then(|name| name.to_str()) != Some(item.name.as_str()) {
+                return Err(DatabaseError::InvalidIndexDocument(format!(
+                    "name '{}' does not match path '{}'",
+                    item.name, item.path
+                )));
+            }
+
+            let absolute = self.path.join(&path);
+            let is_dir = item.kind == IndexDocumentKind::Directory;
+            if absolute.exists() && absolute.is_dir() != is_dir {
+                return Err(match is_dir {
+                    true => DatabaseError::NotADirectory(absolute),
+                    false => DatabaseError::NotAFile(absolute),
+                });
+            }
+            items.push((ItemId::with_index(item.name, item.index), path, is_dir));
+        }
+        items.sort_by(|(_, a, _), (_, b, _)| a.cmp(b));
+
+        let mut added = 0;
+        let mut touched = Vec::new();
+        for (id, path, is_dir) in items {
+            added += self.ensure_parent_directories(&path)?;
+
+            if self.path_exists_in_index(&path) {
+                continue;
+            }
+            let absolute = self.path.join(&path);
+            if !absolute.exists() {
+                if is_dir {
+                    create_dir(&absolute)?;
+                } else {
+                    File::create_new(&absolute)?;
+                }
+            }
+
+            let slot_taken = self
+                .items
+                .get(id.get_name())
+                .is_some_and(|paths| paths.get(id.get_index()).is_some());
+            let id = if slot_taken {
+                self.insert_generated_path(id.get_name().to_string(), path.clone())
+            } else {
+                self.insert_path_for_id(&id, path.clone())?;
+                id
+            };
+            self.emit_created(&id, &path);
+            touched.push(path);
+            added += 1;
+        }
+
+        let touched: Vec<&Path> = touched.iter().map(|path| path.as_path()).collect();
+        self.after_mutation(&touched)?;
+
+        Ok(added)
+    }
+
+    /// Moves a file or directory into the trash instead of deleting it.
+    ///
+    /// The item is moved to `TRASH_DIR_NAME` inside the database and removed from the `index`
+    /// along with its descendants. A restore map next to it remembers the **`ItemId`** and path,
+    /// and survives restarts, so `restore_from_trash` can put the item back later. The space is
+    /// only freed by `empty_trash` or `gc`.
+    ///
+    /// # Parameters
+    /// - `id`: item to move to the trash. Root **`ItemId`** is not allowed.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` is root or cannot be found,
+    /// - reading or writing the restore map fails,
+    /// - moving the item fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.write_new(ItemId::id("a.txt"), ItemId::database_id())?;
+    ///     let trashed = manager.delete_to_trash(ItemId::id("a.txt"))?;
+    ///     manager.restore_from_trash(trashed.get_id())?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn delete_to_trash(&mut self, id: impl Into<ItemId>) -> Result<TrashedItem, DatabaseError> {
+        self.flush()?;
+
+        let id = id.into();
+        if id.get_name().is_empty() {
+            return Err(DatabaseError::RootIdUnsupported);
+        }
+
+        let path = self.locate_absolute(&id)?;
+        let relative_path = self.locate_relative(&id)?.into_path_buf();
+
+        let mut entries = self.read_trash_map()?;
+        let slot = entries
+            .iter()
+            .map(|entry| entry.slot + 1)
+            .max()
+            .unwrap_or(0);
+        let deleted_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+
+        let trash = self.path.join(TRASH_DIR_NAME);
+        fs::create_dir_all(&trash)?;
+        fs::rename(&path, trash.join(slot.to_string()))?;
+        self.forget_subtree(&id, &relative_path)?;
+        self.sync_parent_directory(&path)?;
+        self.emit_deleted(&id, &relative_path);
+
+        let entry = TrashEntry {
+            slot,
+            name: id.get_name().to_string(),
+            index: id.get_index(),
+            path: relative_path.clone(),
+            deleted_at,
+        };
+        let trashed = entry.to_trashed_item();
+        entries.push(entry);
+        self.write_trash_map(&entries)?;
+        self.after_mutation(&[&relative_path])?;
+
+        Ok(trashed)
+    }
+
+    /// Moves the most recently trashed item that had `id` back to its original path.
+    ///
+    /// Missing parent directories are recreated and indexed. The item is indexed under `id`
+    /// when that **`ItemId`** is free, and under a generated index otherwise; descendants get
+    /// generated indexes. Returns the **`ItemId`** the item was restored under.
+    ///
+    /// # Parameters
+    /// - `id`: **`ItemId`** the item had when it was trashed (see **`TrashedItem`**).
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - no trashed item had `id` (`DatabaseError::NoMatchingID`),
+    /// - another item now occupies the original path (`DatabaseError::IdAlreadyExists`),
+    /// - reading or writing the restore map or moving the item fails.
+    pub fn restore_from_trash(&mut self, id: impl Into<ItemId>) -> Result<ItemId, DatabaseError> {
+        let id = id.into();
+        let mut entries = self.read_trash_map()?;
+        let position = entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.name == id.get_name() && entry.index == id.get_index())
+            .max_by_key(|(_, entry)| entry.slot)
+            .map(|(position, _)| position)
+            .ok_or_else(|| DatabaseError::NoMatchingID(id.as_string()))?;
+
+        let path = entries[position].path.clone();
+        let absolute = self.path.join(&path);
+        if fs::symlink_metadata(&absolute).is_ok() || self.path_exists_in_index(&path) {
+            return Err(DatabaseError::IdAlreadyExists(id.as_string()));
+        }
+
+        self.ensure_parent_directories(&path)?;
+        let slot = self
+            .path
+            .join(TRASH_DIR_NAME)
+            .join(entries[position].slot.to_string());
+        fs::rename(slot, &absolute)?;
+        self.sync_parent_directory(&absolute)?;
+        entries.remove(position);
+        self.write_trash_map(&entries)?;
+
+        let slot_taken = self
+            .items
+            .get(id.get_name())
+            .is_some_and(|paths| paths.get(id.get_index()).is_some());
+        let restored = if slot_taken {
+            self.insert_generated_path(id.get_name().to_string(), path.clone())
+        } else {
+            self.insert_path_for_id(&id, path.clone())?;
+            id
+        };
+        if absolute.is_dir() && !is_symlink(&absolute) {
+            for descendant in self.collect_paths_in_scope(&absolute, true)? {
+                let name = os_str_to_string(descendant.file_name())?;
+                self.insert_generated_path(name, descendant);
+            }
+        }
+        self.emit_created(&restored, &path);
+        self.after_mutation(&[&path])?;
+
+        Ok(restored)
+    }
+
+    /// Returns every item in the trash, oldest first.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - reading the restore map fails.
+    pub fn get_trash(&self) -> Result<Vec<TrashedItem>, DatabaseError> {
+        Ok(self
+            .read_trash_map()?
+            .iter()
+            .map(TrashEntry::to_trashed_item)
+            .collect())
+    }
+
+    /// Permanently deletes trashed items that were trashed at least `older_than` ago.
+    ///
+    /// `Duration::ZERO` empties the whole trash. Returns how many items were deleted.
+    ///
+    /// # Parameters
+    /// - `older_than`: minimum time an item has spent in the trash.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - reading or writing the restore map fails,
+    /// - deleting a trashed item fails.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use file_database::{DatabaseError, DatabaseManager};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     let removed = manager.empty_trash(Duration::from_secs(30 * 24 * 3600))?;
+    ///     println!("purged {removed} items");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn empty_trash(&mut self, older_than: Duration) -> Result<usize, DatabaseError> {
+        let purged = self.purge_trash(older_than, false, None)?;
+        Ok(purged.len())
+    }
+
+    /// Turns per-operation latency histograms on or off.
+    ///
+    /// While enabled, every call of a **`LatencyOperation`** is timed, including calls that
+    /// fail, and added to that operation's **`LatencyHistogram`**. Turning tracking off keeps
+    /// the histograms collected so far.
+    ///
+    /// # Parameters
+    /// - `enabled`: whether calls are added to the histograms.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use file_database::{DatabaseError, DatabaseManager, ItemId, LatencyOperation};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.set_latency_tracking(true);
+    ///     manager.write_new(ItemId::id("a.txt"), ItemId::database_id())?;
+    ///     let _ = manager.read_existing(ItemId::id("a.txt"))?;
+    ///     if let Some(reads) = manager.get_latency_histogram(LatencyOperation::Read) {
+    ///         println!("p99 read: {:?}", reads.get_percentile(99.0));
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_latency_tracking(&mut self, enabled: bool) {
+        self.latency.lock().tracking = enabled;
+    }
+
+    /// Returns `true` when latency histograms are being collected.
+    pub fn get_latency_tracking(&self) -> bool {
+        self.latency.lock().tracking
+    }
+
+    /// Returns the histogram of `operation`, or `None` when no call of it was measured.
+    pub fn get_latency_histogram(&self, operation: LatencyOperation) -> Option<LatencyHistogram> {
+        self.latency.lock().histograms.get(&operation).cloned()
+    }
+
+    /// Returns every collected histogram, ordered by **`LatencyOperation`**.
+    pub fn get_latency_histograms(&self) -> Vec<LatencyHistogram> {
+        self.latency.lock().histograms.values().cloned().collect()
+    }
+
+    /// Discards all collected histograms.
+    pub fn reset_latency_histograms(&mut self) {
+        self.latency.lock().histograms.clear();
+    }
+
+    /// Sets a callback that receives every **`LatencyOperation`** call taking at least
+    /// `threshold`, with its **`ItemId`**, path, and duration.
+    ///
+    /// This works whether or not `set_latency_tracking` is enabled. Use it to log stalls, for
+    /// example by forwarding them to your logging framework.
+    ///
+    /// # Parameters
+    /// - `threshold`: minimum duration that counts as slow.
+    /// - `handler`: called after each slow call returns.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use file_database::{DatabaseError, DatabaseManager};
+    ///
+    /// fn main() -> Result<(), DatabaseError> {
+    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
+    ///     manager.set_slow_operation_handler(Duration::from_millis(500), |slow| {
+    ///         eprintln!(
+    ///             "{:?} on {:?} ({:?}) took {:?}",
+    ///             slow.get_operation(),
+    ///             slow.get_id(),
+    ///             slow.get_path(),
+    ///             slow.get_duration()
+    ///         );
+    ///     });
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn set_slow_operation_handler(
+        &mut self,
+        threshold: Duration,
+        handler: impl Fn(&SlowOperation) + Send + Sync + 'static,
+    ) {
+        self.latency.lock().slow = Some((threshold, Arc::new(handler)));
+    }
+
+    /// Removes the slow-operation callback.
+    pub fn clear_slow_operation_handler(&mut self) {
+        self.latency.lock().slow = None;
+    }
+
+    /// Returns all stored `(ItemId, relative_path)` pairs.
+    fn all_paths(&self) -> Vec<(ItemId, &PathBuf)> {
+        let mut result = Vec::new();
+
+        for (name, paths) in &self.items {
+            for (index, path) in paths.iter() {
+                result.push((ItemId::with_index(name.clone(), index), path));
+            }
+        }
+
+        result
+    }
+
+    /// Builds the **`TreeNode`** for `id` at `path`, taking its children out of `children`.
+    fn tree_node(
+        &self,
+        id: ItemId,
+        path: &Path,
+        children: &mut HashMap<&Path, Vec<(ItemId, &PathBuf)>>,
+    ) -> TreeNode {
+        let mut own = children.remove(path).unwrap_or_default();
+        own.sort_by(|(a_id, a_path), (b_id, b_path)| {
+            a_path.cmp(b_path).then_with(|| a_id.cmp(b_id))
+        });
+
+        let kind = match fs::metadata(self.path.join(path)) {
+            Ok(metadata) if metadata.is_dir() => ItemKind::Directory,
+            Ok(_) => ItemKind::File,
+            Err(_) if !own.is_empty() || path.extension().is_none() => ItemKind::Directory,
+            Err(_) => ItemKind::File,
+        };
+
+        TreeNode {
+            id,
+            kind,
+            children: own
+                .into_iter()
+                .map(|(child_id, child_path)| self.tree_node(child_id, child_path, children))
+                .collect(),
+        }
+    }
+
+    /// Returns the tracked items below `parent_path`, excluding `parent_path` itself.
+    fn descendant_paths(&self, parent_path: &Path) -> Vec<(ItemId, &PathBuf)> {
+        self.all_paths()
+            .into_iter()
+            .filter(|(_, item_path)| {
+                item_path
+                    .strip_prefix(parent_path)
+                    .is_ok_and(|relative| !relative.as_os_str().is_empty())
+            })
+            .collect()
+    }
+
+    /// Returns the tracked items whose kind on disk is `kind`.
+    fn paths_of_kind(&self, kind: ItemKind) -> Vec<(ItemId, &PathBuf)> {
+        self.all_paths()
+            .into_iter()
+            .filter(|(_, path)| match fs::metadata(self.path.join(path)) {
+                Ok(metadata) => metadata.is_dir() == (kind == ItemKind::Directory),
+                Err(_) => false,
+            })
+            .collect()
+    }
+
+    /// Returns `true` when any stored item already uses `relative_path`.
+    fn path_exists_in_index(&self, relative_path: &Path) -> bool {
+        self.items
+            .values()
+            .any(|paths| paths.iter().any(|(_, path)| path == relative_path))
+    }
+
+    /// Inserts an exact `ItemId` -> path mapping.
+    fn insert_path_for_id(&mut self, id: &ItemId, path: PathBuf) -> Result<(), DatabaseError> {
+        let name = self.index_key(id.get_name()).into_owned();
+        let paths = self.items.entry(name).or_default();
+        if !paths.insert_at(id.get_index(), path.clone()) {
+            return Err(DatabaseError::IdAlreadyExists(id.as_string()));
+        }
+        self.record_insertion(path);
+        Ok(())
+    }
+
+    /// Inserts a generated id for a shared name and returns the generated `ItemId`.
+    fn insert_generated_path(&mut self, name: String, path: PathBuf) -> ItemId {
+        let name = self.index_key(&name).into_owned();
+        let paths = self.items.entry(name.clone()).or_default();
+        let index = paths.push(path.clone());
+        self.record_insertion(path);
+        ItemId::with_index(name, index)
+    }
+
+    /// Removes one exact id entry from the index and prunes empty name buckets.
+    fn remove_id_from_index(&mut self, id: &ItemId) -> Result<(), DatabaseError> {
+        let path = self.resolve_path_by_id(id)?.clone();
+        let name = self.index_key(id.get_name()).into_owned();
+        let should_drop_name = {
+            let paths = self
+                .items
+                .get_mut(&name)
+                .ok_or_else(|| DatabaseError::NoMatchingID(id.as_string()))?;
+
+            if !paths.remove(id.get_index()) {
+                return Err(DatabaseError::NoMatchingID(id.as_string()));
+            }
+
+            paths.is_empty()
+        };
+
+        if should_drop_name {
+            self.items.remove(&name);
+        }
+        self.records.remove(&path);
+
+        Ok(())
+    }
+
+    /// Checks that `id` can be created under `parent` and returns its absolute and relative
+    /// paths.
+    fn new_item_paths(
+        &self,
+        id: &ItemId,
+        parent: &ItemId,
+    ) -> Result<(PathBuf, PathBuf), DatabaseError> {
+        if id.get_name().is_empty() {
+            return Err(DatabaseError::RootIdUnsupported);
+        }
+        validate_item_name(id.get_name())?;
+
+        let absolute_path = self.locate_absolute(parent)?.join(id.get_name());
+        let relative_path = self.locate_relative(parent)?.join(id.get_name());
+
+        if self.path_exists_in_index(&relative_path) {
+            return Err(DatabaseError::IdAlreadyExists(id.as_string()));
+        }
+
+        Ok((absolute_path, relative_path))
+    }
+
+    /// Stores the fetch handler's contents for `id` when it is tracked but missing on disk.
+    fn fetch_if_missing(&self, id: &ItemId) -> Result<(), DatabaseError> {
+        let Some(fetch) = &self.fetch.0 else {
+            return Ok(());
+        };
+        if id.get_name().is_empty() || !self.is_root_alive() {
+            return Ok(());
+        }
+        let Ok(relative) = self.resolve_path_by_id(id) else {
+            return Ok(());
+        };
+
+        let absolute = self.path.join(relative);
+        if fs::symlink_metadata(&absolute).is_ok()
+            || self.write_behind.lock().contains_key(&absolute)
+        {
+            return Ok(());
+        }
+
+        let data = fetch(id, relative)?;
+        if let Some(parent) = absolute.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        self.overwrite_path_atomic_with(&absolute, |file| {
+            file.write_all(&data)?;
+            Ok(data.len() as u64)
+        })?;
+
+        Ok(())
+    }
+
+    /// Resizes the file at `path` in place, after flushing queued data and replacing an
+    /// attached external link with a local copy.
+    fn set_path_len(&self, path: &Path, new_len: u64) -> Result<(), DatabaseError> {
+        self.materialize_external(path)?;
+        self.flush_pending_writes(|pending, _| pending == path)?;
+
+        let file = fs::OpenOptions::new().write(true).open(path)?;
+        file.set_len(new_len)?;
+        self.sync_file(&file)?;
+        self.emit_overwritten(path);
+        self.after_mutation_at(path)?;
+
+        Ok(())
+    }
+
+    /// Returns the secondary storage file behind `path` when `path` is a link created by
+    /// `apply_tiering`.
+    fn tiered_target(&self, path: &Path) -> Option<PathBuf> {
+        let rule = self.tier_rule.as_ref()?;
+        let target = fs::read_link(path).ok()?;
+        target.starts_with(&rule.location).then_some(target)
+    }
+
+    /// Returns the external file behind `path` when `path` is a link created by
+    /// `attach_external`.
+    fn external_target(&self, path: &Path) -> Option<PathBuf> {
+        let target = fs::read_link(path).ok()?;
+        self.mounts
+            .iter()
+            .any(|mount| target.starts_with(mount))
+            .then_some(target)
+    }
+
+    /// Replaces a link to an attached external file with a local copy, so in-place writes do
+    /// not reach the external directory.
+    fn materialize_external(&self, path: &Path) -> Result<(), DatabaseError> {
+        let Some(target) = self.external_target(path) else {
+            return Ok(());
+        };
+
+        let buffer = self.item_staging_path(path)?;
+        fs::copy(&target, &buffer)?;
+        if let Err(error) = fs::rename(&buffer, path) {
+            let _ = remove_file(&buffer);
+            return Err(error.into());
+        }
+        Ok(())
+    }
+
+    /// Copies the index state `forget_subtree` drops for `relative_path`. The item at
+    /// `relative_path` itself comes first in the entries.
+    fn capture_subtree(&self, relative_path: &Path) -> ForgottenSubtree {
+        let mut entries: Vec<(ItemId, PathBuf)> = self
+            .all_paths()
+            .into_iter()
+            .filter(|(_, path)| path.starts_with(relative_path))
+            .map(|(id, path)| (id, path.clone()))
+            .collect();
+        entries.sort_by(|(a_id, a_path), (b_id, b_path)| {
+            a_path.cmp(b_path).then_with(|| a_id.cmp(b_id))
+        });
+
+        ForgottenSubtree {
+            records: entries
+                .iter()
+                .filter_map(|(_, path)| Some((path.clone(), self.records.get(path)?.clone())))
+                .collect(),
+            entries,
+            access: self
+                .access
+                .iter()
+                .filter(|(path, _)| path.starts_with(relative_path))
+                .map(|(path, entries)| (path.clone(), entries.clone()))
+                .collect(),
+            reservations: self
+                .reservations
+                .iter()
+                .filter(|path| path.starts_with(relative_path))
+                .cloned()
+                .collect(),
+            subtree_configs: self
+                .subtree_configs
+                .range(relative_path.to_path_buf()..)
+                .take_while(|(path, _)| path.starts_with(relative_path))
+                .map(|(path, config)| (path.clone(), config.clone()))
+                .collect(),
+        }
+    }
+
+    /// Puts back index state captured by `capture_subtree`, keeping the original **`ItemId`**
+    /// values.
+    fn restore_subtree(&mut self, forgotten: ForgottenSubtree) -> Result<(), DatabaseError> {
+        for (id, path) in forgotten.entries {
+            self.insert_path_for_id(&id, path)?;
+        }
+        for (path, record) in forgotten.records {
+            self.restore_record(path, Some(record));
+        }
+        self.access.extend(forgotten.access);
+        self.reservations.extend(forgotten.reservations);
+        self.subtree_configs.extend(forgotten.subtree_configs);
+        Ok(())
+    }
+
+    /// Drops `id`, everything tracked below `relative_path`, and their access entries,
+    /// reservations, and subtree configs, after the item was removed from disk.
+    fn forget_subtree(&mut self, id: &ItemId, relative_path: &Path) -> Result<(), DatabaseError> {
+        self.remove_id_from_index(id)?;
+        self.remove_descendants_from_index(relative_path);
+        self.access
+            .retain(|access_path, _| !access_path.starts_with(relative_path));
+        self.reservations
+            .retain(|reserved_path| !reserved_path.starts_with(relative_path));
+        self.subtree_configs
+            .retain(|config_path, _| !config_path.starts_with(relative_path));
+        Ok(())
+    }
+
+    /// Removes every index entry strictly below `root`, along with its record.
+    fn remove_descendants_from_index(&mut self, root: &Path) {
+        for paths in self.items.values_mut() {
+            let inside: Vec<usize> = paths
+                .iter()
+                .filter(|(_, path)| path.starts_with(root) && *path != root)
+                .map(|(index, _)| index)
+                .collect();
+
+            for index in inside {
+                paths.remove(index);
+            }
+        }
+        self.items.retain(|_, paths| !paths.is_empty());
+        self.records
+            .retain(|path, _| !path.starts_with(root) || path == root);
+    }
+
+    /// Gets one specific path for an exact **`ItemId`** key.
+    ///
+    /// This resolves `id.name` to a `StableVec` bucket and `id.index` to its stable slot.
     ///
     /// # Errors
     /// Returns an error if:
-    /// - `id` is the `ItemId::database_id()`,
-    /// - `parent` cannot be found,
-    /// - another item already exists at the target relative path,
-    /// - filesystem create operations fail.
+    /// - the exact key does not exist.
+    fn resolve_path_by_id(&self, id: &ItemId) -> Result<&PathBuf, DatabaseError> {
+        self.items
+            .get(self.index_key(id.get_name()).as_ref())
+            .and_then(|paths| paths.get(id.get_index()))
+            .ok_or_else(|| DatabaseError::NoMatchingID(id.as_string()))
+    }
+
+    /// Returns the staging path for an atomic write to `path` under the configured
+    /// **`StagingStrategy`**, creating `STAGING_DIR_NAME` when needed.
+    fn item_staging_path(&self, path: &Path) -> Result<PathBuf, DatabaseError> {
+        match self.staging {
+            StagingStrategy::Directory if path.starts_with(&self.path) => {
+                static NEXT_STAGING_FILE: AtomicU64 = AtomicU64::new(0);
+
+                let directory = self.path.join(STAGING_DIR_NAME);
+                fs::create_dir_all(&directory)?;
+                let sequence = NEXT_STAGING_FILE.fetch_add(1, Ordering::Relaxed);
+                Ok(directory.join(format!(
+                    "{}-{sequence}{TEMP_FILE_SUFFIX}",
+                    std::process::id()
+                )))
+            }
+            StagingStrategy::Hidden => {
+                let mut name = std::ffi::OsString::from(".");
+                name.push(path.file_name().unwrap_or_default());
+                name.push(format!(".{}{TEMP_FILE_SUFFIX}", std::process::id()));
+                Ok(path.with_file_name(name))
+            }
+            StagingStrategy::Sibling | StagingStrategy::Directory => Ok(staging_path(path)),
+        }
+    }
+
+    /// Overwrites a file safely by using a temp file and rename.
+    ///
+    /// `write_fn` is responsible for writing bytes to the temporary file and returning
+    /// the number of bytes written.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `path` points to a directory,
+    /// - temp create/write/sync/rename fails.
+    fn overwrite_path_atomic_with<F>(&self, path: &Path, write_fn: F) -> Result<u64, DatabaseError>
+    where
+        F: FnOnce(&mut File) -> Result<u64, DatabaseError>,
+    {
+        if path.is_dir() {
+            return Err(DatabaseError::NotAFile(path.to_path_buf()));
+        }
+
+        let buffer = self.item_staging_path(path)?;
+
+        let result: Result<_, DatabaseError> = (|| {
+            let mut file = File::create(&buffer)?;
+            let bytes_written = write_fn(&mut file)?;
+            self.sync_file(&file)?;
+            let check = match path.strip_prefix(&self.path) {
+                Ok(relative_path) => {
+                    let added = file.metadata()?.len();
+                    let replaced = fs::metadata(path).map_or(0, |metadata| metadata.len());
+                    Some(self.check_quotas(relative_path, added, replaced)?)
+                }
+                Err(_) => None,
+            };
+            fs::rename(&buffer, path)?;
+            Ok((bytes_written, check))
+        })();
+
+        if result.is_err() && buffer.exists() {
+            let _ = remove_file(&buffer);
+        }
+
+        let (bytes_written, check) = result?;
+        self.sync_parent_directory(path)?;
+        self.emit_overwritten(path);
+        self.after_mutation_at(path)?;
+        if let Some(check) = check {
+            self.record_quota_usage(check);
+        }
+
+        Ok(bytes_written)
+    }
+
+    /// Syncs a written file unless durability is `Durability::None`.
+    fn sync_file(&self, file: &File) -> io::Result<()> {
+        match self.durability {
+            Durability::None => Ok(()),
+            Durability::File | Durability::FileAndDirectory => file.sync_all(),
+        }
+    }
+
+    /// Syncs the directory holding `path` when durability is `Durability::FileAndDirectory`.
+    fn sync_parent_directory(&self, path: &Path) -> io::Result<()> {
+        if self.durability != Durability::FileAndDirectory {
+            return Ok(());
+        }
+
+        #[cfg(unix)]
+        if let Some(parent) = path.parent() {
+            File::open(parent)?.sync_all()?;
+        }
+        #[cfg(not(unix))]
+        let _ = path;
+        Ok(())
+    }
+
+    /// Collects relative file and folder paths in the scan area.
+    ///
+    /// # Parameters
+    /// - `scope_absolute`: absolute root directory for collection.
+    /// - `recursive`: whether to include descendants recursively.
+    ///
+    /// # Errors
+    /// Returns an error if reading folders fails or converting to a relative prefix fails.
+    fn collect_paths_in_scope(
+        &self,
+        scope_absolute: &Path,
+        recursive: bool,
+    ) -> Result<Vec<PathBuf>, DatabaseError> {
+        let mut collected = Vec::new();
+
+        if recursive {
+            #[cfg(feature = "parallel")]
+            {
+                let threads = match self.scan_threads {
+                    0 => thread::available_parallelism().map_or(1, |count| count.get()),
+                    threads => threads,
+                };
+                collected = scan_tree_parallel(&self.path, scope_absolute, threads)?;
+            }
+            #[cfg(not(feature = "parallel"))]
+            {
+                let mut stack = vec![scope_absolute.to_path_buf()];
+                while let Some(directory) = stack.pop() {
+                    scan_directory(&self.path, &directory, &mut collected, &mut stack)?;
+                }
+            }
+        } else {
+            for entry in fs::read_dir(scope_absolute)? {
+                let entry = entry?;
+                let absolute_path = entry.path();
+                let relative_path = absolute_path.strip_prefix(&self.path)?.to_path_buf();
+
+                if is_internal_file_name(&entry.file_name()) {
+                    continue;
+                }
+
+                if absolute_path.is_dir() || absolute_path.is_file() {
+                    collected.push(relative_path);
+                }
+            }
+        }
+
+        collected.sort();
+        Ok(collected)
+    }
+
+    /// Recursively copies the directory at `from` to `to` for `duplicate_item_with`.
+    ///
+    /// Internal files and excluded entries are skipped. Every copied path, relative to the
+    /// duplicated item, is pushed to `copied`, and `tracker` counts items and bytes copied.
+    fn duplicate_directory_recursive(
+        &self,
+        from: &Path,
+        to: &Path,
+        relative: &Path,
+        options: &mut DuplicateOptions<'_>,
+        copied: &mut Vec<PathBuf>,
+        tracker: &mut ProgressTracker,
+    ) -> Result<(), DatabaseError> {
+        fs::create_dir_all(to)?;
+
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            if is_internal_file_name(&entry.file_name()) {
+                continue;
+            }
+
+            let entry_relative = relative.join(entry.file_name());
+            if options.is_excluded(&entry_relative) {
+                continue;
+            }
+            if options.is_cancelled() {
+                return Err(DatabaseError::Cancelled(
+                    entry_relative.display().to_string(),
+                ));
+            }
+
+            let source_path = entry.path();
+            let destination_path = to.join(entry.file_name());
+
+            let bytes = if source_path.is_dir() {
+                fs::create_dir_all(&destination_path)?;
+                0
+            } else {
+                fs::copy(&source_path, &destination_path)?
+            };
+            tracker.advance(&source_path, bytes);
+            copied.push(entry_relative.clone());
+            if let Some(progress) = options.progress.as_mut() {
+                progress(tracker.progress.items_done, tracker.progress.bytes_done);
+            }
+
+            if source_path.is_dir() {
+                self.duplicate_directory_recursive(
+                    &source_path,
+                    &destination_path,
+                    &entry_relative,
+                    options,
+                    copied,
+                    tracker,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Recursively recreates the directory tree at `from` under `to`, hard linking every file.
+    fn hard_link_directory_recursive(&self, from: &Path, to: &Path) -> Result<(), DatabaseError> {
+        fs::create_dir_all(to)?;
+
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            if entry.file_name() == LOCK_FILE_NAME {
+                continue;
+            }
+            let source_path = entry.path();
+            let destination_path = to.join(entry.file_name());
+
+            if source_path.is_dir() {
+                self.hard_link_directory_recursive(&source_path, &destination_path)?;
+            } else {
+                fs::hard_link(&source_path, &destination_path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Recursively copies a directory tree from `from` to `to`, reporting each item to
+    /// `tracker`. Lock files are skipped, since a lock only belongs to the tree it was taken in.
+    fn copy_directory_recursive(
+        &self,
+        from: &Path,
+        to: &Path,
+        tracker: &mut ProgressTracker,
+    ) -> Result<(), DatabaseError> {
+        fs::create_dir_all(to)?;
+        tracker.advance(from, 0);
+
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            if entry.file_name() == LOCK_FILE_NAME {
+                continue;
+            }
+            let source_path = entry.path();
+            let destination_path = to.join(entry.file_name());
+
+            if source_path.is_dir() {
+                self.copy_directory_recursive(&source_path, &destination_path, tracker)?;
+            } else {
+                let bytes = fs::copy(&source_path, &destination_path)?;
+                tracker.advance(&source_path, bytes);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns whether `path` is inside the requested scan scope.
+    fn is_path_in_scope(
+        &self,
+        path: &Path,
+        scope_relative: Option<&Path>,
+        recursive: bool,
+    ) -> bool {
+        match scope_relative {
+            None => {
+                if recursive {
+                    true
+                } else {
+                    path.parent()
+                        .is_some_and(|parent| parent.as_os_str().is_empty())
+                }
+            }
+            Some(scope_relative) => {
+                if recursive {
+                    path.starts_with(scope_relative) && path != scope_relative
+                } else {
+                    path.parent() == Some(scope_relative)
+                }
+            }
+        }
+    }
+
+    /// Writes and removes the queued write-behind entries accepted by `due`.
+    ///
+    /// Entries are taken out of the queue before writing so the lock is not held during I/O.
+    /// On error, unwritten entries go back unless newer data was queued for them meanwhile.
+    fn flush_pending_writes(
+        &self,
+        due: impl Fn(&Path, &PendingWrite) -> bool,
+    ) -> Result<usize, DatabaseError> {
+        let taken: Vec<(PathBuf, PendingWrite)> = {
+            let mut pending = self.write_behind.lock();
+            let paths: Vec<PathBuf> = pending
+                .iter()
+                .filter(|(path, entry)| due(path, entry))
+                .map(|(path, _)| path.clone())
+                .collect();
+            paths
+                .into_iter()
+                .filter_map(|path| pending.remove(&path).map(|entry| (path, entry)))
+                .collect()
+        };
+
+        let mut written = 0;
+        let mut remaining = taken.into_iter();
+        while let Some((path, entry)) = remaining.next() {
+            let result = self.overwrite_path_atomic_with(&path, |file| {
+                file.write_all(&entry.data)?;
+                Ok(entry.data.len() as u64)
+            });
+            if let Err(error) = result {
+                let mut pending = self.write_behind.lock();
+                for (path, entry) in std::iter::once((path, entry)).chain(remaining) {
+                    pending.entry(path).or_insert(entry);
+                }
+                return Err(error);
+            }
+            written += 1;
+        }
+
+        Ok(written)
+    }
+
+    /// Starts progress tracking for an operation over the trees at `roots` and sends the start
+    /// event.
+    ///
+    /// The totals are only computed while a handler is set.
+    fn start_progress(&self, operation: ProgressOperation, roots: &[&Path]) -> ProgressTracker {
+        let totals = if self.progress.0.is_some() {
+            roots
+                .iter()
+                .try_fold((0u64, 0usize), |(bytes, items), root| {
+                    let (more_bytes, more_items) = tree_totals(root)?;
+                    Ok::<_, DatabaseError>((bytes + more_bytes, items + more_items))
+                })
+                .ok()
+        } else {
+            None
+        };
+
+        self.start_progress_with_totals(
+            operation,
+            totals.map(|(_, items)| items),
+            totals.map(|(bytes, _)| bytes),
+        )
+    }
+
+    /// Starts progress tracking for `operation` with known totals and sends the start event.
+    fn start_progress_with_totals(
+        &self,
+        operation: ProgressOperation,
+        items_total: Option<usize>,
+        bytes_total: Option<u64>,
+    ) -> ProgressTracker {
+        let mut tracker = ProgressTracker {
+            handler: self.progress.clone(),
+            progress: Progress {
+                operation,
+                items_done: 0,
+                items_total,
+                bytes_done: 0,
+                bytes_total,
+                current_path: None,
+            },
+        };
+        tracker.emit(None);
+        tracker
+    }
+
+    /// Copies the file or directory tree at `from` to `to`, reporting each item to `tracker`.
+    fn copy_tree(
+        &self,
+        from: &Path,
+        to: &Path,
+        tracker: &mut ProgressTracker,
+    ) -> Result<(), DatabaseError> {
+        if from.is_dir() {
+            self.copy_directory_recursive(from, to, tracker)
+        } else {
+            let bytes = fs::copy(from, to)?;
+            tracker.advance(from, bytes);
+            Ok(())
+        }
+    }
+
+    /// Deletes a directory in forced or non-forced mode.
+    fn delete_directory(&self, path: &Path, force: bool) -> Result<(), DatabaseError> {
+        if force {
+            remove_dir_all(path)?;
+        } else {
+            let manifest = path.join(MANIFEST_FILE_NAME);
+            if manifest.is_file() {
+                remove_file(manifest)?;
+            }
+            remove_dir(path)?;
+        }
+        Ok(())
+    }
+
+    /// Shared body of the `scan_for_changes` family. `incremental` reuses saved directory
+    /// listings (see `scan_for_changes_incremental`).
+    fn scan(
+        &mut self,
+        scan_from: ItemId,
+        policy: ScanPolicy,
+        recursive: bool,
+        options: ScanOptions,
+        incremental: bool,
+    ) -> Result<ScanReport, DatabaseError> {
+        let mut timer = self.time_operation(LatencyOperation::Scan, &scan_from);
+        let scan_from_absolute = self.locate_absolute(&scan_from)?;
+        timer.set_path(&scan_from_absolute);
+        if !scan_from_absolute.is_dir() {
+            return Err(DatabaseError::NotADirectory(scan_from_absolute));
+        }
+
+        let scope_relative = if scan_from.get_name().is_empty() {
+            None
+        } else {
+            Some(self.locate_relative(&scan_from)?.into_path_buf())
+        };
+
+        let mut discovered_paths = if incremental {
+            self.collect_paths_incremental(&scan_from_absolute)?
+        } else {
+            self.collect_paths_in_scope(&scan_from_absolute, recursive)?
+        };
+        let ignore = self.ignore_selectors(&options)?;
+        let exclude_hidden = options.hidden == HiddenPolicy::Exclude;
+        if !ignore.is_empty() || exclude_hidden {
+            let tracked: HashSet<&PathBuf> =
+                self.all_paths().into_iter().map(|(_, path)| path).collect();
+            discovered_paths.retain(|path| {
+                tracked.contains(path)
+                    || !(is_ignored(&ignore, path)
+                        || exclude_hidden && has_hidden_component(&self.path, path))
+            });
+        }
+        let discovered_set: HashSet<PathBuf> = discovered_paths.iter().cloned().collect();
+        let mut tracker = self.start_progress_with_totals(
+            ProgressOperation::Scan,
+            Some(discovered_paths.len()),
+            None,
+        );
+
+        let mut existing_in_scope_set = HashSet::new();
+        let mut removed = Vec::new();
+        let mut unchanged_count = 0usize;
+        let mut removed_ids = Vec::new();
+
+        for (name, paths) in &self.items {
+            for (index, path) in paths.iter() {
+                if !self.is_path_in_scope(path, scope_relative.as_deref(), recursive) {
+                    continue;
+                }
+
+                existing_in_scope_set.insert(path.clone());
+
+                let id = ItemId::with_index(name.clone(), index);
+                if discovered_set.contains(path) {
+                    unchanged_count += 1;
+                    tracker.advance(path, 0);
+                } else {
+                    removed.push(ExternalChange::Removed {
+                        id: id.clone(),
+                        path: RelativePath(path.clone()),
+                    });
+                    removed_ids.push(id);
+                }
+            }
+        }
+
+        for id in removed_ids {
+            let _ = self.remove_id_from_index(&id);
+        }
+        for change in &removed {
+            if let ExternalChange::Removed { id, path } = change {
+                self.emit_deleted(id, path);
+            }
+        }
+
+        let mut added_paths: Vec<PathBuf> = discovered_paths
+            .into_iter()
+            .filter(|path| !existing_in_scope_set.contains(path))
+            .collect();
+
+        let mut added = Vec::new();
+        match policy {
+            ScanPolicy::DetectOnly => {
+                for path in &added_paths {
+                    tracker.advance(path, 0);
+                    let name = path
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .ok_or(DatabaseError::OsStringConversion)?
+                        .to_string();
+                    added.push(ExternalChange::Added {
+                        id: ItemId::id(name),
+                        path: RelativePath(path.clone()),
+                    });
+                }
+            }
+            ScanPolicy::AddNew => {
+                for path in &added_paths {
+                    tracker.advance(path, 0);
+                    let name = path
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .ok_or(DatabaseError::OsStringConversion)?
+                        .to_string();
+                    let id = self.insert_generated_path(name, path.clone());
+                    self.emit_created(&id, path);
+                    added.push(ExternalChange::Added {
+                        id,
+                        path: RelativePath(path.clone()),
+                    });
+                }
+            }
+            ScanPolicy::RemoveNew => {
+                for path in &added_paths {
+                    tracker.advance(path, 0);
+                    let name = path
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .ok_or(DatabaseError::OsStringConversion)?
+                        .to_string();
+                    added.push(ExternalChange::Added {
+                        id: ItemId::id(name),
+                        path: RelativePath(path.clone()),
+                    });
+                }
+
+                added_paths.sort_by_key(|path| std::cmp::Reverse(path.components().count()));
+                for path in added_paths {
+                    let absolute = self.path.join(&path);
+                    if !absolute.exists() {
+                        continue;
+                    }
+
+                    if absolute.is_dir() {
+                        remove_dir_all(&absolute)?;
+                    } else if absolute.is_file() {
+                        remove_file(&absolute)?;
+                    }
+                }
+            }
+        }
+
+        tracker.finish();
+        let total_changed_count = added.len() + removed.len();
+
+        if policy == ScanPolicy::RemoveNew {
+            added.clear();
+        }
+
+        if total_changed_count > 0 {
+            let scope = scope_relative.clone().unwrap_or_default();
+            self.after_mutation(&[&scope])?;
+        }
+
+        Ok(ScanReport {
+            scanned_from: scan_from,
+            recursive,
+            added,
+            removed,
+            unchanged_count,
+            total_changed_count,
+        })
+    }
+
+    /// Collects the paths below `scope_absolute` like `collect_paths_in_scope`, re-reading only
+    /// directories whose modified time differs from `SCAN_STATE_FILE_NAME`, then saves the new
+    /// state.
+    fn collect_paths_incremental(
+        &self,
+        scope_absolute: &Path,
+    ) -> Result<Vec<PathBuf>, DatabaseError> {
+        // Listings saved this close to their directory's last change may miss a change made in
+        // the same timestamp tick (FAT stores times in 2 second steps).
+        const RACY_WINDOW: Duration = Duration::from_secs(2);
+
+        let state_path = self.path.join(SCAN_STATE_FILE_NAME);
+        let mut state: ScanState = match fs::read(&state_path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_default(),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => ScanState::default(),
+            Err(error) => return Err(error.into()),
+        };
+        let started = SystemTime::now();
+        let trusted_before = state
+            .scanned_at
+            .saturating_sub(RACY_WINDOW.as_nanos() as u64);
+        let scope_relative = scope_absolute.strip_prefix(&self.path)?.to_path_buf();
+
+        let mut collected = Vec::new();
+        let mut visited = BTreeMap::new();
+        let mut stack = vec![scope_relative.clone()];
+        while let Some(directory) = stack.pop() {
+            let absolute = self.path.join(&directory);
+            let modified = match fs::metadata(&absolute) {
+                Ok(metadata) => unix_nanos(metadata.modified()?),
+                Err(error)
+                    if error.kind() == io::ErrorKind::NotFound && directory != scope_relative =>
+                {
+                    continue;
+                }
+                Err(error) => return Err(error.into()),
+            };
+            if directory != scope_relative {
+                collected.push(directory.clone());
+            }
+
+            let listing = match state.directories.remove(&directory) {
+                Some(saved) if saved.modified == modified && modified < trusted_before => saved,
+                _ => {
+                    let mut listing = ScanStateDirectory {
+                        modified,
+                        ..ScanStateDirectory::default()
+                    };
+                    for entry in fs::read_dir(&absolute)? {
+                        let entry = entry?;
+                        if is_internal_file_name(&entry.file_name()) {
+                            continue;
+                        }
+                        let path = entry.path();
+                        if path.is_dir() {
+                            listing.directories.push(PathBuf::from(entry.file_name()));
+                        } else if path.is_file() {
+                            listing.files.push(PathBuf::from(entry.file_name()));
+                        }
+                    }
+                    listing
+                }
+            };
+
+            collected.extend(listing.files.iter().map(|name| directory.join(name)));
+            stack.extend(listing.directories.iter().map(|name| directory.join(name)));
+            visited.insert(directory, listing);
+        }
+
+        state
+            .directories
+            .retain(|path, _| !path.starts_with(&scope_relative));
+        state.directories.extend(visited);
+        state.scanned_at = unix_nanos(started);
+
+        let staging = staging_path(&state_path);
+        fs::write(&staging, serde_json::to_vec(&state)?)?;
+        fs::rename(&staging, &state_path)?;
+
+        collected.sort();
+        Ok(collected)
+    }
+
+    /// Returns the ignore patterns for a scan: the lines of `IGNORE_FILE_NAME`, then the
+    /// patterns in `options`.
+    fn ignore_selectors(&self, options: &ScanOptions) -> Result<Vec<Selector>, DatabaseError> {
+        let mut selectors = Vec::new();
+        match fs::read_to_string(self.path.join(IGNORE_FILE_NAME)) {
+            Ok(contents) => selectors.extend(
+                contents
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(Selector::new),
+            ),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(error) => return Err(error.into()),
+        }
+        selectors.extend(options.ignore.iter().map(Selector::new));
+        Ok(selectors)
+    }
+
+    /// Loads `INDEX_FILE_NAME` for `load_index`. Also returns the relative paths of entries
+    /// that were skipped because nothing exists at their path.
+    fn load_index_file(&mut self) -> Result<(usize, Vec<PathBuf>), DatabaseError> {
+        let bytes = fs::read(self.path.join(INDEX_FILE_NAME))?;
+        let file: IndexFile = serde_json::from_slice(&bytes)?;
+
+        let previous_items = std::mem::take(&mut self.items);
+        let previous_records = std::mem::take(&mut self.records);
+        let previous_normalization =
+            std::mem::replace(&mut self.normalize_extensions, file.normalize_extensions);
+
+        let mut loaded = 0;
+        let mut dropped = Vec::new();
+        for entry in file.entries {
+            if fs::symlink_metadata(self.path.join(&entry.path)).is_err() {
+                dropped.push(entry.path);
+                continue;
+            }
+
+            let id = ItemId::with_index(entry.name, entry.index);
+            if let Err(error) = self.insert_path_for_id(&id, entry.path.clone()) {
+                self.items = previous_items;
+                self.records = previous_records;
+                self.normalize_extensions = previous_normalization;
+                return Err(error);
+            }
+            if let Some(record) = self.records.get_mut(&entry.path) {
+                record.expires_at = entry.expires_at;
+            }
+            loaded += 1;
+        }
+        self.subtree_configs = file.subtree_configs;
+        self.purge_expired_on_open = file.purge_expired_on_open;
+        self.index_saved = true;
+
+        self.after_mutation(&[])?;
+
+        Ok((loaded, dropped))
+    }
+
+    /// Starts timing one call of `operation` on `id`. Nothing is recorded unless latency
+    /// tracking or a slow-operation handler is on.
+    fn time_operation(&self, operation: LatencyOperation, id: &ItemId) -> LatencyTimer {
+        let recorder = self.latency.is_active().then(|| self.latency.clone());
+        let id = match recorder {
+            Some(_) => id.clone(),
+            None => ItemId::database_id(),
+        };
+
+        LatencyTimer {
+            recorder,
+            operation,
+            id,
+            path: None,
+            started: Instant::now(),
+        }
+    }
+
+    /// Reads the trash restore map, or nothing when the trash is empty.
+    fn read_trash_map(&self) -> Result<Vec<TrashEntry>, DatabaseError> {
+        match fs::read(self.path.join(TRASH_DIR_NAME).join(TRASH_MAP_FILE_NAME)) {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Replaces the trash restore map with `entries`, removing the trash when it is empty.
+    fn write_trash_map(&self, entries: &[TrashEntry]) -> Result<(), DatabaseError> {
+        let trash = self.path.join(TRASH_DIR_NAME);
+        if entries.is_empty() {
+            if trash.is_dir() {
+                remove_dir_all(trash)?;
+            }
+            return Ok(());
+        }
+
+        fs::create_dir_all(&trash)?;
+        let map_path = trash.join(TRASH_MAP_FILE_NAME);
+        let buffer = staging_path(&map_path);
+        fs::write(&buffer, serde_json::to_vec_pretty(entries)?)?;
+        fs::rename(&buffer, map_path)?;
+        Ok(())
+    }
+
+    /// Deletes staging files left anywhere in the database, unless `dry_run` is set.
+    ///
+    /// Returns the absolute path and size of every collected file. Stops early when the cancel
+    /// flag of `options` is set.
+    fn sweep_temp_files(
+        &self,
+        dry_run: bool,
+        options: Option<&GcOptions>,
+    ) -> Result<Vec<(PathBuf, u64)>, DatabaseError> {
+        let mut removed = Vec::new();
+        for temp_file in find_temp_files(&self.path)? {
+            if options.is_some_and(|options| options.is_cancelled()) {
+                break;
+            }
+            let size = self.collect_garbage(&temp_file, dry_run)?;
+            removed.push((temp_file, size));
+        }
+
+        Ok(removed)
+    }
+
+    /// Deletes trashed items trashed at least `older_than` ago, unless `dry_run` is set.
+    ///
+    /// Returns the absolute path and size of every purged item. Stops early, keeping the map
+    /// consistent, when the cancel flag of `options` is set.
+    fn purge_trash(
+        &self,
+        older_than: Duration,
+        dry_run: bool,
+        options: Option<&GcOptions>,
+    ) -> Result<Vec<(PathBuf, u64)>, DatabaseError> {
+        let now = SystemTime::now();
+        let (purged, kept): (Vec<TrashEntry>, Vec<TrashEntry>) =
+            self.read_trash_map()?.into_iter().partition(|entry| {
+                let deleted_at = UNIX_EPOCH + Duration::from_millis(entry.deleted_at);
+                now.duration_since(deleted_at).unwrap_or_default() >= older_than
+            });
+
+        let mut removed = Vec::new();
+        let mut remaining = kept;
+        for entry in purged {
+            if options.is_some_and(|options| options.is_cancelled()) {
+                remaining.push(entry);
+                continue;
+            }
+
+            let slot = self.path.join(TRASH_DIR_NAME).join(entry.slot.to_string());
+            let size = match fs::symlink_metadata(&slot) {
+                Ok(_) => self.collect_garbage(&slot, dry_run)?,
+                Err(_) => 0,
+            };
+            removed.push((slot, size));
+            if dry_run {
+                remaining.push(entry);
+            }
+        }
+
+        if !dry_run {
+            remaining.sort_by_key(|entry| entry.slot);
+            self.write_trash_map(&remaining)?;
+        }
+
+        Ok(removed)
+    }
+
+    /// Creates and indexes every missing ancestor directory of the relative `path`, outermost
+    /// first. Returns how many directories were newly indexed.
+    fn ensure_parent_directories(&mut self, path: &Path) -> Result<usize, DatabaseError> {
+        let mut ancestors: Vec<&Path> = path
+            .ancestors()
+            .skip(1)
+            .filter(|ancestor| !ancestor.as_os_str().is_empty())
+            .collect();
+        ancestors.reverse();
+
+        let mut added = 0;
+        for ancestor in ancestors {
+            if self.path_exists_in_index(ancestor) {
+                continue;
+            }
+            fs::create_dir_all(self.path.join(ancestor))?;
+            let name = os_str_to_string(ancestor.file_name())?;
+            let id = self.insert_generated_path(name, ancestor.to_path_buf());
+            self.emit_created(&id, ancestor);
+            added += 1;
+        }
+
+        Ok(added)
+    }
+
+    /// Returns `true` when operations are recorded for `undo`.
+    fn undo_enabled(&self) -> bool {
+        self.undo.depth > 0
+    }
+
+    /// Returns a fresh relative path below `UNDO_DIR_NAME` for parking data.
+    fn next_undo_stash(&self) -> Result<PathBuf, DatabaseError> {
+        fs::create_dir_all(self.path.join(UNDO_DIR_NAME))?;
+
+        let mut stacks = self.undo.lock();
+        stacks.next_stash += 1;
+        Ok(Path::new(UNDO_DIR_NAME).join(stacks.next_stash.to_string()))
+    }
+
+    /// Records that the item at `path` was created, when `undo` is enabled.
+    fn record_created(&self, path: &Path) -> Result<(), DatabaseError> {
+        if !self.undo_enabled() {
+            return Ok(());
+        }
+
+        let stash = self.next_undo_stash()?;
+        self.record_undo(UndoAction::Created {
+            path: path.to_path_buf(),
+            stash,
+        })
+    }
+
+    /// Pushes `action` onto the undo stack, clears the redo stack, and drops the oldest
+    /// entries beyond the depth, removing the data they parked.
+    fn record_undo(&self, action: UndoAction) -> Result<(), DatabaseError> {
+        let dropped: Vec<UndoAction> = {
+            let mut stacks = self.undo.lock();
+            stacks.recorded += 1;
+            stacks.undo.push_back(action);
+            let excess = stacks.undo.len().saturating_sub(self.undo.depth);
+            let mut dropped: Vec<UndoAction> = stacks.undo.drain(..excess).collect();
+            dropped.append(&mut stacks.redo);
+            dropped
+        };
+
+        for action in &dropped {
+            self.discard_stash(action)?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes the entries recorded since `recorded` was read from the undo stack, so a
+    /// rolled-back batch leaves no history behind.
+    fn forget_undo_since(&self, recorded: u64) -> Result<(), DatabaseError> {
+        let dropped: Vec<UndoAction> = {
+            let mut stacks = self.undo.lock();
+            let count = (stacks.recorded - recorded).min(stacks.undo.len() as u64) as usize;
+            let keep = stacks.undo.len() - count;
+            stacks.undo.drain(keep..).collect()
+        };
+
+        for action in &dropped {
+            self.discard_stash(action)?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes the data parked by `action`, if any is left.
+    fn discard_stash(&self, action: &UndoAction) -> Result<(), DatabaseError> {
+        let Some(stash) = action.stash() else {
+            return Ok(());
+        };
+
+        let stash = self.path.join(stash);
+        match fs::symlink_metadata(&stash) {
+            Ok(metadata) if metadata.is_dir() => remove_dir_all(stash)?,
+            Ok(_) => remove_file(stash)?,
+            Err(_) => {}
+        }
+
+        Ok(())
+    }
+
+    /// Copies the current contents of the file at `path` into a new stash, so an overwrite can
+    /// be undone. Returns the relative stash path.
+    fn stash_contents(&self, path: &Path) -> Result<PathBuf, DatabaseError> {
+        if path.is_dir() {
+            return Err(DatabaseError::NotAFile(path.to_path_buf()));
+        }
+        self.flush_pending_writes(|pending, _| pending == path)?;
+
+        let stash = self.next_undo_stash()?;
+        let stash_absolute = self.path.join(&stash);
+        if fs::hard_link(path, &stash_absolute).is_err() {
+            fs::copy(path, &stash_absolute)?;
+        }
+
+        Ok(stash)
+    }
+
+    /// Reverses (`undo == true`) or repeats `action` without recording anything.
+    fn apply_undo_action(&mut self, action: &UndoAction, undo: bool) -> Result<(), DatabaseError> {
+        let depth = std::mem::replace(&mut self.undo.depth, 0);
+        let result = match (action, undo) {
+            (UndoAction::Created { path, stash }, true)
+            | (UndoAction::Deleted { path, stash }, false) => self.park_item(path, stash),
+            (UndoAction::Created { path, stash }, false)
+            | (UndoAction::Deleted { path, stash }, true) => self.unpark_item(path, stash),
+            (UndoAction::Moved { from, to }, true) => self.move_item_to(to, from),
+            (UndoAction::Moved { from, to }, false) => self.move_item_to(from, to),
+            (UndoAction::Overwritten { path, stash }, _) => self
+                .swap_with_stash(path, stash)
+                .map(|()| self.emit_overwritten(&self.path.join(path))),
+        };
+        self.undo.depth = depth;
+
+        if result.is_ok() {
+            let touched: Vec<&Path> = match action {
+                UndoAction::Created { path, .. }
+                | UndoAction::Deleted { path, .. }
+                | UndoAction::Overwritten { path, .. } => vec![path],
+                UndoAction::Moved { from, to } => vec![from, to],
+            };
+            self.after_mutation(&touched)?;
+        }
+        result
+    }
+
+    /// Moves the tracked item at `path` to `stash` and drops it from the `index`.
+    fn park_item(&mut self, path: &Path, stash: &Path) -> Result<(), DatabaseError> {
+        let id = self
+            .id_for_path(path)
+            .ok_or_else(|| DatabaseError::NoMatchingID(path.display().to_string()))?;
+
+        fs::rename(self.path.join(path), self.path.join(stash))?;
+        self.forget_subtree(&id, path)?;
+        self.emit_deleted(&id, path);
+        Ok(())
+    }
+
+    /// Moves the item parked at `stash` back to `path` and indexes it and its descendants.
+    fn unpark_item(&mut self, path: &Path, stash: &Path) -> Result<(), DatabaseError> {
+        let name = os_str_to_string(path.file_name())?;
+        let absolute = self.path.join(path);
+        if fs::symlink_metadata(&absolute).is_ok() || self.path_exists_in_index(path) {
+            return Err(DatabaseError::IdAlreadyExists(name));
+        }
+
+        fs::rename(self.path.join(stash), &absolute)?;
+        let id = self.insert_generated_path(name, path.to_path_buf());
+        if absolute.is_dir() && !is_symlink(&absolute) {
+            for descendant in self.collect_paths_in_scope(&absolute, true)? {
+                let name = os_str_to_string(descendant.file_name())?;
+                self.insert_generated_path(name, descendant);
+            }
+        }
+        self.emit_created(&id, path);
+
+        Ok(())
+    }
+
+    /// Moves the tracked item at `from` to `to` with `rename` or `migrate_item`.
+    fn move_item_to(&mut self, from: &Path, to: &Path) -> Result<(), DatabaseError> {
+        let id = self
+            .id_for_path(from)
+            .ok_or_else(|| DatabaseError::NoMatchingID(from.display().to_string()))?;
+        let name = os_str_to_string(to.file_name())?;
+        if fs::symlink_metadata(self.path.join(to)).is_ok() || self.path_exists_in_index(to) {
+            return Err(DatabaseError::IdAlreadyExists(name));
+        }
+
+        let parent = to.parent().unwrap_or(Path::new(""));
+        if from.parent() == Some(parent) {
+            self.rename(id, name)?;
+        } else {
+            let parent_id = match parent.as_os_str().is_empty() {
+                true => ItemId::database_id(),
+                false => self
+                    .id_for_path(parent)
+                    .ok_or_else(|| DatabaseError::NoMatchingID(parent.display().to_string()))?,
+            };
+            self.migrate_item(id, parent_id)?;
+        }
+
+        Ok(())
+    }
+
+    /// Exchanges the contents of the file at `path` with the data parked at `stash`.
+    fn swap_with_stash(&self, path: &Path, stash: &Path) -> Result<(), DatabaseError> {
+        let absolute = self.path.join(path);
+        let stash = self.path.join(stash);
+        let swap = staging_path(&stash);
+
+        fs::rename(&absolute, &swap)?;
+        fs::rename(&stash, &absolute)?;
+        fs::rename(&swap, &stash)?;
+        Ok(())
+    }
+
+    /// Moves `from` to `to`, falling back to copy-then-delete when a rename is not possible
+    /// (for example across filesystems). The move is journaled when the journal is enabled.
+    fn move_path(
+        &self,
+        from: &Path,
+        to: &Path,
+        tracker: &mut ProgressTracker,
+    ) -> Result<(), DatabaseError> {
+        self.journal_begin(from, to)?;
+        if fs::rename(from, to).is_err() {
+            self.copy_tree(from, to, tracker)?;
+            self.journal_mark_copied(from, to)?;
+            if from.is_dir() {
+                remove_dir_all(from)?;
+            } else {
+                remove_file(from)?;
+            }
+        }
+        self.journal_end(from, to)
+    }
+
+    /// Records that a move from `from` to `to` is about to start.
+    fn journal_begin(&self, from: &Path, to: &Path) -> Result<(), DatabaseError> {
+        if !self.journal {
+            return Ok(());
+        }
+
+        let mut entries = read_journal(&self.path)?;
+        entries.push(JournalEntry {
+            from: from.to_path_buf(),
+            to: to.to_path_buf(),
+            copied: false,
+        });
+        write_journal(&self.path, &entries)
+    }
+
+    /// Records that `to` now holds a complete copy of `from`.
+    fn journal_mark_copied(&self, from: &Path, to: &Path) -> Result<(), DatabaseError> {
+        if !self.journal {
+            return Ok(());
+        }
+
+        let mut entries = read_journal(&self.path)?;
+        for entry in &mut entries {
+            if entry.from == from && entry.to == to {
+                entry.copied = true;
+            }
+        }
+        write_journal(&self.path, &entries)
+    }
+
+    /// Removes the finished move from `from` to `to` from the journal.
+    fn journal_end(&self, from: &Path, to: &Path) -> Result<(), DatabaseError> {
+        if !self.journal {
+            return Ok(());
+        }
+
+        let mut entries = read_journal(&self.path)?;
+        entries.retain(|entry| entry.from != from || entry.to != to);
+        write_journal(&self.path, &entries)
+    }
+
+    /// Returns `(ItemId, relative path)` for every tracked item matched by `selector`, in path
+    /// order.
+    fn matching_paths(&self, selector: &Selector) -> Result<Vec<(ItemId, PathBuf)>, DatabaseError> {
+        let parent_path = self.directory_relative_path(&selector.parent)?;
+        let mut matches: Vec<(ItemId, PathBuf)> = self
+            .all_paths()
+            .into_iter()
+            .filter(|(_, path)| {
+                path.strip_prefix(&parent_path).is_ok_and(|relative| {
+                    !relative.as_os_str().is_empty() && selector.matches(relative)
+                })
+            })
+            .map(|(id, path)| (id, path.clone()))
+            .collect();
+
+        matches.sort_by(|(a_id, a_path), (b_id, b_path)| {
+            a_path.cmp(b_path).then_with(|| a_id.cmp(b_id))
+        });
+        Ok(matches)
+    }
+
+    /// Deletes the file or directory at `path` unless `dry_run` is set, returning its size.
+    fn collect_garbage(&self, path: &Path, dry_run: bool) -> Result<u64, DatabaseError> {
+        let metadata = fs::symlink_metadata(path)?;
+        let size = if metadata.is_dir() {
+            directory_size(path)?
+        } else {
+            metadata.len()
+        };
+
+        if !dry_run {
+            if metadata.is_dir() {
+                remove_dir_all(path)?;
+            } else {
+                remove_file(path)?;
+            }
+        }
+
+        Ok(size)
+    }
+
+    /// Records that `path` was inserted now, unless it already has a record.
+    fn record_insertion(&mut self, path: PathBuf) {
+        if let Entry::Vacant(entry) = self.records.entry(path) {
+            entry.insert(ItemRecord {
+                insertion: InsertionRecord {
+                    sequence: self.next_sequence,
+                    inserted_at: SystemTime::now(),
+                },
+                import_mode: None,
+                expires_at: None,
+            });
+            self.next_sequence += 1;
+        }
+    }
+
+    /// Puts back a record captured before an entry was moved.
+    fn restore_record(&mut self, path: PathBuf, record: Option<ItemRecord>) {
+        if let Some(record) = record {
+            self.records.insert(path, record);
+        }
+    }
+
+    /// Returns the first value `pick` finds in the configs of `relative_path` and its ancestors,
+    /// nearest first.
+    fn subtree_setting<T>(
+        &self,
+        relative_path: &Path,
+        pick: impl Fn(&SubtreeConfig) -> Option<T>,
+    ) -> Option<T> {
+        if self.subtree_configs.is_empty() {
+            return None;
+        }
+        relative_path
+            .ancestors()
+            .find_map(|ancestor| self.subtree_configs.get(ancestor).and_then(&pick))
+    }
+
+    /// Returns `true` when the directory at `relative_path` should keep a manifest.
+    fn manifests_enabled_for(&self, relative_path: &Path) -> bool {
+        self.subtree_setting(relative_path, |config| config.manifests)
+            .unwrap_or(self.manifests)
+    }
+
+    /// Returns `true` when manifests are enabled anywhere in the database.
+    fn uses_manifests(&self) -> bool {
+        self.manifests
+            || self
+                .subtree_configs
+                .values()
+                .any(|config| config.manifests == Some(true))
+    }
+
+    /// Moves subtree configs at or below `from` so they follow a renamed or migrated directory.
+    fn relocate_subtree_configs(&mut self, from: &Path, to: &Path) {
+        let moved: Vec<PathBuf> = self
+            .subtree_configs
+            .keys()
+            .filter(|path| path.starts_with(from))
+            .cloned()
+            .collect();
+
+        for path in moved {
+            if let (Some(config), Some(rebased)) = (
+                self.subtree_configs.remove(&path),
+                rebase_path(&path, from, to),
+            ) {
+                self.subtree_configs.insert(rebased, config);
+            }
+        }
+    }
+
+    /// Returns `DatabaseError::DatabaseRootMissing` when the root directory is gone.
+    fn check_root(&self) -> Result<(), DatabaseError> {
+        if self.is_root_alive() {
+            Ok(())
+        } else {
+            Err(DatabaseError::DatabaseRootMissing(self.path.clone()))
+        }
+    }
+
+    /// In strict mode, verifies that the entry for `id` at `relative_path` still matches the
+    /// filesystem.
+    fn check_strict(&self, id: &ItemId, relative_path: &Path) -> Result<(), DatabaseError> {
+        let strict = self.subtree_setting(relative_path, |config| config.strict);
+        if !strict.unwrap_or(self.strict) {
+            return Ok(());
+        }
+
+        let absolute = self.path.join(relative_path);
+        if !absolute.exists() {
+            return Err(DatabaseError::StaleIndexEntry(id.as_string(), absolute));
+        }
+
+        let has_children = self
+            .items
+            .values()
+            .flat_map(|paths| paths.iter())
+            .any(|(_, path)| path.parent() == Some(relative_path));
+        if has_children && !absolute.is_dir() {
+            return Err(DatabaseError::StaleIndexEntry(id.as_string(), absolute));
+        }
+
+        Ok(())
+    }
+
+    /// Exports the item at `source_absolute` to the exact path `destination_absolute`.
     ///
-    /// # Examples
-    /// ```no_run
-    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    /// Shared by `export_item` and `export_map` once the destination is known.
+    fn export_to_path(
+        &mut self,
+        id: &ItemId,
+        source_absolute: &Path,
+        destination_absolute: PathBuf,
+        mode: &ExportMode,
+        tracker: &mut ProgressTracker,
+    ) -> Result<ExportSummary, DatabaseError> {
+        if destination_absolute == source_absolute {
+            return Err(DatabaseError::IdenticalSourceDestination(
+                destination_absolute,
+            ));
+        }
+
+        if destination_absolute.exists() {
+            if destination_absolute.is_dir() {
+                remove_dir_all(&destination_absolute)?;
+            } else {
+                remove_file(&destination_absolute)?;
+            }
+        }
+
+        let (bytes_copied, items_affected) = tree_totals(source_absolute)?;
+
+        match mode {
+            ExportMode::Copy => self.copy_tree(source_absolute, &destination_absolute, tracker)?,
+            ExportMode::Move => {
+                let tiered_target = self.tiered_target(source_absolute);
+                let moved_path = tiered_target.as_deref().unwrap_or(source_absolute);
+                self.move_path(moved_path, &destination_absolute, tracker)?;
+                if tiered_target.is_some() {
+                    remove_symlink(source_absolute)?;
+                }
+                self.remove_id_from_index(id)?;
+                if let Ok(relative) = source_absolute.strip_prefix(&self.path) {
+                    self.emit_deleted(id, relative);
+                }
+            }
+        }
+
+        match mode {
+            ExportMode::Copy => self.after_mutation(&[])?,
+            ExportMode::Move => self.after_mutation_at(source_absolute)?,
+        }
+
+        Ok(ExportSummary {
+            destination: destination_absolute,
+            bytes_copied,
+            items_affected,
+        })
+    }
+
+    /// Returns `(unix millis, path)` for every backup named `<prefix><millis>` in
+    /// `destination`, oldest first.
+    fn list_backups(
+        &self,
+        destination: &Path,
+        prefix: &str,
+    ) -> Result<Vec<(u64, PathBuf)>, DatabaseError> {
+        let mut backups = Vec::new();
+        for entry in fs::read_dir(destination)? {
+            let entry = entry?;
+            let millis = entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.strip_prefix(prefix))
+                .and_then(|millis| millis.parse::<u64>().ok());
+
+            if let Some(millis) = millis
+                && entry.path().is_dir()
+            {
+                backups.push((millis, entry.path()));
+            }
+        }
+
+        backups.sort();
+        Ok(backups)
+    }
+
+    /// Returns the relative paths of the root and every tracked directory.
+    fn tracked_directories(&self) -> Vec<PathBuf> {
+        let mut directories = vec![PathBuf::new()];
+        directories.extend(
+            self.all_paths()
+                .into_iter()
+                .map(|(_, path)| path.clone())
+                .filter(|path| {
+                    let absolute = self.path.join(path);
+                    absolute.is_dir() && !is_symlink(&absolute)
+                }),
+        );
+        directories
+    }
+
+    /// Writes or removes the manifest of the tracked directory at `directory`, following
+    /// whether manifests are enabled for it.
+    fn refresh_manifest(&self, directory: &Path) -> Result<(), DatabaseError> {
+        if self.manifests_enabled_for(directory) {
+            self.write_manifest(directory)
+        } else {
+            let manifest = self.path.join(directory).join(MANIFEST_FILE_NAME);
+            if manifest.is_file() {
+                remove_file(manifest)?;
+            }
+            Ok(())
+        }
+    }
+
+    /// Writes the manifest for the tracked directory at `directory`.
+    fn write_manifest(&self, directory: &Path) -> Result<(), DatabaseError> {
+        let absolute = self.path.join(directory);
+        let manifest_path = absolute.join(MANIFEST_FILE_NAME);
+
+        let previous: DirectoryManifest = fs::read(&manifest_path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+
+        let mut entries = Vec::new();
+        for (_, path) in self.all_paths() {
+            if path.parent() != Some(directory) {
+                continue;
+            }
+
+            let Ok(metadata) = fs::metadata(self.path.join(path)) else {
+                continue;
+            };
+            let name = os_str_to_string(path.file_name())?;
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|duration| duration.as_nanos() as u64);
+
+            let (size, hash) = if metadata.is_dir() {
+                (0, None)
+            } else {
+                let size = metadata.len();
+                let hash = match previous.get_entry(&name) {
+                    Some(entry)
+                        if entry.hash.is_some()
+                            && entry.size == size
+                            && entry.modified.is_some()
+                            && entry.modified == modified =>
+                    {
+                        entry.hash.clone()
+                    }
+                    _ => Some(format!("{:016x}", fnv1a_file(&self.path.join(path))?)),
+                };
+                (size, hash)
+            };
+
+            entries.push(ManifestEntry {
+                name,
+                is_dir: metadata.is_dir(),
+                size,
+                hash,
+                modified,
+            });
+        }
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let manifest = DirectoryManifest { entries };
+        if manifest == previous {
+            return Ok(());
+        }
+
+        let buffer = staging_path(&manifest_path);
+        fs::write(&buffer, serde_json::to_vec_pretty(&manifest)?)?;
+        fs::rename(&buffer, manifest_path)?;
+
+        Ok(())
+    }
+
+    /// Returns the `index` key for `name` under the current normalization setting.
+    fn index_key<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        index_key(name, self.normalize_extensions)
+    }
+
+    /// Returns `id` with its name normalized for the `index`.
+    fn normalize_id(&self, id: ItemId) -> ItemId {
+        match self.index_key(id.get_name()) {
+            Cow::Borrowed(_) => id,
+            Cow::Owned(name) => ItemId::with_index(name, id.get_index()),
+        }
+    }
+
+    /// Returns the relative path of a reserved item, or `DatabaseError::NotReserved`.
+    fn reserved_path(&self, id: &ItemId) -> Result<PathBuf, DatabaseError> {
+        let relative_path = self.relative_path_of(id)?;
+        if !self.reservations.contains(&relative_path) {
+            return Err(DatabaseError::NotReserved(id.as_string()));
+        }
+
+        Ok(relative_path)
+    }
+
+    /// Returns the relative path of `id`, or an error when it is not a directory.
+    fn directory_relative_path(&self, id: &ItemId) -> Result<RelativePath, DatabaseError> {
+        let absolute = self.locate_absolute(id)?;
+        if !absolute.is_dir() {
+            return Err(DatabaseError::NotADirectory(absolute));
+        }
+
+        self.locate_relative(id)
+    }
+
+    /// Runs follow-up work after a successful mutating operation that changed the items at the
+    /// relative paths in `touched`.
+    ///
+    /// Only the manifests of the directories holding `touched`, and of tracked directories at
+    /// or below a `touched` path, are rewritten.
+    fn after_mutation(&self, touched: &[&Path]) -> Result<(), DatabaseError> {
+        self.generation.bump();
+        self.check_usage_watchdog();
+        if !self.uses_manifests() || touched.is_empty() {
+            return Ok(());
+        }
+
+        let mut directories: Vec<PathBuf> = touched
+            .iter()
+            .filter_map(|path| path.parent())
+            .map(Path::to_path_buf)
+            .collect();
+        directories.extend(
+            self.all_paths()
+                .into_iter()
+                .map(|(_, path)| path)
+                .filter(|path| touched.iter().any(|touched| path.starts_with(touched)))
+                .filter(|path| {
+                    let absolute = self.path.join(path);
+                    absolute.is_dir() && !is_symlink(&absolute)
+                })
+                .cloned(),
+        );
+        directories.sort();
+        directories.dedup();
+        for directory in directories {
+            self.refresh_manifest(&directory)?;
+        }
+
+        Ok(())
+    }
+
+    /// Runs `after_mutation` for the item at `absolute_path`, which may lie outside the root.
+    fn after_mutation_at(&self, absolute_path: &Path) -> Result<(), DatabaseError> {
+        match absolute_path.strip_prefix(&self.path) {
+            Ok(relative_path) => self.after_mutation(&[relative_path]),
+            Err(_) => self.after_mutation(&[]),
+        }
+    }
+
+    /// Returns `true` when a database-wide or per-directory quota is set.
+    fn has_quotas(&self) -> bool {
+        self.quota.is_some()
+            || self
+                .subtree_configs
+                .values()
+                .any(|config| config.quota.is_some())
+    }
+
+    /// Fails with `DatabaseError::InsufficientSpace` when the free-space check is enabled and
+    /// the filesystem holding `destination` has less than `required` bytes free.
+    fn preflight_free_space(&self, destination: &Path, required: u64) -> Result<(), DatabaseError> {
+        if !self.free_space_check {
+            return Ok(());
+        }
+
+        match available_space(destination) {
+            Ok(available) if available < required => Err(DatabaseError::InsufficientSpace(
+                destination.to_path_buf(),
+                required,
+                available,
+            )),
+            Err(error) if error.kind() != io::ErrorKind::Unsupported => Err(error.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks every quota on `relative_path` and its enclosing directories, assuming a write
+    /// adds `added` bytes and replaces `replaced` bytes.
+    ///
+    /// Sizes measured since the last mutation are reused, and directories with no cached size
+    /// are measured in one walk. Pass the result to `record_quota_usage` once the write lands
+    /// so the next check does not measure again.
+    fn check_quotas(
+        &self,
+        relative_path: &Path,
+        added: u64,
+        replaced: u64,
+    ) -> Result<QuotaCheck, DatabaseError> {
+        let mut quotas: Vec<(PathBuf, u64)> = self
+            .subtree_configs
+            .iter()
+            .filter(|(path, _)| relative_path.starts_with(path))
+            .filter_map(|(path, config)| config.quota.map(|quota| (path.clone(), quota)))
+            .collect();
+        if let Some(quota) = self.quota {
+            quotas.push((PathBuf::new(), quota));
+        }
+
+        let generation = self.generation.get();
+        let mut check = QuotaCheck {
+            generation,
+            sizes: Vec::new(),
+        };
+        if quotas.is_empty() {
+            return Ok(check);
+        }
+
+        let cached: Vec<Option<u64>> = {
+            let mut usage = self.quota_usage.lock();
+            if usage.generation != generation {
+                usage.generation = generation;
+                usage.sizes.clear();
+            }
+            quotas
+                .iter()
+                .map(|(path, _)| usage.sizes.get(path).copied())
+                .collect()
+        };
+        let unmeasured: Vec<&Path> = quotas
+            .iter()
+            .zip(&cached)
+            .filter(|(_, size)| size.is_none())
+            .map(|((path, _), _)| path.as_path())
+            .collect();
+        let mut measured = self.measure_quota_directories(&unmeasured)?.into_iter();
+
+        for ((path, quota), size) in quotas.into_iter().zip(cached) {
+            let size = match size {
+                Some(size) => size,
+                None => measured.next().unwrap_or_default(),
+            };
+            let needed = size.saturating_add(added).saturating_sub(replaced);
+            if needed > quota {
+                return Err(DatabaseError::QuotaExceeded(
+                    self.path.join(path),
+                    quota,
+                    needed,
+                ));
+            }
+            check.sizes.push((path, needed));
+        }
+
+        Ok(check)
+    }
+
+    /// Stores the sizes from a passed `check_quotas` after its write landed.
+    ///
+    /// The sizes are only kept when nothing but that write's own `after_mutation` ran since the
+    /// check.
+    fn record_quota_usage(&self, check: QuotaCheck) {
+        if check.sizes.is_empty() {
+            return;
+        }
+
+        let generation = self.generation.get();
+        if generation > check.generation + 1 {
+            return;
+        }
+
+        let mut usage = self.quota_usage.lock();
+        if usage.generation != check.generation {
+            return;
+        }
+        usage.generation = generation;
+        usage.sizes.extend(check.sizes);
+    }
+
+    /// Returns the quota size of each directory in `directories`, which all enclose one path.
+    ///
+    /// The outermost directory is walked once and every file counts toward each directory that
+    /// holds it. Staging files of writes in progress and data parked in `UNDO_DIR_NAME` do not
+    /// count.
+    fn measure_quota_directories(&self, directories: &[&Path]) -> Result<Vec<u64>, DatabaseError> {
+        let mut sizes = vec![0u64; directories.len()];
+        let Some(outermost) = directories
+            .iter()
+            .min_by_key(|path| path.components().count())
+        else {
+            return Ok(sizes);
+        };
+
+        let undo = self.path.join(UNDO_DIR_NAME);
+        let mut stack = vec![self.path.join(outermost)];
+        while let Some(directory) = stack.pop() {
+            for entry in fs::read_dir(&directory)? {
+                let entry = entry?;
+                let path = entry.path();
+                let metadata = entry.metadata()?;
+                if metadata.is_dir() {
+                    if path != undo {
+                        stack.push(path);
+                    }
+                    continue;
+                }
+                if entry
+                    .file_name()
+                    .to_str()
+                    .is_some_and(|name| name.ends_with(TEMP_FILE_SUFFIX))
+                {
+                    continue;
+                }
+
+                let relative = path.strip_prefix(&self.path)?;
+                for (size, directory) in sizes.iter_mut().zip(directories) {
+                    if relative.starts_with(directory) {
+                        *size += metadata.len();
+                    }
+                }
+            }
+        }
+
+        Ok(sizes)
+    }
+
+    /// Evaluates the usage watchdog and fires its callback for newly crossed thresholds.
+    fn check_usage_watchdog(&self) {
+        let Some(watchdog) = &self.usage_watchdog else {
+            return;
+        };
+
+        match watchdog.last_checked.lock() {
+            Ok(mut last_checked) => {
+                let now = Instant::now();
+                if last_checked.is_some_and(|checked| {
+                    now.duration_since(checked) < self.usage_watchdog_interval
+                }) {
+                    return;
+                }
+                *last_checked = Some(now);
+            }
+            Err(_) => return,
+        }
+
+        let Ok(used_bytes) = directory_size(&self.path) else {
+            return;
+        };
+        let limit_bytes = match watchdog.limit {
+            UsageLimit::Bytes(limit_bytes) => limit_bytes,
+            UsageLimit::Disk => match available_space(&self.path) {
+                Ok(available) => used_bytes.saturating_add(available),
+                Err(_) => return,
+            },
+        };
+
+        let usage = used_bytes as f64 / limit_bytes.max(1) as f64;
+        let level = watchdog
+            .thresholds
+            .iter()
+            .take_while(|threshold| usage >= **threshold)
+            .count();
+
+        let previous = match watchdog.level.lock() {
+            Ok(mut current) => std::mem::replace(&mut *current, level),
+            Err(_) => return,
+        };
+
+        if level > previous {
+            (watchdog.callback)(&UsageAlert {
+                threshold: watchdog.thresholds[level - 1],
+                used_bytes,
+                limit_bytes,
+            });
+        }
+    }
+
+    /// Runs the `on_create` hooks for `id` at `relative_path`.
+    fn emit_created(&self, id: &ItemId, relative_path: &Path) {
+        for hook in &self.hooks.create {
+            hook(id, relative_path);
+        }
+    }
+
+    /// Runs the `on_delete` hooks for `id`, which was at `relative_path`.
+    fn emit_deleted(&self, id: &ItemId, relative_path: &Path) {
+        for hook in &self.hooks.delete {
+            hook(id, relative_path);
+        }
+    }
+
+    /// Runs the `on_rename` hooks for a move from `old_path` to `new_path`.
+    fn emit_renamed(&self, old_id: &ItemId, old_path: &Path, new_id: &ItemId, new_path: &Path) {
+        for hook in &self.hooks.rename {
+            hook(old_id, old_path, new_id, new_path);
+        }
+    }
+
+    /// Runs the `on_overwrite` hooks for the tracked file at `absolute_path`.
     ///
-    /// fn main() -> Result<(), DatabaseError> {
-    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
-    ///     manager.write_new(ItemId::id("notes.txt"), ItemId::database_id())?;
-    ///     Ok(())
-    /// }
-    /// ```
-    pub fn write_new(
-        &mut self,
-        id: impl Into<ItemId>,
-        parent: impl Into<ItemId>,
-    ) -> Result<(), DatabaseError> {
-        let id = id.into();
-        let parent = parent.into();
+    /// The **`ItemId`** is only looked up when a hook is registered.
+    fn emit_overwritten(&self, absolute_path: &Path) {
+        if self.hooks.overwrite.is_empty() {
+            return;
+        }
+        let Ok(relative_path) = absolute_path.strip_prefix(&self.path) else {
+            return;
+        };
+        let Some(id) = self.id_for_path(relative_path) else {
+            return;
+        };
+        for hook in &self.hooks.overwrite {
+            hook(&id, relative_path);
+        }
+    }
+
+    /// Returns the **`ItemId`** stored for an exact relative path, if any.
+    fn id_for_path(&self, relative_path: &Path) -> Option<ItemId> {
+        self.items.iter().find_map(|(name, paths)| {
+            paths
+                .iter()
+                .find(|(_, path)| path.as_path() == relative_path)
+                .map(|(index, _)| ItemId::with_index(name.clone(), index))
+        })
+    }
 
+    /// Resolves `id` inside the subtree rooted at `root` and returns its database-relative path.
+    ///
+    /// `ItemId::database_id()` resolves to `root` itself. IDs outside the subtree are reported
+    /// as `DatabaseError::NoMatchingID`.
+    fn relative_path_in_subtree(&self, root: &Path, id: &ItemId) -> Result<PathBuf, DatabaseError> {
         if id.get_name().is_empty() {
-            return Err(DatabaseError::RootIdUnsupported);
+            return Ok(root.to_path_buf());
         }
 
-        let absolute_parent_path = self.locate_absolute(&parent)?;
-        let relative_path = if parent.get_name().is_empty() {
-            PathBuf::from(id.get_name())
+        let path = self.resolve_path_by_id(id)?;
+        if path.starts_with(root) && path != root {
+            Ok(path.clone())
         } else {
-            let mut path = self.locate_relative(parent)?.to_path_buf();
-            path.push(id.get_name());
-            path
-        };
-        let absolute_path = absolute_parent_path.join(id.get_name());
+            Err(DatabaseError::NoMatchingID(id.as_string()))
+        }
+    }
 
-        if self.path_exists_in_index(&relative_path) {
-            return Err(DatabaseError::IdAlreadyExists(id.as_string()));
+    /// Maps an ID from inside a subtree view onto the matching manager-level **`ItemId`**.
+    fn id_in_subtree(&self, root: &Path, id: &ItemId) -> Result<ItemId, DatabaseError> {
+        let path = self.relative_path_in_subtree(root, id)?;
+        if path.as_os_str().is_empty() {
+            return Ok(ItemId::database_id());
         }
 
-        if relative_path.extension().is_none() {
-            create_dir(&absolute_path)?;
+        if path == root {
+            return self
+                .id_for_path(root)
+                .ok_or_else(|| DatabaseError::NoMatchingID(id.as_string()));
+        }
+
+        Ok(id.clone())
+    }
+
+    /// Returns the database-relative path for `id`, using an empty path for the root.
+    fn relative_path_of(&self, id: &ItemId) -> Result<PathBuf, DatabaseError> {
+        if id.get_name().is_empty() {
+            return Ok(PathBuf::new());
+        }
+
+        Ok(self.resolve_path_by_id(id)?.clone())
+    }
+
+    /// Returns the database-relative path a new item called `name` gets inside `parent`, after
+    /// checking `name` with the `ItemId::try_new` rules.
+    fn child_path(&self, parent: &ItemId, name: &str) -> Result<PathBuf, DatabaseError> {
+        validate_item_name(name)?;
+        Ok(self.relative_path_of(parent)?.join(name))
+    }
+
+    /// Returns the database-relative path `id` gets when renamed to `name`, after checking
+    /// `name` with the `ItemId::try_new` rules.
+    fn renamed_path(&self, id: &ItemId, name: &str) -> Result<PathBuf, DatabaseError> {
+        validate_item_name(name)?;
+        Ok(self.relative_path_of(id)?.with_file_name(name))
+    }
+
+    /// Fails with `DatabaseError::InvalidItemName` unless `path` lies strictly inside `root`.
+    fn ensure_inside(root: &Path, path: &Path, name: &str) -> Result<(), DatabaseError> {
+        if path.starts_with(root) && path != root {
+            Ok(())
         } else {
-            File::create_new(&absolute_path)?;
+            Err(DatabaseError::InvalidItemName(
+                name.to_string(),
+                "name leaves the view's root directory",
+            ))
         }
+    }
 
-        self.insert_path_for_id(&id, relative_path)?;
-        Ok(())
+    /// Finds the nearest access control entry for `principal` at or above `relative_path`.
+    fn resolve_access(
+        &self,
+        relative_path: &Path,
+        principal: &str,
+    ) -> Option<&HashSet<AccessOperation>> {
+        relative_path.ancestors().find_map(|ancestor| {
+            self.access
+                .get(ancestor)
+                .and_then(|entries| entries.get(principal))
+        })
     }
 
-    /// Overwrites an existing file with raw bytes in a safe way.
-    ///
-    /// It writes to a temp file first, then replaces the target file.
-    ///
-    /// # Parameters
-    /// - `id`: target file **`ItemId`**.
-    /// - `data`: raw bytes to write.
-    ///
-    /// # Errors
-    /// Returns an error if:
-    /// - `id` cannot be found,
-    /// - `id` points to a directory,
-    /// - writing, syncing, or renaming fails.
-    ///
-    /// # Examples
-    /// ```no_run
-    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    /// Moves access control entries at or below `from` so they sit at the same place below `to`.
+    fn relocate_access(&mut self, from: &Path, to: &Path) {
+        let moved: Vec<PathBuf> = self
+            .access
+            .keys()
+            .filter(|path| path.starts_with(from))
+            .cloned()
+            .collect();
+
+        for path in moved {
+            if let (Some(entries), Some(rebased)) =
+                (self.access.remove(&path), rebase_path(&path, from, to))
+            {
+                self.access.insert(rebased, entries);
+            }
+        }
+    }
+
+    /// Moves reservations at or below `from` so they follow a renamed or migrated item.
+    fn relocate_reservations(&mut self, from: &Path, to: &Path) {
+        self.reservations = self
+            .reservations
+            .drain()
+            .map(|path| rebase_path(&path, from, to).unwrap_or(path))
+            .collect();
+    }
+
+    /// Rewrites every stored path at or below `from` so it sits at the same place below `to`.
     ///
-    /// fn main() -> Result<(), DatabaseError> {
-    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
-    ///     manager.write_new(ItemId::id("blob.bin"), ItemId::database_id())?;
-    ///     manager.overwrite_existing(ItemId::id("blob.bin"), [1_u8, 2, 3, 4])?;
-    ///     Ok(())
-    /// }
-    /// ```
-    pub fn overwrite_existing<T>(&self, id: impl Into<ItemId>, data: T) -> Result<(), DatabaseError>
-    where
-        T: AsRef<[u8]>,
-    {
-        let id = id.into();
-        let bytes = data.as_ref();
+    /// Returns how many index entries were rewritten.
+    fn relocate_index_paths(&mut self, from: &Path, to: &Path) -> usize {
+        let mut relocated = 0usize;
 
-        let path = self.locate_absolute(id)?;
+        for paths in self.items.values_mut() {
+            for (_, path) in paths.iter_mut() {
+                if let Some(rebased) = rebase_path(path, from, to) {
+                    *path = rebased;
+                    relocated += 1;
+                }
+            }
+        }
 
-        self.overwrite_path_atomic_with(&path, |file| {
-            file.write_all(bytes)?;
-            Ok(bytes.len() as u64)
-        })?;
+        self.records = self
+            .records
+            .drain()
+            .map(|(path, record)| (rebase_path(&path, from, to).unwrap_or(path), record))
+            .collect();
 
-        Ok(())
+        relocated
     }
+}
 
-    /// Converts `value` to JSON and overwrites the target file.
-    ///
-    /// # Parameters
-    /// - `id`: target file **`ItemId`**.
-    /// - `value`: serializable value.
-    /// - `pretty`: if `true`, writes pretty-printed JSON; otherwise compact JSON.
+/// A view of a **`DatabaseManager`** that checks access control entries for one principal.
+///
+/// Create it with `DatabaseManager::scoped`. Every operation is checked against the entries set
+/// with `DatabaseManager::set_access` before it reaches the underlying manager, and fails with
+/// `DatabaseError::AccessDenied` when the principal is not allowed.
+///
+/// Enforcement is application-level only. It does not change filesystem permissions.
+#[derive(Debug)]
+pub struct ScopedManager<'a> {
+    manager: &'a mut DatabaseManager,
+    principal: String,
+}
+
+impl ScopedManager<'_> {
+    /// Returns the principal this view enforces access for.
+    pub fn get_principal(&self) -> &str {
+        &self.principal
+    }
+
+    /// Returns whether this principal may perform `operation` on `id`.
     ///
     /// # Errors
     /// Returns an error if:
-    /// - JSON serialization fails,
-    /// - finding `id` or overwriting the file fails.
-    ///
-    /// # Examples
-    /// ```no_run
-    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
-    /// use serde::Serialize;
-    ///
-    /// #[derive(Serialize)]
-    /// struct Config {
-    ///     retries: u8,
-    /// }
-    ///
-    /// fn main() -> Result<(), DatabaseError> {
-    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
-    ///     manager.write_new(ItemId::id("config.json"), ItemId::database_id())?;
-    ///     manager.overwrite_existing_json(ItemId::id("config.json"), &Config { retries: 3 }, false)?;
-    ///     Ok(())
-    /// }
-    /// ```
-    pub fn overwrite_existing_json<T: serde::Serialize>(
+    /// - `id` cannot be found.
+    pub fn can(
         &self,
         id: impl Into<ItemId>,
-        value: &T,
-        pretty: impl Into<bool>,
-    ) -> Result<(), DatabaseError> {
-        let pretty = pretty.into();
-        let data = if pretty {
-            serde_json::to_vec_pretty(value)?
-        } else {
-            serde_json::to_vec(value)?
-        };
-        self.overwrite_existing(id, data)
+        operation: AccessOperation,
+    ) -> Result<bool, DatabaseError> {
+        self.manager.is_allowed(id, &self.principal, operation)
     }
 
-    /// Converts `value` to bincode and overwrites the target file.
-    ///
-    /// # Parameters
-    /// - `id`: target file **`ItemId`**.
-    /// - `value`: serializable value.
+    /// Creates a new item under `parent`. Requires `AccessOperation::Create` on `parent` and at
+    /// the path the new item would get.
     ///
-    /// # Errors
-    /// Returns an error if:
-    /// - bincode serialization fails,
-    /// - finding `id` or overwriting the file fails.
+    /// See `DatabaseManager::write_new` for the remaining errors.
     ///
     /// # Examples
-    /// ```no_run
-    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
-    /// use serde::Serialize;
-    ///
-    /// #[derive(Serialize)]
-    /// enum State {
-    ///     Ready,
-    /// }
+    /// ```
+    /// use file_database::{AccessOperation, DatabaseError, DatabaseManager, ItemId};
     ///
     /// fn main() -> Result<(), DatabaseError> {
-    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
-    ///     manager.write_new(ItemId::id("state.bin"), ItemId::database_id())?;
-    ///     manager.overwrite_existing_binary(ItemId::id("state.bin"), &State::Ready)?;
+    ///     let parent = std::env::temp_dir().join("file_database_scoped_escape");
+    ///     let _ = std::fs::remove_dir_all(&parent);
+    ///     std::fs::create_dir_all(&parent)?;
+    ///     let mut manager = DatabaseManager::create_database(&parent, "database")?;
+    ///     manager.write_new(ItemId::id("plugin_a"), ItemId::database_id())?;
+    ///     manager.set_access(ItemId::id("plugin_a"), "plugin_a", [AccessOperation::Create])?;
+    ///
+    ///     let mut scoped = manager.scoped("plugin_a");
+    ///     assert!(scoped.write_new(ItemId::id("../escape.json"), ItemId::id("plugin_a")).is_err());
+    ///     assert!(!parent.join("database/escape.json").exists());
+    ///     scoped.write_new(ItemId::id("state.json"), ItemId::id("plugin_a"))?;
     ///     Ok(())
     /// }
     /// ```
-    pub fn overwrite_existing_binary<T: serde::Serialize>(
-        &self,
+    pub fn write_new(
+        &mut self,
         id: impl Into<ItemId>,
-        value: &T,
-    ) -> Result<(), DatabaseError> {
-        let data = bincode::serialize(value)?;
-        self.overwrite_existing(id, data)
+        parent: impl Into<ItemId>,
+    ) -> Result<CreatedItem, DatabaseError> {
+        let id = id.into();
+        let parent = parent.into();
+        let destination = self.manager.child_path(&parent, id.get_name())?;
+        self.check(&parent, AccessOperation::Create)?;
+        self.check_path(&destination, AccessOperation::Create, &id)?;
+        self.manager.write_new(id, parent)
     }
 
-    /// Streams bytes from `reader` into the target file and returns bytes written.
-    ///
-    /// This uses chunked I/O and a safe replace step, so it works well for large payloads.
+    /// Reads a managed file. Requires `AccessOperation::Read` on `id`.
     ///
-    /// # Parameters
-    /// - `id`: target file **`ItemId`**.
-    /// - `reader`: source stream consumed until EOF.
+    /// See `DatabaseManager::read_existing` for the remaining errors.
+    pub fn read_existing(&self, id: impl Into<ItemId>) -> Result<Vec<u8>, DatabaseError> {
+        let id = id.into();
+        self.check(&id, AccessOperation::Read)?;
+        self.manager.read_existing(id)
+    }
+
+    /// Overwrites a managed file. Requires `AccessOperation::Write` on `id`.
     ///
-    /// # Errors
-    /// Returns an error if:
-    /// - `id` cannot be found,
-    /// - target is not a file,
-    /// - stream read/write/sync/rename fails.
+    /// See `DatabaseManager::overwrite_existing` for the remaining errors.
+    pub fn overwrite_existing<T>(&self, id: impl Into<ItemId>, data: T) -> Result<(), DatabaseError>
+    where
+        T: AsRef<[u8]>,
+    {
+        let id = id.into();
+        self.check(&id, AccessOperation::Write)?;
+        self.manager.overwrite_existing(id, data)
+    }
+
+    /// Renames a managed item. Requires `AccessOperation::Write` on `id` and at the path it is
+    /// renamed to.
     ///
-    /// # Examples
-    /// ```no_run
-    /// use std::io::Cursor;
-    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    /// See `DatabaseManager::rename` for the remaining errors.
+    pub fn rename(
+        &mut self,
+        id: impl Into<ItemId>,
+        to: impl AsRef<str>,
+    ) -> Result<ItemId, DatabaseError> {
+        let id = id.into();
+        let destination = self.manager.renamed_path(&id, to.as_ref())?;
+        self.check(&id, AccessOperation::Write)?;
+        self.check_path(
+            &destination,
+            AccessOperation::Write,
+            &ItemId::id(to.as_ref()),
+        )?;
+        self.manager.rename(id, to)
+    }
+
+    /// Deletes a managed item. Requires `AccessOperation::Delete` on `id`.
     ///
-    /// fn main() -> Result<(), DatabaseError> {
-    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
-    ///     manager.write_new(ItemId::id("stream.bin"), ItemId::database_id())?;
-    ///     let mut source = Cursor::new(vec![9_u8; 1024]);
-    ///     let _bytes = manager.overwrite_existing_from_reader(ItemId::id("stream.bin"), &mut source)?;
-    ///     Ok(())
-    /// }
-    /// ```
-    pub fn overwrite_existing_from_reader<R: io::Read>(
-        &self,
+    /// See `DatabaseManager::delete` for the remaining errors.
+    pub fn delete(
+        &mut self,
         id: impl Into<ItemId>,
-        reader: &mut R,
-    ) -> Result<u64, DatabaseError> {
+        force: impl Into<bool>,
+    ) -> Result<DeleteSummary, DatabaseError> {
         let id = id.into();
-        let path = self.locate_absolute(id)?;
-        self.overwrite_path_atomic_with(&path, |file| Ok(io::copy(reader, file)?))
+        self.check(&id, AccessOperation::Delete)?;
+        self.manager.delete(id, force)
     }
 
-    /// Reads a managed file and returns its raw bytes.
+    /// Lists the direct children of `parent`. Requires `AccessOperation::Read` on `parent`.
     ///
-    /// # Parameters
-    /// - `id`: target file **`ItemId`**.
+    /// See `DatabaseManager::get_by_parent` for the remaining errors.
+    pub fn get_by_parent(
+        &self,
+        parent: impl Into<ItemId>,
+        sort: impl Into<SortBy>,
+    ) -> Result<Vec<ItemId>, DatabaseError> {
+        let parent = parent.into();
+        self.check(&parent, AccessOperation::Read)?;
+        self.manager.get_by_parent(parent, sort)
+    }
+
+    /// Returns `DatabaseError::AccessDenied` unless `operation` is allowed on `id`.
+    fn check(&self, id: &ItemId, operation: AccessOperation) -> Result<(), DatabaseError> {
+        if self.manager.is_allowed(id, &self.principal, operation)? {
+            Ok(())
+        } else {
+            Err(DatabaseError::AccessDenied(
+                self.principal.clone(),
+                operation,
+                id.as_string(),
+            ))
+        }
+    }
+
+    /// Returns `DatabaseError::AccessDenied` unless `operation` is allowed at the
+    /// database-relative `path`, which `id` is about to occupy.
+    fn check_path(
+        &self,
+        path: &Path,
+        operation: AccessOperation,
+        id: &ItemId,
+    ) -> Result<(), DatabaseError> {
+        let allowed = self
+            .manager
+            .resolve_access(path, &self.principal)
+            .is_some_and(|operations| operations.contains(&operation));
+        if allowed {
+            Ok(())
+        } else {
+            Err(DatabaseError::AccessDenied(
+                self.principal.clone(),
+                operation,
+                id.as_string(),
+            ))
+        }
+    }
+}
+
+/// A view of a **`DatabaseManager`** where every **`ItemId`** resolves inside one namespace directory.
+///
+/// Create it with `DatabaseManager::namespace`. `ItemId::database_id()` refers to the namespace
+/// directory, paths are reported relative to it, and IDs stored in other namespaces fail with
+/// `DatabaseError::NoMatchingID` as if they did not exist.
+#[derive(Debug)]
+pub struct NamespaceView<'a> {
+    manager: &'a mut DatabaseManager,
+    root: PathBuf,
+}
+
+impl NamespaceView<'_> {
+    /// Returns the namespace directory path relative to the database root.
+    pub fn get_root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Creates a new file or directory under `parent` inside this namespace.
     ///
-    /// # Errors
-    /// Returns an error if:
-    /// - `id` cannot be found,
-    /// - `id` points to a directory,
-    /// - file reading fails.
+    /// Names that would place the item outside the namespace, such as `"../other/a.txt"`, fail
+    /// with `DatabaseError::InvalidItemName`. See `DatabaseManager::write_new` for naming rules
+    /// and the remaining errors.
     ///
     /// # Examples
-    /// ```no_run
+    /// ```
     /// use file_database::{DatabaseError, DatabaseManager, ItemId};
     ///
     /// fn main() -> Result<(), DatabaseError> {
-    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
-    ///     manager.write_new(ItemId::id("data.bin"), ItemId::database_id())?;
-    ///     manager.overwrite_existing(ItemId::id("data.bin"), [1_u8, 2, 3])?;
-    ///     let _data = manager.read_existing(ItemId::id("data.bin"))?;
+    ///     let parent = std::env::temp_dir().join("file_database_namespace_escape");
+    ///     let _ = std::fs::remove_dir_all(&parent);
+    ///     std::fs::create_dir_all(&parent)?;
+    ///     let mut manager = DatabaseManager::create_database(&parent, "database")?;
+    ///     manager.namespace("tenant_b")?;
+    ///
+    ///     let mut tenant_a = manager.namespace("tenant_a")?;
+    ///     let escape = tenant_a.write_new(ItemId::id("../tenant_b/evil.txt"), ItemId::database_id());
+    ///     assert!(matches!(escape, Err(DatabaseError::InvalidItemName(..))));
+    ///     tenant_a.write_new(ItemId::id("own.txt"), ItemId::database_id())?;
+    ///     assert!(tenant_a.rename(ItemId::id("own.txt"), "../evil.txt").is_err());
+    ///
+    ///     drop(tenant_a);
+    ///     assert!(!parent.join("database/tenant_b/evil.txt").exists());
+    ///     assert!(!parent.join("database/evil.txt").exists());
+    ///
+    ///     assert!(matches!(manager.namespace(".."), Err(DatabaseError::InvalidItemName(..))));
+    ///     assert!(matches!(manager.namespace("/"), Err(DatabaseError::InvalidItemName(..))));
+    ///     assert!(matches!(manager.namespace("."), Err(DatabaseError::InvalidItemName(..))));
     ///     Ok(())
     /// }
     /// ```
+    pub fn write_new(
+        &mut self,
+        id: impl Into<ItemId>,
+        parent: impl Into<ItemId>,
+    ) -> Result<CreatedItem, DatabaseError> {
+        let id = id.into();
+        let parent = self.manager.id_in_subtree(&self.root, &parent.into())?;
+        let destination = self.manager.child_path(&parent, id.get_name())?;
+        DatabaseManager::ensure_inside(&self.root, &destination, id.get_name())?;
+        self.manager.write_new(id, parent)
+    }
+
+    /// Reads a managed file inside this namespace.
+    ///
+    /// See `DatabaseManager::read_existing` for the remaining errors.
     pub fn read_existing(&self, id: impl Into<ItemId>) -> Result<Vec<u8>, DatabaseError> {
+        let id = self.manager.id_in_subtree(&self.root, &id.into())?;
+        self.manager.read_existing(id)
+    }
+
+    /// Overwrites a managed file inside this namespace.
+    ///
+    /// See `DatabaseManager::overwrite_existing` for the remaining errors.
+    pub fn overwrite_existing<T>(&self, id: impl Into<ItemId>, data: T) -> Result<(), DatabaseError>
+    where
+        T: AsRef<[u8]>,
+    {
+        let id = self.manager.id_in_subtree(&self.root, &id.into())?;
+        self.manager.overwrite_existing(id, data)
+    }
+
+    /// Renames an item inside this namespace.
+    ///
+    /// The namespace directory itself cannot be renamed through the view.
+    ///
+    /// See `DatabaseManager::rename` for the remaining errors.
+    pub fn rename(
+        &mut self,
+        id: impl Into<ItemId>,
+        to: impl AsRef<str>,
+    ) -> Result<ItemId, DatabaseError> {
         let id = id.into();
-        let path = self.locate_absolute(id)?;
+        if id.get_name().is_empty() {
+            return Err(DatabaseError::RootIdUnsupported);
+        }
 
-        if path.is_dir() {
-            return Err(DatabaseError::NotAFile(path));
+        let id = self.manager.id_in_subtree(&self.root, &id)?;
+        let destination = self.manager.renamed_path(&id, to.as_ref())?;
+        DatabaseManager::ensure_inside(&self.root, &destination, to.as_ref())?;
+        self.manager.rename(id, to)
+    }
+
+    /// Deletes an item inside this namespace.
+    ///
+    /// The namespace directory itself cannot be deleted through the view.
+    ///
+    /// See `DatabaseManager::delete` for the remaining errors.
+    pub fn delete(
+        &mut self,
+        id: impl Into<ItemId>,
+        force: impl Into<bool>,
+    ) -> Result<DeleteSummary, DatabaseError> {
+        let id = id.into();
+        if id.get_name().is_empty() {
+            return Err(DatabaseError::RootIdUnsupported);
         }
 
-        Ok(fs::read(path)?)
+        let id = self.manager.id_in_subtree(&self.root, &id)?;
+        self.manager.delete(id, force)
     }
 
-    /// Reads a managed file and turns JSON into `T`.
+    /// Returns all tracked items that are direct children of `parent` inside this namespace.
     ///
-    /// # Parameters
-    /// - `id`: target file **`ItemId`**.
+    /// See `DatabaseManager::get_by_parent` for the remaining errors.
+    pub fn get_by_parent(
+        &self,
+        parent: impl Into<ItemId>,
+        sort: impl Into<SortBy>,
+    ) -> Result<Vec<ItemId>, DatabaseError> {
+        let parent = self.manager.id_in_subtree(&self.root, &parent.into())?;
+        self.manager.get_by_parent(parent, sort)
+    }
+
+    /// Returns every tracked item inside this namespace.
+    pub fn get_all(&self, sort: impl Into<SortBy>) -> Vec<ItemId> {
+        let items = self
+            .manager
+            .all_paths()
+            .into_iter()
+            .filter(|(_, path)| path.starts_with(&self.root) && **path != self.root)
+            .collect();
+
+        sort_listing(items, sort.into(), &self.manager.path)
+    }
+
+    /// Gets the absolute file path for an **`ItemId`** inside this namespace.
     ///
     /// # Errors
     /// Returns an error if:
-    /// - finding `id` or reading the file fails,
-    /// - JSON deserialization fails.
+    /// - `id` does not exist inside this namespace.
+    pub fn locate_absolute(&self, id: impl Into<ItemId>) -> Result<PathBuf, DatabaseError> {
+        let path = self
+            .manager
+            .relative_path_in_subtree(&self.root, &id.into())?;
+        Ok(self.manager.path.join(path))
+    }
+
+    /// Gets an item's path relative to the namespace directory.
     ///
-    /// # Examples
-    /// ```no_run
-    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
-    /// use serde::{Deserialize, Serialize};
+    /// `ItemId::database_id()` returns an empty **`RelativePath`**.
     ///
-    /// #[derive(Serialize, Deserialize)]
-    /// struct Config {
-    ///     retries: u8,
-    /// }
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` does not exist inside this namespace.
+    pub fn locate_relative(&self, id: impl Into<ItemId>) -> Result<RelativePath, DatabaseError> {
+        let path = self
+            .manager
+            .relative_path_in_subtree(&self.root, &id.into())?;
+        Ok(RelativePath(path.strip_prefix(&self.root)?.to_path_buf()))
+    }
+}
+
+/// A read-only view of a **`DatabaseManager`** restricted to one directory subtree.
+///
+/// Create it with `DatabaseManager::read_only_view`. It borrows the manager immutably and only
+/// offers non-mutating methods, so code holding it cannot change anything. Inside the view,
+/// `ItemId::database_id()` refers to the view's root directory, and IDs outside the subtree
+/// fail with `DatabaseError::NoMatchingID` as if they did not exist.
+#[derive(Debug, Clone)]
+pub struct ReadOnlyView<'a> {
+    manager: &'a DatabaseManager,
+    root: PathBuf,
+}
+
+impl ReadOnlyView<'_> {
+    /// Returns the view's root directory path relative to the database root.
+    pub fn get_root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Reads a managed file inside this view.
     ///
-    /// fn main() -> Result<(), DatabaseError> {
-    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
-    ///     manager.write_new(ItemId::id("config.json"), ItemId::database_id())?;
-    ///     manager.overwrite_existing_json(ItemId::id("config.json"), &Config { retries: 3 }, false)?;
-    ///     let _loaded: Config = manager.read_existing_json(ItemId::id("config.json"))?;
-    ///     Ok(())
-    /// }
-    /// ```
+    /// See `DatabaseManager::read_existing` for the remaining errors.
+    pub fn read_existing(&self, id: impl Into<ItemId>) -> Result<Vec<u8>, DatabaseError> {
+        let id = self.manager.id_in_subtree(&self.root, &id.into())?;
+        self.manager.read_existing(id)
+    }
+
+    /// Reads a managed file inside this view and turns JSON into `T`.
+    ///
+    /// See `DatabaseManager::read_existing_json` for the remaining errors.
     pub fn read_existing_json<T: serde::de::DeserializeOwned>(
         &self,
         id: impl Into<ItemId>,
     ) -> Result<T, DatabaseError> {
-        let bytes = self.read_existing(id)?;
-        Ok(serde_json::from_slice(&bytes)?)
+        let id = self.manager.id_in_subtree(&self.root, &id.into())?;
+        self.manager.read_existing_json(id)
     }
 
-    /// Reads a managed file and turns bincode into `T`.
-    ///
-    /// # Parameters
-    /// - `id`: target file **`ItemId`**.
-    ///
-    /// # Errors
-    /// Returns an error if:
-    /// - finding `id` or reading the file fails,
-    /// - bincode deserialization fails.
-    ///
-    /// # Examples
-    /// ```no_run
-    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
-    /// use serde::{Deserialize, Serialize};
+    /// Reads a managed file inside this view and turns bincode into `T`.
     ///
-    /// #[derive(Serialize, Deserialize)]
-    /// enum State {
-    ///     Ready,
-    /// }
-    ///
-    /// fn main() -> Result<(), DatabaseError> {
-    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
-    ///     manager.write_new(ItemId::id("state.bin"), ItemId::database_id())?;
-    ///     manager.overwrite_existing_binary(ItemId::id("state.bin"), &State::Ready)?;
-    ///     let _loaded: State = manager.read_existing_binary(ItemId::id("state.bin"))?;
-    ///     Ok(())
-    /// }
-    /// ```
+    /// See `DatabaseManager::read_existing_binary` for the remaining errors.
     pub fn read_existing_binary<T: serde::de::DeserializeOwned>(
         &self,
         id: impl Into<ItemId>,
     ) -> Result<T, DatabaseError> {
-        let bytes = self.read_existing(id)?;
-        Ok(bincode::deserialize(&bytes)?)
+        let id = self.manager.id_in_subtree(&self.root, &id.into())?;
+        self.manager.read_existing_binary(id)
     }
 
-    /// Returns every tracked item in the database.
+    /// Returns metadata for an item inside this view.
     ///
-    /// # Parameters
-    /// - `sorted`: whether output should be sorted by **`ItemId`** ordering.
-    ///
-    /// # Examples
-    /// ```no_run
-    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
-    ///
-    /// fn main() -> Result<(), DatabaseError> {
-    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
-    ///     manager.write_new(ItemId::id("a.txt"), ItemId::database_id())?;
-    ///     let _all = manager.get_all(true);
-    ///     Ok(())
-    /// }
-    /// ```
-    pub fn get_all(&self, sorted: impl Into<bool>) -> Vec<ItemId> {
-        let sorted = sorted.into();
-
-        let mut list: Vec<ItemId> = self
-            .items
-            .iter()
-            .flat_map(|(name, paths)| {
-                paths
-                    .iter()
-                    .map(|(index, _)| ItemId::with_index(name.clone(), index))
-                    .collect::<Vec<ItemId>>()
-            })
-            .collect();
-
-        if sorted {
-            list.sort();
-        }
-
-        list
+    /// See `DatabaseManager::get_file_information` for the remaining errors.
+    pub fn get_file_information(
+        &self,
+        id: impl Into<ItemId>,
+    ) -> Result<FileInformation, DatabaseError> {
+        let id = self.manager.id_in_subtree(&self.root, &id.into())?;
+        self.manager.get_file_information(id)
     }
 
-    /// Returns all tracked items that are direct children of `parent`.
-    ///
-    /// If `parent` is the `ItemId::database_id()`, this returns all top-level items.
-    ///
-    /// # Parameters
-    /// - `parent`: parent directory item to query.
-    /// - `sorted`: whether output should be sorted by **`ItemId`**.
-    ///
-    /// # Errors
-    /// Returns an error if:
-    /// - `parent` cannot be found,
-    /// - `parent` points to a file instead of a directory.
+    /// Returns metadata for an item inside this view, falling back to what the `index` knows
+    /// when the metadata lookup fails.
     ///
-    /// # Examples
-    /// ```no_run
-    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    /// See `DatabaseManager::get_file_information_lenient` for details and the remaining errors.
+    pub fn get_file_information_lenient(
+        &self,
+        id: impl Into<ItemId>,
+    ) -> Result<(FileInformation, Option<DatabaseError>), DatabaseError> {
+        let id = self.manager.id_in_subtree(&self.root, &id.into())?;
+        self.manager.get_file_information_lenient(id)
+    }
+
+    /// Returns all tracked items that are direct children of `parent` inside this view.
     ///
-    /// fn main() -> Result<(), DatabaseError> {
-    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
-    ///     manager.write_new(ItemId::id("folder"), ItemId::database_id())?;
-    ///     manager.write_new(ItemId::id("a.txt"), ItemId::id("folder"))?;
-    ///     let _children = manager.get_by_parent(ItemId::id("folder"), true)?;
-    ///     Ok(())
-    /// }
-    /// ```
+    /// See `DatabaseManager::get_by_parent` for the remaining errors.
     pub fn get_by_parent(
         &self,
         parent: impl Into<ItemId>,
-        sorted: impl Into<bool>,
+        sort: impl Into<SortBy>,
     ) -> Result<Vec<ItemId>, DatabaseError> {
-        let parent = parent.into();
-        let sorted = sorted.into();
-
-        let absolute_parent = self.locate_absolute(&parent)?;
-
-        if !absolute_parent.is_dir() {
-            return Err(DatabaseError::NotADirectory(absolute_parent));
-        }
-
-        let mut list: Vec<ItemId> = Vec::new();
-        let parent_path = if parent.get_name().is_empty() {
-            None
-        } else {
-            Some(self.locate_relative(&parent)?.as_path())
-        };
-
-        for (name, paths) in &self.items {
-            for (index, item_path) in paths.iter() {
-                let is_match = match parent_path {
-                    None => item_path
-                        .parent()
-                        .is_some_and(|path| path.as_os_str().is_empty()),
-                    Some(parent_path) => item_path.parent() == Some(parent_path),
-                };
-
-                if is_match {
-                    list.push(ItemId::with_index(name.clone(), index));
-                }
-            }
-        }
+        let parent = self.manager.id_in_subtree(&self.root, &parent.into())?;
+        self.manager.get_by_parent(parent, sort)
+    }
 
-        if sorted {
-            list.sort();
-        }
+    /// Returns every tracked item inside this view.
+    pub fn get_all(&self, sort: impl Into<SortBy>) -> Vec<ItemId> {
+        let items = self
+            .manager
+            .all_paths()
+            .into_iter()
+            .filter(|(_, path)| path.starts_with(&self.root) && **path != self.root)
+            .collect();
 
-        Ok(list)
+        sort_listing(items, sort.into(), &self.manager.path)
     }
 
-    /// Returns the parent **`ItemId`** for an item.
-    ///
-    /// Top-level items return [`ItemId::database_id`].
-    ///
-    /// # Parameters
-    /// - `id`: item whose parent should be looked up.
+    /// Gets the absolute file path for an **`ItemId`** inside this view.
     ///
     /// # Errors
     /// Returns an error if:
-    /// - `id` cannot be found,
-    /// - parent path data cannot be converted to UTF-8 string.
+    /// - `id` does not exist inside this view.
+    pub fn locate_absolute(&self, id: impl Into<ItemId>) -> Result<PathBuf, DatabaseError> {
+        let path = self
+            .manager
+            .relative_path_in_subtree(&self.root, &id.into())?;
+        Ok(self.manager.path.join(path))
+    }
+
+    /// Gets an item's path relative to the view's root directory.
     ///
-    /// # Examples
-    /// ```no_run
-    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    /// `ItemId::database_id()` returns an empty **`RelativePath`**.
     ///
-    /// fn main() -> Result<(), DatabaseError> {
-    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
-    ///     manager.write_new(ItemId::id("folder"), ItemId::database_id())?;
-    ///     manager.write_new(ItemId::id("a.txt"), ItemId::id("folder"))?;
-    ///     let _parent = manager.get_parent(ItemId::id("a.txt"))?;
-    ///     Ok(())
-    /// }
-    /// ```
-    pub fn get_parent(&self, id: impl Into<ItemId>) -> Result<ItemId, DatabaseError> {
-        let id = id.into();
-        let path = self.locate_relative(&id)?;
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` does not exist inside this view.
+    pub fn locate_relative(&self, id: impl Into<ItemId>) -> Result<RelativePath, DatabaseError> {
+        let path = self
+            .manager
+            .relative_path_in_subtree(&self.root, &id.into())?;
+        Ok(RelativePath(path.strip_prefix(&self.root)?.to_path_buf()))
+    }
+}
 
-        let parent = match path.parent() {
-            Some(parent) => parent,
-            None => return Ok(ItemId::database_id()),
-        };
+/// A validated handle to one managed file.
+///
+/// Create it with `DatabaseManager::file`. The file-vs-directory check happens once, when the
+/// handle is created, and the resolved path is reused by every method.
+#[derive(Debug)]
+pub struct FileHandle<'a> {
+    manager: &'a DatabaseManager,
+    id: ItemId,
+    path: PathBuf,
+}
 
-        if parent.as_os_str().is_empty() {
-            return Ok(ItemId::database_id());
-        }
+impl FileHandle<'_> {
+    /// Returns the **`ItemId`** this handle was created for.
+    pub fn get_id(&self) -> &ItemId {
+        &self.id
+    }
 
-        for (name, paths) in &self.items {
-            for (index, item_path) in paths.iter() {
-                if item_path.as_path() == parent {
-                    return Ok(ItemId::with_index(name.clone(), index));
-                }
-            }
-        }
+    /// Returns the absolute path resolved when the handle was created.
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
 
-        match parent.file_name() {
-            Some(name) => Ok(ItemId::id(os_str_to_string(Some(name))?)),
-            None => Err(DatabaseError::NoParent(id.as_string())),
+    /// Reads the whole file and returns its raw bytes, including queued write-behind data.
+    ///
+    /// # Errors
+    /// Returns an error if file reading fails.
+    pub fn read(&self) -> Result<Vec<u8>, DatabaseError> {
+        if let Some(pending) = self.manager.write_behind.lock().get(&self.path) {
+            return Ok(pending.data.clone());
         }
+
+        Ok(fs::read(&self.path)?)
     }
 
-    /// Renames the chosen item to `to` in the same parent directory.
+    /// Overwrites the file with raw bytes using the same safe replace as `overwrite_existing`.
     ///
-    /// # Parameters
-    /// - `id`: source **`ItemId`** to rename.
-    /// - `to`: new file or directory name.
+    /// Queued write-behind data for the file is dropped, since these contents replace it.
     ///
     /// # Errors
-    /// Returns an error if:
-    /// - `id` is the `ItemId::database_id()`,
-    /// - `id` cannot be found,
-    /// - destination `name` already exists at the same relative `path`,
-    /// - underlying filesystem rename fails.
+    /// Returns an error if writing, syncing, or renaming fails.
     ///
     /// # Examples
-    /// ```no_run
+    /// ```
     /// use file_database::{DatabaseError, DatabaseManager, ItemId};
+    /// use std::time::Duration;
     ///
     /// fn main() -> Result<(), DatabaseError> {
-    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
-    ///     manager.write_new(ItemId::id("old.txt"), ItemId::database_id())?;
-    ///     manager.rename(ItemId::id("old.txt"), "new.txt")?;
+    ///     let parent = std::env::temp_dir().join("file_database_handle_write_behind");
+    ///     let _ = std::fs::remove_dir_all(&parent);
+    ///     std::fs::create_dir_all(&parent)?;
+    ///     let mut manager = DatabaseManager::create_database(&parent, "database")?;
+    ///     manager.write_new(ItemId::id("a.txt"), ItemId::database_id())?;
+    ///     manager.set_write_behind(Some(Duration::from_secs(60)))?;
+    ///
+    ///     manager.overwrite_existing(ItemId::id("a.txt"), "queued-old")?;
+    ///     let handle = manager.file(ItemId::id("a.txt"))?;
+    ///     assert_eq!(handle.read()?, b"queued-old");
+    ///     handle.write("handle-new")?;
+    ///     manager.flush()?;
+    ///     assert_eq!(manager.read_existing(ItemId::id("a.txt"))?, b"handle-new");
+    ///
+    ///     manager.overwrite_existing(ItemId::id("a.txt"), "queued")?;
+    ///     manager.file(ItemId::id("a.txt"))?.append("+tail")?;
+    ///     manager.flush()?;
+    ///     assert_eq!(manager.read_existing(ItemId::id("a.txt"))?, b"queued+tail");
     ///     Ok(())
     /// }
     /// ```
-    pub fn rename(
-        &mut self,
-        id: impl Into<ItemId>,
-        to: impl AsRef<str>,
-    ) -> Result<(), DatabaseError> {
-        let id = id.into();
-        let name = to.as_ref().to_owned();
+    pub fn write(&self, data: impl AsRef<[u8]>) -> Result<(), DatabaseError> {
+        let bytes = data.as_ref();
+        self.manager.write_behind.lock().remove(&self.path);
+        self.manager
+            .overwrite_path_atomic_with(&self.path, |file| {
+                file.write_all(bytes)?;
+                Ok(bytes.len() as u64)
+            })?;
+        Ok(())
+    }
 
-        if id.get_name().is_empty() {
-            return Err(DatabaseError::RootIdUnsupported);
-        }
+    /// Shrinks or extends the file to `new_len` bytes in place, like `truncate_existing`.
+    ///
+    /// # Errors
+    /// Returns an error if opening, resizing, or syncing the file fails.
+    pub fn set_len(&self, new_len: u64) -> Result<(), DatabaseError> {
+        self.manager.set_path_len(&self.path, new_len)
+    }
 
-        let path = self.locate_absolute(&id)?;
-        let mut relative_path = self.locate_relative(&id)?.to_path_buf();
+    /// Appends raw bytes to the end of the file without rewriting existing contents.
+    ///
+    /// Queued write-behind data for the file is flushed first, so the bytes land after it.
+    ///
+    /// # Errors
+    /// Returns an error if opening or writing the file fails.
+    pub fn append(&self, data: impl AsRef<[u8]>) -> Result<(), DatabaseError> {
+        self.manager.materialize_external(&self.path)?;
+        self.manager
+            .flush_pending_writes(|pending, _| pending == self.path)?;
+        let mut file = fs::OpenOptions::new().append(true).open(&self.path)?;
+        file.write_all(data.as_ref())?;
+        self.manager.emit_overwritten(&self.path);
+        self.manager.after_mutation_at(&self.path)?;
+        Ok(())
+    }
+}
 
-        let renamed_path = path.with_file_name(&name);
-        relative_path = match relative_path.pop() {
-            true => {
-                relative_path.push(&name);
-                relative_path
-            }
-            false => PathBuf::from(&name),
-        };
+/// An OS file lock on one managed item, returned by `DatabaseManager::lock_item`.
+///
+/// The lock is released when the guard is dropped.
+#[derive(Debug)]
+pub struct ItemLock {
+    id: ItemId,
+    kind: LockKind,
+    file: File,
+}
 
-        let new_id = ItemId::with_index(name.clone(), id.get_index());
+impl ItemLock {
+    /// Returns the locked **`ItemId`**.
+    pub fn get_id(&self) -> &ItemId {
+        &self.id
+    }
 
-        if self
-            .all_paths()
-            .iter()
-            .any(|(entry_id, entry_path)| entry_id != &id && *entry_path == &relative_path)
-        {
-            return Err(DatabaseError::IdAlreadyExists(new_id.as_string()));
-        }
+    /// Returns whether the lock is shared or exclusive.
+    pub fn get_kind(&self) -> LockKind {
+        self.kind
+    }
+}
+
+impl Drop for ItemLock {
+    /// Releases the lock.
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
 
-        fs::rename(&path, renamed_path)?;
+/// A streaming writer that replaces one managed file atomically when committed.
+///
+/// Create it with `DatabaseManager::open_writer`. Bytes go to a staging file next to the target;
+/// the target keeps its old contents until `commit` syncs the staging file and renames it into
+/// place. Dropping the writer without committing discards everything written.
+#[derive(Debug)]
+pub struct ItemWriter<'a> {
+    manager: &'a DatabaseManager,
+    path: PathBuf,
+    buffer: PathBuf,
+    file: Option<io::BufWriter<File>>,
+    written: u64,
+}
 
-        self.remove_id_from_index(&id)?;
-        self.insert_path_for_id(&new_id, relative_path)?;
+impl ItemWriter<'_> {
+    /// Returns the absolute path of the file that `commit` replaces.
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
 
-        Ok(())
+    /// Returns how many bytes were written so far.
+    pub fn get_bytes_written(&self) -> u64 {
+        self.written
     }
 
-    /// Deletes a file, directory, or the whole database root.
+    /// Syncs the written data and swaps it into place, returning the number of bytes written.
     ///
-    /// # Parameters
-    /// - `id`: item to delete. Use `ItemId::database_id()` to target the database folder itself.
-    /// - `force`: when deleting directories, controls recursive vs empty-only behavior.
+    /// Queued write-behind data for the file is dropped, since the committed contents replace it.
     ///
     /// # Errors
-    /// Returns an error if:
-    /// - `id` cannot be found,
-    /// - directory deletion does not match `force` rules,
-    /// - filesystem delete operations fail.
-    ///
-    /// # Examples
-    /// ```no_run
-    /// use file_database::{DatabaseError, DatabaseManager, ForceDeletion, ItemId};
-    ///
-    /// fn main() -> Result<(), DatabaseError> {
-    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
-    ///     manager.write_new(ItemId::id("tmp.txt"), ItemId::database_id())?;
-    ///     manager.delete(ItemId::id("tmp.txt"), ForceDeletion::Force)?;
-    ///     Ok(())
-    /// }
-    /// ```
-    pub fn delete(
-        &mut self,
-        id: impl Into<ItemId>,
-        force: impl Into<bool>,
-    ) -> Result<(), DatabaseError> {
-        let id = id.into();
-        let force = force.into();
+    /// Returns an error if flushing, syncing, or renaming fails. The staging file is removed and
+    /// the target keeps its old contents.
+    pub fn commit(mut self) -> Result<u64, DatabaseError> {
+        let Some(file) = self.file.take() else {
+            return Ok(self.written);
+        };
 
-        if id.get_name().is_empty() {
-            match self.delete_directory(&self.locate_absolute(id)?, force) {
-                Ok(_) => {
-                    self.path = PathBuf::new();
-                    self.items.drain();
-                    return Ok(());
+        let result = (|| {
+            let file = file.into_inner().map_err(|error| error.into_error())?;
+            self.manager.sync_file(&file)?;
+            let check = match self.path.strip_prefix(&self.manager.path) {
+                Ok(relative_path) => {
+                    let added = file.metadata()?.len();
+                    let replaced = fs::metadata(&self.path).map_or(0, |metadata| metadata.len());
+                    Some(self.manager.check_quotas(relative_path, added, replaced)?)
                 }
-                Err(error) => return Err(error),
+                Err(_) => None,
+            };
+            fs::rename(&self.buffer, &self.path)?;
+            Ok(check)
+        })();
+
+        let check = match result {
+            Ok(check) => check,
+            Err(error) => {
+                let _ = remove_file(&self.buffer);
+                return Err(error);
             }
+        };
+
+        self.manager.write_behind.lock().remove(&self.path);
+        self.manager.sync_parent_directory(&self.path)?;
+        self.manager.emit_overwritten(&self.path);
+        self.manager.after_mutation_at(&self.path)?;
+        if let Some(check) = check {
+            self.manager.record_quota_usage(check);
         }
 
-        let path = self.locate_absolute(&id)?;
+        Ok(self.written)
+    }
+}
 
-        if path.is_dir() {
-            self.delete_directory(&path, force)?;
-        } else {
-            remove_file(path)?;
-        }
+impl io::Write for ItemWriter<'_> {
+    /// Writes to the staging file.
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let file = self
+            .file
+            .as_mut()
+            .ok_or_else(|| io::Error::other("writer was already committed"))?;
+        let written = file.write(data)?;
+        self.written += written as u64;
+        Ok(written)
+    }
 
-        self.remove_id_from_index(&id)?;
+    /// Flushes buffered bytes to the staging file. The target is only replaced by `commit`.
+    fn flush(&mut self) -> io::Result<()> {
+        match self.file.as_mut() {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
 
-        Ok(())
+impl Drop for ItemWriter<'_> {
+    /// Removes the staging file if the writer was not committed.
+    fn drop(&mut self) {
+        if self.file.take().is_some() {
+            let _ = remove_file(&self.buffer);
+        }
     }
+}
 
-    /// Gets the absolute file path for an **`ItemId`**.
-    ///
-    /// For the `ItemId::database_id()`, this returns the database directory path.
-    ///
-    /// # Parameters
-    /// - `id`: **`ItemId`** to look up.
-    ///
-    /// # Errors
-    /// Returns an error if:
-    /// - `id` does not exist.
-    ///
-    /// # Examples
-    /// ```no_run
-    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
-    ///
-    /// fn main() -> Result<(), DatabaseError> {
-    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
-    ///     manager.write_new(ItemId::id("a.txt"), ItemId::database_id())?;
-    ///     let _path = manager.locate_absolute(ItemId::id("a.txt"))?;
-    ///     Ok(())
-    /// }
-    /// ```
-    pub fn locate_absolute(&self, id: impl Into<ItemId>) -> Result<PathBuf, DatabaseError> {
-        let id = id.into();
+/// A read-only memory map of a managed file, returned by `DatabaseManager::read_existing_mmap`.
+///
+/// Dereferences to `&[u8]` without copying the file into memory. The mapping is released on drop.
+#[cfg(feature = "mmap")]
+#[derive(Debug)]
+pub struct MappedFile {
+    pointer: *const u8,
+    length: usize,
+}
 
-        if id.get_name().is_empty() {
-            return Ok(self.path.to_path_buf());
+// The mapping is private and read-only and is only ever read through `&self`; the file itself
+// not changing underneath it is the contract of `read_existing_mmap`, not of these impls.
+#[cfg(feature = "mmap")]
+unsafe impl Send for MappedFile {}
+#[cfg(feature = "mmap")]
+unsafe impl Sync for MappedFile {}
+
+#[cfg(feature = "mmap")]
+impl std::ops::Deref for MappedFile {
+    type Target = [u8];
+
+    /// Returns the mapped bytes.
+    fn deref(&self) -> &[u8] {
+        if self.length == 0 {
+            return &[];
         }
+        // SAFETY: `pointer` came from a successful `mmap` of `length` readable bytes that stays
+        // mapped until `drop`.
+        unsafe { std::slice::from_raw_parts(self.pointer, self.length) }
+    }
+}
 
-        Ok(self.path.join(self.resolve_path_by_id(&id)?))
+#[cfg(feature = "mmap")]
+impl AsRef<[u8]> for MappedFile {
+    /// Returns the mapped bytes.
+    fn as_ref(&self) -> &[u8] {
+        self
     }
+}
 
-    /// Gets the stored relative path reference for an **`ItemId`**.
-    ///
-    /// For the `ItemId::database_id()`, this currently returns a reference to the manager root path.
-    ///
-    /// # Parameters
-    /// - `id`: **`ItemId`** to look up.
-    ///
-    /// # Errors
-    /// Returns an error if:
-    /// - `id` does not exist.
-    ///
-    /// # Examples
-    /// ```no_run
-    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
-    ///
-    /// fn main() -> Result<(), DatabaseError> {
-    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
-    ///     manager.write_new(ItemId::id("a.txt"), ItemId::database_id())?;
-    ///     let _relative = manager.locate_relative(ItemId::id("a.txt"))?;
-    ///     Ok(())
-    /// }
-    /// ```
-    pub fn locate_relative(&self, id: impl Into<ItemId>) -> Result<&PathBuf, DatabaseError> {
-        let id = id.into();
-        if id.get_name().is_empty() {
-            return Ok(&self.path);
+#[cfg(feature = "mmap")]
+impl Drop for MappedFile {
+    /// Unmaps the file.
+    fn drop(&mut self) {
+        if self.length > 0 {
+            unmap_file(self.pointer, self.length);
         }
+    }
+}
 
-        self.resolve_path_by_id(&id)
+/// A validated handle to one managed directory.
+///
+/// Create it with `DatabaseManager::dir`. The directory check happens once, when the handle is
+/// created.
+#[derive(Debug)]
+pub struct DirHandle<'a> {
+    manager: &'a mut DatabaseManager,
+    id: ItemId,
+    path: PathBuf,
+}
+
+impl DirHandle<'_> {
+    /// Returns the **`ItemId`** this handle was created for.
+    pub fn get_id(&self) -> &ItemId {
+        &self.id
     }
 
-    /// Returns all stored **`ItemId`** values that share a `name`.
-    ///
-    /// The returned IDs use the occupied stable indexes from that internal name bucket.
-    pub fn get_ids_by_name(&self, name: impl AsRef<str>) -> Vec<ItemId> {
-        self.items
-            .get(name.as_ref())
-            .map(|paths| {
-                paths
-                    .iter()
-                    .map(|(index, _)| ItemId::with_index(name.as_ref(), index))
-                    .collect()
-            })
-            .unwrap_or_default()
+    /// Returns the absolute path resolved when the handle was created.
+    pub fn get_path(&self) -> &Path {
+        &self.path
     }
 
-    /// Returns all stored **`ItemId`** values that share an `index`.
+    /// Returns all tracked items that are direct children of this directory.
     ///
-    /// This scans all name buckets and returns every ID whose stable slot equals `index`.
-    pub fn get_ids_by_index(&self, index: usize) -> Vec<ItemId> {
-        self.items
-            .iter()
-            .filter_map(|(name, paths)| {
-                paths
-                    .get(index)
-                    .map(|_| ItemId::with_index(name.clone(), index))
-            })
-            .collect()
+    /// # Errors
+    /// Returns an error if the directory is no longer tracked.
+    pub fn list(&self, sort: impl Into<SortBy>) -> Result<Vec<ItemId>, DatabaseError> {
+        self.manager.get_by_parent(&self.id, sort)
     }
 
-    /// Scans files on disk and compares them to entries in this scan area.
-    ///
-    /// Missing tracked items are always removed from the `items` index kept in memory.
+    /// Creates a new file or directory inside this directory.
     ///
-    /// Policy behavior for newly discovered external items:
-    /// - `DetectOnly`: detect only.
-    /// - `AddNew`: detect and add to the `index`.
-    /// - `RemoveNew`: delete from disk and do not keep them in the report.
-    ///
-    /// # Parameters
-    /// - `scan_from`: root **`ItemId`** to scan from (`ItemId::database_id()` scans the full database).
-    /// - `policy`: change handling policy.
-    /// - `recursive`: `true` scans full subtree, `false` scans immediate children only.
+    /// See `DatabaseManager::write_new` for naming rules and errors.
+    pub fn create_child(&mut self, id: impl Into<ItemId>) -> Result<CreatedItem, DatabaseError> {
+        self.manager.write_new(id, &self.id)
+    }
+}
+
+/// A scratch item that is deleted when the guard is dropped, returned by
+/// `DatabaseManager::write_temp`.
+///
+/// The guard dereferences to the **`DatabaseManager`**, so the item can be used (and even
+/// renamed or moved) while the guard is alive. Drop runs during a panic too, so a failing step
+/// does not leak the item. Call `keep` to hold on to it instead.
+#[derive(Debug)]
+pub struct TempItem<'a> {
+    manager: &'a mut DatabaseManager,
+    id: ItemId,
+    handle: ItemHandle,
+    kept: bool,
+}
+
+impl TempItem<'_> {
+    /// Returns the **`ItemId`** the item was created with.
+    pub fn get_id(&self) -> &ItemId {
+        &self.id
+    }
+
+    /// Returns the **`ItemHandle`** of the item, which keeps working after renames and moves.
+    pub fn get_handle(&self) -> ItemHandle {
+        self.handle
+    }
+
+    /// Keeps the item in the database and returns its current **`ItemId`**.
     ///
     /// # Errors
-    /// Returns an error if:
-    /// - `scan_from` cannot be found,
-    /// - `scan_from` points to a file,
-    /// - path-to-string conversion fails for discovered entries,
-    /// - filesystem read or delete operations fail.
-    ///
-    /// # Examples
-    /// ```no_run
-    /// use file_database::{DatabaseError, DatabaseManager, ItemId, ScanPolicy};
+    /// Returns an error if the item was already deleted.
+    pub fn keep(mut self) -> Result<ItemId, DatabaseError> {
+        self.kept = true;
+        self.manager.resolve_handle(self.handle)
+    }
+}
+
+impl std::ops::Deref for TempItem<'_> {
+    type Target = DatabaseManager;
+
+    fn deref(&self) -> &DatabaseManager {
+        self.manager
+    }
+}
+
+impl std::ops::DerefMut for TempItem<'_> {
+    fn deref_mut(&mut self) -> &mut DatabaseManager {
+        self.manager
+    }
+}
+
+impl Drop for TempItem<'_> {
+    /// Deletes the item and everything inside it, unless `keep` was called. Errors are ignored.
+    fn drop(&mut self) {
+        if self.kept {
+            return;
+        }
+        if let Ok(id) = self.manager.resolve_handle(self.handle) {
+            let _ = self.manager.delete(id, ForceDeletion::Force);
+        }
+    }
+}
+
+/// One step recorded by a **`Batch`**.
+#[derive(Debug, Clone)]
+enum BatchOperation {
+    CreateDir {
+        id: ItemId,
+        parent: ItemId,
+    },
+    CreateFile {
+        id: ItemId,
+        parent: ItemId,
+        data: Vec<u8>,
+    },
+    Overwrite {
+        id: ItemId,
+        data: Vec<u8>,
+    },
+    Rename {
+        id: ItemId,
+        to: String,
+    },
+    Delete {
+        id: ItemId,
+    },
+}
+
+/// Planned state of one item while a **`Batch`** is validated.
+#[derive(Debug, Clone)]
+struct PlannedItem {
+    path: PathBuf,
+    origin: Option<PathBuf>,
+    is_dir: Option<bool>,
+}
+
+/// Work needed to roll back one applied **`Batch`** step.
+#[derive(Debug)]
+enum BatchUndo {
+    Nothing,
+    Remove {
+        id: ItemId,
+        absolute: PathBuf,
+    },
+    Restore {
+        id: ItemId,
+        data: Vec<u8>,
+    },
+    Rename {
+        id: ItemId,
+        to: String,
+    },
+    Unstage {
+        staged: PathBuf,
+        absolute: PathBuf,
+        forgotten: ForgottenSubtree,
+    },
+}
+
+/// Index state dropped by `forget_subtree`, kept so a **`Batch`** rollback can put it back.
+#[derive(Debug)]
+struct ForgottenSubtree {
+    entries: Vec<(ItemId, PathBuf)>,
+    records: Vec<(PathBuf, ItemRecord)>,
+    access: Vec<(PathBuf, HashMap<String, HashSet<AccessOperation>>)>,
+    reservations: Vec<PathBuf>,
+    subtree_configs: Vec<(PathBuf, SubtreeConfig)>,
+}
+
+/// A builder for several manager operations that are validated together and then applied.
+///
+/// Create it with `DatabaseManager::batch`. Nothing touches the filesystem until `apply` is
+/// called. `apply` first checks the whole plan (missing items and parents, name collisions,
+/// files used as parents) and fails with `DatabaseError::BatchStepFailed` naming the first bad
+/// step. When the batch is transactional, a failure while applying rolls back the steps that
+/// already ran.
+#[derive(Debug)]
+pub struct Batch<'a> {
+    manager: &'a mut DatabaseManager,
+    operations: Vec<BatchOperation>,
+    transactional: bool,
+}
+
+impl Batch<'_> {
+    /// Adds a step that creates directory `id` in the database root.
+    pub fn create_dir(self, id: impl Into<ItemId>) -> Self {
+        self.create_dir_in(ItemId::database_id(), id)
+    }
+
+    /// Adds a step that creates directory `id` inside `parent`.
     ///
-    /// fn main() -> Result<(), DatabaseError> {
-    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
-    ///     let _report = manager.scan_for_changes(ItemId::database_id(), ScanPolicy::AddNew, true)?;
-    ///     Ok(())
-    /// }
-    /// ```
-    pub fn scan_for_changes(
-        &mut self,
-        scan_from: impl Into<ItemId>,
-        policy: ScanPolicy,
-        recursive: bool,
-    ) -> Result<ScanReport, DatabaseError> {
-        let scan_from = scan_from.into();
-        let scan_from_absolute = self.locate_absolute(&scan_from)?;
-        if !scan_from_absolute.is_dir() {
-            return Err(DatabaseError::NotADirectory(scan_from_absolute));
-        }
+    /// Unlike `write_new`, this always creates a directory, even when `id` has an extension.
+    pub fn create_dir_in(mut self, parent: impl Into<ItemId>, id: impl Into<ItemId>) -> Self {
+        self.operations.push(BatchOperation::CreateDir {
+            id: id.into(),
+            parent: parent.into(),
+        });
+        self
+    }
 
-        let scope_relative = if scan_from.get_name().is_empty() {
-            None
-        } else {
-            Some(self.locate_relative(&scan_from)?.clone())
-        };
+    /// Adds a step that creates file `id` in the database root with initial contents.
+    pub fn create_file(self, id: impl Into<ItemId>, data: impl AsRef<[u8]>) -> Self {
+        self.create_file_in(ItemId::database_id(), id, data)
+    }
 
-        let discovered_paths = self.collect_paths_in_scope(&scan_from_absolute, recursive)?;
-        let discovered_set: HashSet<PathBuf> = discovered_paths.iter().cloned().collect();
+    /// Adds a step that creates file `id` inside `parent` with initial contents.
+    ///
+    /// Unlike `write_new`, this always creates a file, even when `id` has no extension.
+    pub fn create_file_in(
+        mut self,
+        parent: impl Into<ItemId>,
+        id: impl Into<ItemId>,
+        data: impl AsRef<[u8]>,
+    ) -> Self {
+        self.operations.push(BatchOperation::CreateFile {
+            id: id.into(),
+            parent: parent.into(),
+            data: data.as_ref().to_vec(),
+        });
+        self
+    }
 
-        let mut existing_in_scope_set = HashSet::new();
-        let mut removed = Vec::new();
-        let mut unchanged_count = 0usize;
-        let mut removed_ids = Vec::new();
+    /// Adds a step that overwrites an existing file.
+    pub fn overwrite(mut self, id: impl Into<ItemId>, data: impl AsRef<[u8]>) -> Self {
+        self.operations.push(BatchOperation::Overwrite {
+            id: id.into(),
+            data: data.as_ref().to_vec(),
+        });
+        self
+    }
 
-        for (name, paths) in &self.items {
-            for (index, path) in paths.iter() {
-                if !self.is_path_in_scope(path, scope_relative.as_deref(), recursive) {
-                    continue;
-                }
+    /// Adds a step that renames an item in place. Later steps refer to it by its new name.
+    pub fn rename(mut self, id: impl Into<ItemId>, to: impl AsRef<str>) -> Self {
+        self.operations.push(BatchOperation::Rename {
+            id: id.into(),
+            to: to.as_ref().to_string(),
+        });
+        self
+    }
 
-                existing_in_scope_set.insert(path.clone());
+    /// Adds a step that deletes an item and everything below it.
+    pub fn delete(mut self, id: impl Into<ItemId>) -> Self {
+        self.operations
+            .push(BatchOperation::Delete { id: id.into() });
+        self
+    }
 
-                let id = ItemId::with_index(name.clone(), index);
-                if discovered_set.contains(path) {
-                    unchanged_count += 1;
-                } else {
-                    removed.push(ExternalChange::Removed {
-                        id: id.clone(),
-                        path: path.clone(),
-                    });
-                    removed_ids.push(id);
+    /// Sets whether a failure while applying rolls back the steps that already ran.
+    ///
+    /// Deleted items are staged next to their original location until the batch finishes,
+    /// so they can be restored.
+    pub fn transactional(mut self, transactional: bool) -> Self {
+        self.transactional = transactional;
+        self
+    }
+
+    /// Validates the whole plan and then applies it in order.
+    ///
+    /// # Errors
+    /// Returns `DatabaseError::BatchStepFailed` with the zero-based step number when:
+    /// - validation finds a missing item or parent, a collision, or a file used as a parent,
+    /// - a filesystem operation fails while applying (after rolling back when transactional).
+    ///
+    /// When a transactional rollback cannot undo every step, returns
+    /// `DatabaseError::BatchRollbackFailed` with the step error and each rollback error instead.
+    pub fn apply(self) -> Result<(), DatabaseError> {
+        let Batch {
+            manager,
+            operations,
+            transactional,
+        } = self;
+
+        validate_batch(manager, &operations)?;
+
+        let recorded = manager.undo.lock().recorded;
+        let mut undo_log = Vec::new();
+        for (step, operation) in operations.into_iter().enumerate() {
+            match apply_batch_operation(manager, operation, transactional) {
+                Ok(undo) => undo_log.push(undo),
+                Err(error) => {
+                    let mut rollback_errors = Vec::new();
+                    if transactional {
+                        let depth = std::mem::replace(&mut manager.undo.depth, 0);
+                        for undo in undo_log.into_iter().rev() {
+                            if let Err(error) = roll_back_batch_operation(manager, undo) {
+                                rollback_errors.push(error);
+                            }
+                        }
+                        manager.undo.depth = depth;
+                        if let Err(error) = manager.forget_undo_since(recorded) {
+                            rollback_errors.push(error);
+                        }
+                    }
+                    if !rollback_errors.is_empty() {
+                        return Err(DatabaseError::BatchRollbackFailed(
+                            step,
+                            Box::new(error),
+                            rollback_errors,
+                        ));
+                    }
+                    return Err(DatabaseError::BatchStepFailed(step, Box::new(error)));
                 }
             }
         }
 
-        for id in removed_ids {
-            let _ = self.remove_id_from_index(&id);
+        // Overwrites, renames and plain deletes refreshed their manifests when they ran.
+        let touched: Vec<PathBuf> = undo_log
+            .iter()
+            .filter_map(|undo| match undo {
+                BatchUndo::Remove { absolute, .. } | BatchUndo::Unstage { absolute, .. } => {
+                    absolute
+                        .strip_prefix(&manager.path)
+                        .ok()
+                        .map(Path::to_path_buf)
+                }
+                _ => None,
+            })
+            .collect();
+        for undo in undo_log {
+            if let BatchUndo::Unstage { staged, .. } = undo {
+                if staged.is_dir() {
+                    remove_dir_all(&staged)?;
+                } else {
+                    remove_file(&staged)?;
+                }
+            }
         }
 
-        let mut added_paths: Vec<PathBuf> = discovered_paths
-            .into_iter()
-            .filter(|path| !existing_in_scope_set.contains(path))
-            .collect();
+        let touched: Vec<&Path> = touched.iter().map(|path| path.as_path()).collect();
+        manager.after_mutation(&touched)?;
 
-        let mut added = Vec::new();
-        match policy {
-            ScanPolicy::DetectOnly => {
-                for path in &added_paths {
-                    let name = path
-                        .file_name()
-                        .and_then(|name| name.to_str())
-                        .ok_or(DatabaseError::OsStringConversion)?
-                        .to_string();
-                    added.push(ExternalChange::Added {
-                        id: ItemId::id(name),
-                        path: path.clone(),
-                    });
+        Ok(())
+    }
+}
+
+/// Checks a batch plan against a planned copy of the index without touching the filesystem.
+fn validate_batch(
+    manager: &DatabaseManager,
+    operations: &[BatchOperation],
+) -> Result<(), DatabaseError> {
+    let mut planned: HashMap<ItemId, PlannedItem> = manager
+        .all_paths()
+        .into_iter()
+        .map(|(id, path)| {
+            (
+                id,
+                PlannedItem {
+                    path: path.clone(),
+                    origin: Some(path.clone()),
+                    is_dir: None,
+                },
+            )
+        })
+        .collect();
+    let tracked: HashSet<PathBuf> = planned.values().map(|item| item.path.clone()).collect();
+
+    let is_dir = |item: &PlannedItem| match (item.is_dir, &item.origin) {
+        (Some(is_dir), _) => is_dir,
+        (None, Some(origin)) => manager.path.join(origin).is_dir(),
+        (None, None) => false,
+    };
+
+    for (step, operation) in operations.iter().enumerate() {
+        let fail = |error: DatabaseError| DatabaseError::BatchStepFailed(step, Box::new(error));
+
+        let occupied = |planned: &HashMap<ItemId, PlannedItem>, path: &Path| {
+            planned.values().any(|item| item.path == path)
+                || (!tracked.contains(path) && manager.path.join(path).exists())
+        };
+
+        match operation {
+            BatchOperation::CreateDir { id, parent }
+            | BatchOperation::CreateFile { id, parent, .. } => {
+                if id.get_name().is_empty() {
+                    return Err(fail(DatabaseError::RootIdUnsupported));
                 }
+                validate_item_name(id.get_name()).map_err(fail)?;
+
+                let parent_path = if parent.get_name().is_empty() {
+                    PathBuf::new()
+                } else {
+                    let parent_item = planned
+                        .get(parent)
+                        .ok_or_else(|| fail(DatabaseError::NoMatchingID(parent.as_string())))?;
+                    if !is_dir(parent_item) {
+                        return Err(fail(DatabaseError::NotADirectory(
+                            manager.path.join(&parent_item.path),
+                        )));
+                    }
+                    parent_item.path.clone()
+                };
+
+                let path = parent_path.join(id.get_name());
+                if planned.contains_key(id) || occupied(&planned, &path) {
+                    return Err(fail(DatabaseError::IdAlreadyExists(id.as_string())));
+                }
+
+                let is_dir = matches!(operation, BatchOperation::CreateDir { .. });
+                planned.insert(
+                    id.clone(),
+                    PlannedItem {
+                        path,
+                        origin: None,
+                        is_dir: Some(is_dir),
+                    },
+                );
             }
-            ScanPolicy::AddNew => {
-                for path in &added_paths {
-                    let name = path
-                        .file_name()
-                        .and_then(|name| name.to_str())
-                        .ok_or(DatabaseError::OsStringConversion)?
-                        .to_string();
-                    let id = self.insert_generated_path(name, path.clone());
-                    added.push(ExternalChange::Added {
-                        id,
-                        path: path.clone(),
-                    });
+            BatchOperation::Overwrite { id, .. } => {
+                let item = planned
+                    .get(id)
+                    .ok_or_else(|| fail(DatabaseError::NoMatchingID(id.as_string())))?;
+                if is_dir(item) {
+                    return Err(fail(DatabaseError::NotAFile(manager.path.join(&item.path))));
                 }
             }
-            ScanPolicy::RemoveNew => {
-                for path in &added_paths {
-                    let name = path
-                        .file_name()
-                        .and_then(|name| name.to_str())
-                        .ok_or(DatabaseError::OsStringConversion)?
-                        .to_string();
-                    added.push(ExternalChange::Added {
-                        id: ItemId::id(name),
-                        path: path.clone(),
-                    });
+            BatchOperation::Rename { id, to } => {
+                if id.get_name().is_empty() {
+                    return Err(fail(DatabaseError::RootIdUnsupported));
+                }
+                validate_item_name(to).map_err(fail)?;
+
+                let item = planned
+                    .get(id)
+                    .ok_or_else(|| fail(DatabaseError::NoMatchingID(id.as_string())))?;
+                let from = item.path.clone();
+                let renamed = from.with_file_name(to);
+                let new_id = ItemId::with_index(to.clone(), id.get_index());
+
+                if occupied(&planned, &renamed) || (new_id != *id && planned.contains_key(&new_id))
+                {
+                    return Err(fail(DatabaseError::IdAlreadyExists(new_id.as_string())));
                 }
 
-                added_paths.sort_by_key(|path| std::cmp::Reverse(path.components().count()));
-                for path in added_paths {
-                    let absolute = self.path.join(&path);
-                    if !absolute.exists() {
-                        continue;
-                    }
-
-                    if absolute.is_dir() {
-                        remove_dir_all(&absolute)?;
-                    } else if absolute.is_file() {
-                        remove_file(&absolute)?;
+                let mut item = planned
+                    .remove(id)
+                    .ok_or_else(|| fail(DatabaseError::NoMatchingID(id.as_string())))?;
+                item.is_dir = Some(is_dir(&item));
+                for other in planned.values_mut() {
+                    if let Some(rebased) = rebase_path(&other.path, &from, &renamed) {
+                        other.path = rebased;
                     }
                 }
+                item.path = renamed;
+                planned.insert(new_id, item);
             }
-        }
-
-        let total_changed_count = added.len() + removed.len();
+            BatchOperation::Delete { id } => {
+                if id.get_name().is_empty() {
+                    return Err(fail(DatabaseError::RootIdUnsupported));
+                }
 
-        match policy {
-            ScanPolicy::RemoveNew => {
-                added.clear();
-            },
-            _ => (),
+                let item = planned
+                    .remove(id)
+                    .ok_or_else(|| fail(DatabaseError::NoMatchingID(id.as_string())))?;
+                planned.retain(|_, other| !other.path.starts_with(&item.path));
+            }
         }
-
-        Ok(ScanReport {
-            scanned_from: scan_from,
-            recursive,
-            added,
-            removed,
-            unchanged_count,
-            total_changed_count,
-        })
     }
 
-    /// Moves the entire database directory to a new parent directory.
-    ///
-    /// Existing destination database directory with the same name is removed first.
-    ///
-    /// # Parameters
-    /// - `to`: destination parent directory.
-    ///
-    /// # Errors
-    /// Returns an error if:
-    /// - current database path is invalid,
-    /// - destination cleanup fails,
-    /// - recursive copy or source removal fails.
-    ///
-    /// # Examples
-    /// ```no_run
-    /// use file_database::{DatabaseError, DatabaseManager};
-    ///
-    /// fn main() -> Result<(), DatabaseError> {
-    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
-    ///     manager.migrate_database("./new_parent")?;
-    ///     Ok(())
-    /// }
-    /// ```
-    pub fn migrate_database(&mut self, to: impl AsRef<Path>) -> Result<(), DatabaseError> {
-        let destination = to.as_ref().to_path_buf();
-        let name = self
-            .path
-            .file_name()
-            .ok_or_else(|| DatabaseError::NotADirectory(self.path.clone()))?;
-        let destination_database_path = destination.join(name);
+    Ok(())
+}
 
-        if destination_database_path.exists() {
-            remove_dir_all(&destination_database_path)?;
+/// Applies one batch step and returns what is needed to roll it back.
+fn apply_batch_operation(
+    manager: &mut DatabaseManager,
+    operation: BatchOperation,
+    transactional: bool,
+) -> Result<BatchUndo, DatabaseError> {
+    match operation {
+        BatchOperation::CreateDir { id, parent } => {
+            let relative = manager.locate_relative(&parent)?.join(id.get_name());
+            let absolute = manager.path.join(&relative);
+            create_dir(&absolute)?;
+            manager.sync_parent_directory(&absolute)?;
+            manager.insert_path_for_id(&id, relative.clone())?;
+            manager.emit_created(&id, &relative);
+            manager.record_created(&relative)?;
+            Ok(BatchUndo::Remove { id, absolute })
         }
+        BatchOperation::CreateFile { id, parent, data } => {
+            let relative = manager.locate_relative(&parent)?.join(id.get_name());
+            let absolute = manager.path.join(&relative);
+            let check = manager.check_quotas(&relative, data.len() as u64, 0)?;
+            let mut file = File::create_new(&absolute)?;
+            file.write_all(&data)?;
+            manager.sync_file(&file)?;
+            manager.record_quota_usage(check);
+            manager.sync_parent_directory(&absolute)?;
+            manager.insert_path_for_id(&id, relative.clone())?;
+            manager.emit_created(&id, &relative);
+            manager.record_created(&relative)?;
+            Ok(BatchUndo::Remove { id, absolute })
+        }
+        BatchOperation::Overwrite { id, data } => {
+            if !transactional {
+                manager.overwrite_existing(&id, data)?;
+                return Ok(BatchUndo::Nothing);
+            }
 
-        self.copy_directory_recursive(&self.path, &destination_database_path)?;
-        remove_dir_all(&self.path)?;
-
-        self.path = destination_database_path;
+            let previous = manager.read_existing(&id)?;
+            manager.overwrite_existing(&id, data)?;
+            Ok(BatchUndo::Restore { id, data: previous })
+        }
+        BatchOperation::Rename { id, to } => {
+            let previous = id.as_string();
+            let renamed = manager.rename(&id, &to)?;
+            Ok(BatchUndo::Rename {
+                id: renamed,
+                to: previous,
+            })
+        }
+        BatchOperation::Delete { id } => {
+            if !transactional {
+                manager.delete(&id, ForceDeletion::Force)?;
+                return Ok(BatchUndo::Nothing);
+            }
 
-        Ok(())
+            let relative = manager.locate_relative(&id)?.into_path_buf();
+            let absolute = manager.path.join(&relative);
+            let mut staged_name = absolute.file_name().unwrap_or_default().to_os_string();
+            staged_name.push(".batch-deleted");
+            let staged = absolute.with_file_name(staged_name);
+
+            let forgotten = manager.capture_subtree(&relative);
+            fs::rename(&absolute, &staged)?;
+            manager.forget_subtree(&id, &relative)?;
+            manager.sync_parent_directory(&absolute)?;
+            manager.emit_deleted(&id, &relative);
+            Ok(BatchUndo::Unstage {
+                staged,
+                absolute,
+                forgotten,
+            })
+        }
     }
+}
 
-    /// Moves a managed item to another directory inside the same database.
-    ///
-    /// # Parameters
-    /// - `id`: source item to move.
-    /// - `to`: destination directory item (or `ItemId::database_id()`).
-    ///
-    /// # Errors
-    /// Returns an error if:
-    /// - `id` is root or cannot be found,
-    /// - destination is not a directory,
-    /// - source and destination are identical,
-    /// - filesystem move fails.
-    ///
-    /// # Examples
-    /// ```no_run
-    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
-    ///
-    /// fn main() -> Result<(), DatabaseError> {
-    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
-    ///     manager.write_new(ItemId::id("folder"), ItemId::database_id())?;
-    ///     manager.write_new(ItemId::id("a.txt"), ItemId::database_id())?;
-    ///     manager.migrate_item(ItemId::id("a.txt"), ItemId::id("folder"))?;
-    ///     Ok(())
-    /// }
-    /// ```
-    pub fn migrate_item(
-        &mut self,
-        id: impl Into<ItemId>,
-        to: impl Into<ItemId>,
-    ) -> Result<(), DatabaseError> {
-        let id = id.into();
-        let to = to.into();
-
-        if id.get_name().is_empty() {
-            return Err(DatabaseError::RootIdUnsupported);
+/// Rolls back one applied batch step.
+fn roll_back_batch_operation(
+    manager: &mut DatabaseManager,
+    undo: BatchUndo,
+) -> Result<(), DatabaseError> {
+    match undo {
+        BatchUndo::Nothing => Ok(()),
+        BatchUndo::Remove { id, absolute } => {
+            if absolute.is_dir() {
+                remove_dir_all(&absolute)?;
+            } else if absolute.is_file() {
+                remove_file(&absolute)?;
+            }
+            manager.remove_id_from_index(&id)?;
+            if let Ok(relative) = absolute.strip_prefix(&manager.path) {
+                manager.emit_deleted(&id, relative);
+            }
+            Ok(())
         }
-
-        let destination_dir = self.locate_absolute(&to)?;
-        if !destination_dir.is_dir() {
-            return Err(DatabaseError::NotADirectory(destination_dir));
+        BatchUndo::Restore { id, data } => manager.overwrite_existing(id, data),
+        BatchUndo::Rename { id, to } => manager.rename(id, to).map(|_| ()),
+        BatchUndo::Unstage {
+            staged,
+            absolute,
+            forgotten,
+        } => {
+            fs::rename(&staged, &absolute)?;
+            if let Some((id, relative)) = forgotten.entries.first().cloned() {
+                manager.restore_subtree(forgotten)?;
+                manager.emit_created(&id, &relative);
+            }
+            Ok(())
         }
+    }
+}
+
+/// Declares a typed accessor for one well-known file in the database.
+///
+/// `typed_file!(vis Name, ValueType, "relative/path.ext", format)` generates a unit struct
+/// `Name` with:
+/// - `Name::PATH`: the database-relative path,
+/// - `Name::id(&manager)`: the tracked **`ItemId`** at that path,
+/// - `Name::load(&manager)`: reads and deserializes the value,
+/// - `Name::save(&manager, &value)`: serializes and overwrites the file,
+/// - `Name::update(&manager, |value| ...)`: loads, changes, saves, and returns the new value.
+///
+/// `format` is one of `json`, `json_pretty`, or `binary`. The file must already be tracked.
+///
+/// # Examples
+/// ```no_run
+/// use file_database::{typed_file, DatabaseError, DatabaseManager, ItemId};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize, Default)]
+/// struct Settings {
+///     volume: u8,
+/// }
+///
+/// typed_file!(pub SettingsFile, Settings, "config/settings.json", json_pretty);
+///
+/// fn main() -> Result<(), DatabaseError> {
+///     let mut manager = DatabaseManager::create_database(".", "database")?;
+///     manager.write_new_or_existing(ItemId::id("config"), ItemId::database_id())?;
+///     manager.write_new_or_existing(ItemId::id("settings.json"), ItemId::id("config"))?;
+///
+///     SettingsFile::save(&manager, &Settings::default())?;
+///     let updated = SettingsFile::update(&manager, |settings| settings.volume = 7)?;
+///     assert_eq!(updated.volume, 7);
+///     Ok(())
+/// }
+/// ```
+#[macro_export]
+macro_rules! typed_file {
+    ($vis:vis $name:ident, $value:ty, $path:expr, json) => {
+        $crate::typed_file!(@impl $vis $name, $value, $path, $crate::FileFormat::Json);
+    };
+    ($vis:vis $name:ident, $value:ty, $path:expr, json_pretty) => {
+        $crate::typed_file!(@impl $vis $name, $value, $path, $crate::FileFormat::JsonPretty);
+    };
+    ($vis:vis $name:ident, $value:ty, $path:expr, binary) => {
+        $crate::typed_file!(@impl $vis $name, $value, $path, $crate::FileFormat::Binary);
+    };
+    (@impl $vis:vis $name:ident, $value:ty, $path:expr, $format:expr) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+        $vis struct $name;
+
+        impl $name {
+            /// Database-relative path of this file.
+            pub const PATH: &'static str = $path;
+
+            /// Returns the tracked **`ItemId`** for this file.
+            pub fn id(
+                manager: &$crate::DatabaseManager,
+            ) -> ::std::result::Result<$crate::ItemId, $crate::DatabaseError> {
+                manager.get_id_by_path(Self::PATH)
+            }
 
-        let source_absolute = self.locate_absolute(&id)?;
-        let source_name = source_absolute
-            .file_name()
-            .ok_or_else(|| DatabaseError::NoMatchingID(id.as_string()))?;
-        let destination_absolute = destination_dir.join(source_name);
+            /// Reads and deserializes this file.
+            pub fn load(
+                manager: &$crate::DatabaseManager,
+            ) -> ::std::result::Result<$value, $crate::DatabaseError> {
+                manager.read_existing_as(Self::id(manager)?, $format)
+            }
 
-        if destination_absolute == source_absolute {
-            return Err(DatabaseError::IdenticalSourceDestination(
-                destination_absolute,
-            ));
-        }
+            /// Serializes `value` and overwrites this file.
+            pub fn save(
+                manager: &$crate::DatabaseManager,
+                value: &$value,
+            ) -> ::std::result::Result<(), $crate::DatabaseError> {
+                manager.overwrite_existing_as(Self::id(manager)?, value, $format)
+            }
 
-        if destination_absolute.exists() {
-            if destination_absolute.is_dir() {
-                remove_dir_all(&destination_absolute)?;
-            } else {
-                remove_file(&destination_absolute)?;
+            /// Loads this file, applies `change`, saves it, and returns the new value.
+            pub fn update(
+                manager: &$crate::DatabaseManager,
+                change: impl FnOnce(&mut $value),
+            ) -> ::std::result::Result<$value, $crate::DatabaseError> {
+                let mut value = Self::load(manager)?;
+                change(&mut value);
+                Self::save(manager, &value)?;
+                Ok(value)
             }
         }
+    };
+}
 
-        fs::rename(&source_absolute, &destination_absolute)?;
+/// An immutable view of a **`DatabaseManager`** index taken at one point in time.
+///
+/// Create it with `DatabaseManager::freeze` or `DatabaseManager::freeze_with_contents`.
+/// Listings and lookups use the copied index, so they never see half-finished changes made to
+/// the live manager afterwards.
+#[derive(Debug)]
+pub struct Snapshot {
+    root: PathBuf,
+    items: BTreeMap<String, StableVec<PathBuf>>,
+    contents: Option<PathBuf>,
+    normalize_extensions: bool,
+}
 
-        let relative_destination = destination_absolute.strip_prefix(&self.path)?.to_path_buf();
-        let source_name = relative_destination
-            .file_name()
-            .and_then(|name| name.to_str())
-            .ok_or(DatabaseError::OsStringConversion)?
-            .to_string();
-        let migrated_id = ItemId::with_index(source_name, id.get_index());
+impl Snapshot {
+    /// Returns `true` when file contents were captured with `freeze_with_contents`.
+    pub fn has_contents(&self) -> bool {
+        self.contents.is_some()
+    }
 
-        self.remove_id_from_index(&id)?;
-        self.insert_path_for_id(&migrated_id, relative_destination)?;
+    /// Returns every item tracked when the snapshot was taken.
+    pub fn get_all(&self, sort: impl Into<SortBy>) -> Vec<ItemId> {
+        let items = self
+            .items
+            .iter()
+            .flat_map(|(name, paths)| {
+                paths
+                    .iter()
+                    .map(|(index, path)| (ItemId::with_index(name.clone(), index), path))
+            })
+            .collect();
 
-        Ok(())
+        sort_listing(items, sort.into(), &self.root)
     }
 
-    /// Exports a managed file or directory to an external destination directory.
-    ///
-    /// `Copy` keeps the item in the `index`. `Move` removes the moved entry from the `index`.
-    ///
-    /// # Parameters
-    /// - `id`: source item to export.
-    /// - `to`: external destination directory path.
-    /// - `mode`: copy or move behavior.
+    /// Returns the items that were direct children of `parent` when the snapshot was taken.
     ///
     /// # Errors
     /// Returns an error if:
-    /// - `id` is root or cannot be found,
-    /// - destination is inside the database,
-    /// - destination path cannot be created or used as a directory,
-    /// - filesystem copy/move operations fail.
-    ///
-    /// # Examples
-    /// ```no_run
-    /// use file_database::{DatabaseError, DatabaseManager, ExportMode, ItemId};
+    /// - `parent` was not tracked.
+    pub fn get_by_parent(
+        &self,
+        parent: impl Into<ItemId>,
+        sort: impl Into<SortBy>,
+    ) -> Result<Vec<ItemId>, DatabaseError> {
+        let parent_path = self.locate_relative(parent)?;
+
+        let items = self
+            .items
+            .iter()
+            .flat_map(|(name, paths)| {
+                paths
+                    .iter()
+                    .filter(|(_, path)| path.parent() == Some(parent_path.as_path()))
+                    .map(|(index, path)| (ItemId::with_index(name.clone(), index), path))
+            })
+            .collect();
+
+        Ok(sort_listing(items, sort.into(), &self.root))
+    }
+
+    /// Returns all IDs that shared `name` when the snapshot was taken.
+    pub fn get_ids_by_name(&self, name: impl AsRef<str>) -> Vec<ItemId> {
+        let name = index_key(name.as_ref(), self.normalize_extensions);
+        self.items
+            .get(name.as_ref())
+            .map(|paths| {
+                paths
+                    .iter()
+                    .map(|(index, _)| ItemId::with_index(name.as_ref(), index))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Gets the database-relative path an item had when the snapshot was taken.
     ///
-    /// fn main() -> Result<(), DatabaseError> {
-    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
-    ///     manager.write_new(ItemId::id("a.txt"), ItemId::database_id())?;
-    ///     manager.export_item(ItemId::id("a.txt"), "./exports", ExportMode::Copy)?;
-    ///     Ok(())
-    /// }
-    /// ```
-    pub fn export_item(
-        &mut self,
-        id: impl Into<ItemId>,
-        to: impl AsRef<Path>,
-        mode: ExportMode,
-    ) -> Result<(), DatabaseError> {
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` was not tracked.
+    pub fn locate_relative(&self, id: impl Into<ItemId>) -> Result<RelativePath, DatabaseError> {
         let id = id.into();
-        let destination_dir = {
-            let to = to.as_ref();
-            if to.is_absolute() {
-                to.to_path_buf()
-            } else {
-                current_dir()?.join(to)
-            }
-        };
-
         if id.get_name().is_empty() {
-            return Err(DatabaseError::RootIdUnsupported);
+            return Ok(RelativePath::default());
         }
 
-        if destination_dir.starts_with(&self.path) {
-            return Err(DatabaseError::ExportDestinationInsideDatabase(
-                destination_dir,
-            ));
-        }
+        self.items
+            .get(index_key(id.get_name(), self.normalize_extensions).as_ref())
+            .and_then(|paths| paths.get(id.get_index()))
+            .map(|path| RelativePath(path.clone()))
+            .ok_or_else(|| DatabaseError::NoMatchingID(id.as_string()))
+    }
 
-        fs::create_dir_all(&destination_dir)?;
-        if !destination_dir.is_dir() {
-            return Err(DatabaseError::NotADirectory(destination_dir));
-        }
+    /// Gets the absolute path used to read an item from this snapshot.
+    ///
+    /// This points into the captured contents when available, otherwise into the live database.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` was not tracked.
+    pub fn locate_absolute(&self, id: impl Into<ItemId>) -> Result<PathBuf, DatabaseError> {
+        let relative = self.locate_relative(id)?;
+        let root = self.contents.as_ref().unwrap_or(&self.root);
+        Ok(root.join(relative))
+    }
 
-        let source_absolute = self.locate_absolute(&id)?;
-        let source_name = source_absolute
-            .file_name()
-            .ok_or_else(|| DatabaseError::NoMatchingID(id.as_string()))?;
-        let destination_absolute = destination_dir.join(source_name);
+    /// Reads a file as it is stored for this snapshot.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - `id` was not tracked,
+    /// - `id` points to a directory,
+    /// - file reading fails.
+    pub fn read_existing(&self, id: impl Into<ItemId>) -> Result<Vec<u8>, DatabaseError> {
+        let path = self.locate_absolute(id)?;
 
-        if destination_absolute == source_absolute {
-            return Err(DatabaseError::IdenticalSourceDestination(
-                destination_absolute,
-            ));
+        if path.is_dir() {
+            return Err(DatabaseError::NotAFile(path));
         }
 
-        if destination_absolute.exists() {
-            if destination_absolute.is_dir() {
-                remove_dir_all(&destination_absolute)?;
-            } else {
-                remove_file(&destination_absolute)?;
-            }
-        }
+        Ok(fs::read(path)?)
+    }
+}
 
-        match mode {
-            ExportMode::Copy => {
-                if source_absolute.is_dir() {
-                    self.copy_directory_recursive(&source_absolute, &destination_absolute)?;
-                } else {
-                    fs::copy(&source_absolute, &destination_absolute)?;
-                }
-            }
-            ExportMode::Move => {
-                match fs::rename(&source_absolute, &destination_absolute) {
-                    Ok(_) => (),
-                    Err(_) => {
-                        if source_absolute.is_dir() {
-                            self.copy_directory_recursive(&source_absolute, &destination_absolute)?;
-                            remove_dir_all(&source_absolute)?;
-                        } else {
-                            fs::copy(&source_absolute, &destination_absolute)?;
-                            remove_file(&source_absolute)?;
-                        }
-                    }
-                }
-                self.remove_id_from_index(&id)?;
-            }
+impl Drop for Snapshot {
+    /// Removes captured contents, if any.
+    fn drop(&mut self) {
+        if let Some(contents) = &self.contents {
+            let _ = remove_dir_all(contents);
         }
+    }
+}
 
-        Ok(())
+// -------- Functions --------
+/// Removes `steps` trailing segments from `path`.
+///
+/// # Errors
+/// Returns [`DatabaseError::PathStepOverflow`] when `steps` is too large for `path`.
+fn truncate(mut path: PathBuf, steps: i32) -> Result<PathBuf, DatabaseError> {
+    let parents = (path.ancestors().count() - 1) as i32;
+
+    if parents <= steps {
+        return Err(DatabaseError::PathStepOverflow(steps, parents));
     }
 
-    /// Imports an external file or directory into a database destination directory.
-    ///
-    /// The imported item keeps its original `name`.
-    ///
-    /// # Parameters
-    /// - `from`: source path outside the database.
-    /// - `to`: destination directory item in the database.
-    ///
-    /// # Errors
-    /// Returns an error if:
-    /// - source path points inside the database,
-    /// - destination is not a directory,
-    /// - destination `path`/`name` already exists,
-    /// - source does not exist as file or directory,
-    /// - filesystem copy operations fail.
-    ///
-    /// # Examples
-    /// ```no_run
-    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
-    ///
-    /// fn main() -> Result<(), DatabaseError> {
-    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
-    ///     manager.write_new(ItemId::id("imports"), ItemId::database_id())?;
-    ///     manager.import_item("./outside/example.txt", ItemId::id("imports"))?;
-    ///     Ok(())
-    /// }
-    /// ```
-    pub fn import_item(
-        &mut self,
-        from: impl AsRef<Path>,
-        to: impl Into<ItemId>,
-    ) -> Result<(), DatabaseError> {
-        let source_path = {
-            let from = from.as_ref();
-            if from.is_absolute() {
-                from.to_path_buf()
-            } else {
-                current_dir()?.join(from)
-            }
-        };
-        let to = to.into();
+    for _ in 0..steps {
+        path.pop();
+    }
+
+    Ok(path)
+}
+
+/// Converts an optional `OsStr` into an owned `String`.
+///
+/// # Errors
+/// Returns [`DatabaseError::OsStringConversion`] if the value is `None` or invalid UTF-8.
+fn os_str_to_string(os_str: Option<&OsStr>) -> Result<String, DatabaseError> {
+    let os_str = match os_str {
+        Some(os_str) => os_str,
+        None => return Err(DatabaseError::OsStringConversion),
+    };
+
+    match os_str.to_os_string().into_string() {
+        Ok(string) => Ok(string),
+        Err(_) => Err(DatabaseError::OsStringConversion),
+    }
+}
+
+/// Converts `SystemTime` to Unix timestamp seconds.
+///
+/// Returns `None` for platform or conversion failures.
+fn sys_time_to_unsigned_int(time: io::Result<SystemTime>) -> Option<u64> {
+    match time {
+        Ok(time) => match time.duration_since(UNIX_EPOCH) {
+            Ok(duration) => Some(duration.as_secs()),
+            Err(_) => None,
+        },
+        Err(_) => None,
+    }
+}
+
+/// Converts `SystemTime` to "time since now" represented as Unix-seconds duration.
+///
+/// Returns `None` for platform or conversion failures.
+fn sys_time_to_time_since(time: io::Result<SystemTime>) -> Option<u64> {
+    let duration = match time {
+        Ok(time) => match SystemTime::now().duration_since(time) {
+            Ok(duration) => duration,
+            Err(_) => return None,
+        },
+        Err(_) => return None,
+    };
 
-        if source_path.starts_with(&self.path) {
-            return Err(DatabaseError::ImportSourceInsideDatabase(source_path));
-        }
+    sys_time_to_unsigned_int(Ok(UNIX_EPOCH + duration))
+}
 
-        let destination_parent = self.locate_absolute(&to)?;
-        if !destination_parent.is_dir() {
-            return Err(DatabaseError::NotADirectory(destination_parent));
-        }
+/// Checks `name` against the `ItemId::try_new` rules, so it names exactly one item inside its
+/// parent directory.
+///
+/// # Errors
+/// Returns `DatabaseError::InvalidItemName` when `name` breaks a rule.
+fn validate_item_name(name: &str) -> Result<(), DatabaseError> {
+    let problem = if name.is_empty() {
+        "name is empty"
+    } else if name.len() > MAX_NAME_LENGTH {
+        "name is longer than 255 bytes"
+    } else if name == "." || name == ".." {
+        "name is a relative path marker"
+    } else if name.contains(['/', '\\']) || Path::new(name).is_absolute() {
+        "name contains a path separator"
+    } else if name.contains('\0') {
+        "name contains a NUL character"
+    } else if is_internal_file_name(OsStr::new(name)) {
+        "name is reserved for database files"
+    } else {
+        return Ok(());
+    };
 
-        let item_name = source_path
-            .file_name()
-            .ok_or_else(|| DatabaseError::NotAFile(source_path.clone()))?
-            .to_string_lossy()
-            .to_string();
+    Err(DatabaseError::InvalidItemName(name.to_string(), problem))
+}
 
-        let destination_absolute = destination_parent.join(&item_name);
-        let destination_relative = if to.get_name().is_empty() {
-            PathBuf::from(&item_name)
-        } else {
-            let mut relative = self.locate_relative(&to)?.to_path_buf();
-            relative.push(&item_name);
-            relative
-        };
+/// Returns `path` moved from below `from` to the same place below `to`.
+///
+/// Returns `None` when `path` is not `from` or one of its descendants.
+fn rebase_path(path: &Path, from: &Path, to: &Path) -> Option<PathBuf> {
+    let suffix = path.strip_prefix(from).ok()?;
+
+    if suffix.as_os_str().is_empty() {
+        Some(to.to_path_buf())
+    } else {
+        Some(to.join(suffix))
+    }
+}
 
-        if destination_absolute.exists()
-            || self.path_exists_in_index(&destination_relative)
-        {
-            return Err(DatabaseError::IdAlreadyExists(item_name));
+/// Runs a three-way merge over the lines of `base`, `ours`, and `theirs`.
+///
+/// Returns the merged text and the number of conflicting regions. Conflicts are written
+/// with `<<<<<<<`, `=======`, and `>>>>>>>` markers labelled with `ours_label` and `theirs_label`.
+fn merge_lines(
+    base: &str,
+    ours: &str,
+    theirs: &str,
+    ours_label: &str,
+    theirs_label: &str,
+) -> (String, usize) {
+    let base: Vec<&str> = base.split_inclusive('\n').collect();
+    let ours: Vec<&str> = ours.split_inclusive('\n').collect();
+    let theirs: Vec<&str> = theirs.split_inclusive('\n').collect();
+
+    let ours_matches = match_lines(&base, &ours);
+    let theirs_matches = match_lines(&base, &theirs);
+
+    let mut merged = String::new();
+    let mut conflict_count = 0usize;
+    let (mut base_at, mut ours_at, mut theirs_at) = (0usize, 0usize, 0usize);
+
+    loop {
+        // Next base line kept by both sides, or the end of all three inputs
+        let stable = (base_at..base.len()).find_map(|index| {
+            match (ours_matches[index], theirs_matches[index]) {
+                (Some(ours_index), Some(theirs_index)) => Some((index, ours_index, theirs_index)),
+                _ => None,
+            }
+        });
+        let (base_end, ours_end, theirs_end) =
+            stable.unwrap_or((base.len(), ours.len(), theirs.len()));
+
+        if base_end == base_at && ours_end == ours_at && theirs_end == theirs_at {
+            match stable {
+                Some(_) => {
+                    merged.push_str(base[base_at]);
+                    base_at += 1;
+                    ours_at += 1;
+                    theirs_at += 1;
+                    continue;
+                }
+                None => break,
+            }
         }
 
-        if source_path.is_dir() {
-            self.copy_directory_recursive(&source_path, &destination_absolute)?;
-        } else if source_path.is_file() {
-            fs::copy(&source_path, &destination_absolute)?;
+        let base_chunk = &base[base_at..base_end];
+        let ours_chunk = &ours[ours_at..ours_end];
+        let theirs_chunk = &theirs[theirs_at..theirs_end];
+
+        if ours_chunk == base_chunk {
+            merged.extend(theirs_chunk.iter().copied());
+        } else if theirs_chunk == base_chunk || ours_chunk == theirs_chunk {
+            merged.extend(ours_chunk.iter().copied());
         } else {
-            return Err(DatabaseError::NoMatchingID(
-                source_path.display().to_string(),
-            ));
+            conflict_count += 1;
+            merged.push_str(&format!("<<<<<<< {ours_label}\n"));
+            push_conflict_side(&mut merged, ours_chunk);
+            merged.push_str("=======\n");
+            push_conflict_side(&mut merged, theirs_chunk);
+            merged.push_str(&format!(">>>>>>> {theirs_label}\n"));
         }
 
-        let _id = self.insert_generated_path(item_name, destination_relative);
+        base_at = base_end;
+        ours_at = ours_end;
+        theirs_at = theirs_end;
+    }
 
-        Ok(())
+    (merged, conflict_count)
+}
+
+/// Appends one side of a conflict, making sure the following marker starts on a new line.
+fn push_conflict_side(merged: &mut String, lines: &[&str]) {
+    merged.extend(lines.iter().copied());
+    if lines.last().is_some_and(|line| !line.ends_with('\n')) {
+        merged.push('\n');
     }
+}
 
-    /// Duplicates a managed item into `parent` using a caller-provided `name`.
-    ///
-    /// # Parameters
-    /// - `id`: source item to duplicate.
-    /// - `parent`: destination parent directory item (or `ItemId::database_id()`).
-    /// - `name`: new name for the duplicate.
-    ///
-    /// # Errors
-    /// Returns an error if:
-    /// - `id` is root or cannot be found,
-    /// - destination parent is not a directory,
-    /// - destination `name` already exists in the target directory,
-    /// - filesystem copy fails.
-    ///
-    /// # Examples
-    /// ```no_run
-    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
-    ///
-    /// fn main() -> Result<(), DatabaseError> {
-    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
-    ///     manager.write_new(ItemId::id("a.txt"), ItemId::database_id())?;
-    ///     manager.duplicate_item(ItemId::id("a.txt"), ItemId::database_id(), "copy.txt")?;
-    ///     Ok(())
-    /// }
-    /// ```
-    pub fn duplicate_item(
-        &mut self,
-        id: impl Into<ItemId>,
-        parent: impl Into<ItemId>,
-        name: impl AsRef<str>,
-    ) -> Result<(), DatabaseError> {
-        let id = id.into();
-        let parent = parent.into();
-        let name = name.as_ref().to_owned();
+/// Matches each line of `base` to its position in `other` using a longest common subsequence.
+///
+/// Uses Hirschberg's divide-and-conquer algorithm, so memory stays linear in the input length.
+/// Unmatched base lines map to `None`. Matched positions are strictly increasing.
+fn match_lines(base: &[&str], other: &[&str]) -> Vec<Option<usize>> {
+    let mut matches = vec![None; base.len()];
+    match_line_range(base, other, 0, 0, &mut matches);
+    matches
+}
 
-        if id.get_name().is_empty() {
-            return Err(DatabaseError::RootIdUnsupported);
-        }
+/// Records a longest common subsequence of `base` and `other` in `matches`. The slices start at
+/// `base_offset` and `other_offset` in the full inputs.
+fn match_line_range(
+    base: &[&str],
+    other: &[&str],
+    base_offset: usize,
+    other_offset: usize,
+    matches: &mut [Option<usize>],
+) {
+    // A shared prefix or suffix is always part of some longest common subsequence.
+    let prefix = base
+        .iter()
+        .zip(other)
+        .take_while(|(base_line, other_line)| base_line == other_line)
+        .count();
+    for index in 0..prefix {
+        matches[base_offset + index] = Some(other_offset + index);
+    }
+    let (base, other) = (&base[prefix..], &other[prefix..]);
+    let (base_offset, other_offset) = (base_offset + prefix, other_offset + prefix);
+
+    let suffix = base
+        .iter()
+        .rev()
+        .zip(other.iter().rev())
+        .take_while(|(base_line, other_line)| base_line == other_line)
+        .count();
+    for index in 0..suffix {
+        matches[base_offset + base.len() - suffix + index] =
+            Some(other_offset + other.len() - suffix + index);
+    }
+    let (base, other) = (&base[..base.len() - suffix], &other[..other.len() - suffix]);
 
-        let source_absolute = self.locate_absolute(&id)?;
-        let parent_absolute = self.locate_absolute(&parent)?;
-        if !parent_absolute.is_dir() {
-            return Err(DatabaseError::NotADirectory(parent_absolute));
+    if base.is_empty() || other.is_empty() {
+        return;
+    }
+    if base.len() == 1 {
+        if let Some(position) = other.iter().position(|line| *line == base[0]) {
+            matches[base_offset] = Some(other_offset + position);
         }
+        return;
+    }
 
-        let destination_absolute = parent_absolute.join(&name);
-        let destination_relative = if parent.get_name().is_empty() {
-            PathBuf::from(&name)
-        } else {
-            let mut path = self.locate_relative(&parent)?.to_path_buf();
-            path.push(&name);
-            path
-        };
+    // Split `other` where the LCS of the top half of `base` and the LCS of the bottom half
+    // add up to the most, then solve both halves independently.
+    let middle = base.len() / 2;
+    let forward = lcs_lengths(&base[..middle], other, false);
+    let backward = lcs_lengths(&base[middle..], other, true);
+    let split = (0..=other.len())
+        .max_by_key(|&split| forward[split] + backward[other.len() - split])
+        .unwrap_or(0);
+
+    match_line_range(
+        &base[..middle],
+        &other[..split],
+        base_offset,
+        other_offset,
+        matches,
+    );
+    match_line_range(
+        &base[middle..],
+        &other[split..],
+        base_offset + middle,
+        other_offset + split,
+        matches,
+    );
+}
 
-        if destination_absolute.exists()
-            || self.path_exists_in_index(&destination_relative)
-        {
-            return Err(DatabaseError::IdAlreadyExists(name));
-        }
+/// Returns, for every prefix length `n` of `other`, the length of the longest common
+/// subsequence of `base` and the first `n` lines of `other`. With `reversed`, both inputs are
+/// read from the end, so entry `n` covers the last `n` lines of `other`.
+fn lcs_lengths(base: &[&str], other: &[&str], reversed: bool) -> Vec<usize> {
+    let position = |length: usize, index: usize| match reversed {
+        true => length - 1 - index,
+        false => index,
+    };
 
-        if source_absolute.is_dir() {
-            self.copy_directory_recursive(&source_absolute, &destination_absolute)?;
-        } else {
-            fs::copy(&source_absolute, &destination_absolute)?;
+    let mut lengths = vec![0usize; other.len() + 1];
+    for base_index in 0..base.len() {
+        let base_line = base[position(base.len(), base_index)];
+        let mut diagonal = 0;
+        for other_index in 0..other.len() {
+            let above = lengths[other_index + 1];
+            lengths[other_index + 1] = if base_line == other[position(other.len(), other_index)] {
+                diagonal + 1
+            } else {
+                above.max(lengths[other_index])
+            };
+            diagonal = above;
         }
+    }
 
-        let duplicate_name = destination_relative
-            .file_name()
-            .map(|name| name.to_string_lossy().to_string())
-            .unwrap_or_default();
-        let _duplicate_id = self.insert_generated_path(duplicate_name, destination_relative);
+    lengths
+}
 
-        Ok(())
+/// Returns the total size in bytes of all files below `path`.
+///
+/// # Errors
+/// Returns an error if reading directories or file metadata fails.
+fn directory_size(path: &Path) -> Result<u64, DatabaseError> {
+    Ok(tree_totals(path)?.0)
+}
+
+/// Returns the total file size in bytes and the number of items (including `path` itself)
+/// of the tree at `path`.
+fn tree_totals(path: &Path) -> Result<(u64, usize), DatabaseError> {
+    let metadata = fs::metadata(path)?;
+    if !metadata.is_dir() {
+        return Ok((metadata.len(), 1));
     }
 
-    /// Returns filesystem metadata summary for a managed file or directory.
-    ///
-    /// Includes:
-    /// - `name`/`extension`,
-    /// - normalized size,
-    /// - Unix timestamps and "time since" timestamps where available.
-    ///
-    /// # Parameters
-    /// - `id`: item to inspect.
-    ///
-    /// # Errors
-    /// Returns an error if:
-    /// - `id` cannot be found,
-    /// - metadata lookup fails.
-    ///
-    /// # Examples
-    /// ```no_run
-    /// use file_database::{DatabaseError, DatabaseManager, ItemId};
-    ///
-    /// fn main() -> Result<(), DatabaseError> {
-    ///     let mut manager = DatabaseManager::create_database(".", "database")?;
-    ///     manager.write_new(ItemId::id("a.txt"), ItemId::database_id())?;
-    ///     let _info = manager.get_file_information(ItemId::id("a.txt"))?;
-    ///     Ok(())
-    /// }
-    /// ```
-    pub fn get_file_information(
-        &self,
-        id: impl Into<ItemId>,
-    ) -> Result<FileInformation, DatabaseError> {
-        let id = id.into();
+    let mut total = 0u64;
+    let mut count = 1usize;
+    let mut stack = vec![path.to_path_buf()];
 
-        let path = self.locate_absolute(id)?;
+    while let Some(directory) = stack.pop() {
+        for entry in fs::read_dir(&directory)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            count += 1;
+            if metadata.is_dir() {
+                stack.push(entry.path());
+            } else {
+                total += metadata.len();
+            }
+        }
+    }
 
-        let metadata = fs::metadata(&path)?;
+    Ok((total, count))
+}
 
-        let name = {
-            let os = if path.is_dir() {
-                path.file_name()
-            } else {
-                path.file_stem()
-            };
+/// Orders `(id, relative path)` pairs according to `sort` and returns the IDs.
+///
+/// Metadata orderings read each item below `root` once.
+fn sort_listing(mut entries: Vec<(ItemId, &PathBuf)>, sort: SortBy, root: &Path) -> Vec<ItemId> {
+    match sort {
+        SortBy::Unsorted => {}
+        SortBy::Id => entries.sort_by(|(a, _), (b, _)| a.cmp(b)),
+        SortBy::Path => entries.sort_by(|(a_id, a_path), (b_id, b_path)| {
+            a_path.cmp(b_path).then_with(|| a_id.cmp(b_id))
+        }),
+        SortBy::Name(order) => sort_listing_by_key(&mut entries, order, |path| {
+            path.file_name().map(OsStr::to_os_string)
+        }),
+        SortBy::Extension(order) => sort_listing_by_key(&mut entries, order, |path| {
+            Some(path.extension().unwrap_or_default().to_os_string())
+        }),
+        SortBy::Size(order) => sort_listing_by_key(&mut entries, order, |path| {
+            fs::metadata(root.join(path))
+                .ok()
+                .map(|metadata| if metadata.is_dir() { 0 } else { metadata.len() })
+        }),
+        SortBy::Created(order) => sort_listing_by_key(&mut entries, order, |path| {
+            fs::metadata(root.join(path))
+                .and_then(|metadata| metadata.created())
+                .ok()
+        }),
+        SortBy::Modified(order) => sort_listing_by_key(&mut entries, order, |path| {
+            fs::metadata(root.join(path))
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        }),
+    }
+
+    entries.into_iter().map(|(id, _)| id).collect()
+}
 
-            os_str_to_string(os).ok()
+/// Sorts `entries` by the key `key` returns for each relative path, computing every key once.
+///
+/// Entries without a key come last in either `order`; ties are broken by path, then
+/// **`ItemId`**.
+fn sort_listing_by_key<K: Ord>(
+    entries: &mut Vec<(ItemId, &PathBuf)>,
+    order: SortOrder,
+    key: impl Fn(&Path) -> Option<K>,
+) {
+    let mut keyed: Vec<_> = entries
+        .drain(..)
+        .map(|entry| (key(entry.1), entry))
+        .collect();
+
+    keyed.sort_by(|(a_key, (a_id, a_path)), (b_key, (b_id, b_path))| {
+        let by_key = match (a_key, b_key) {
+            (Some(a), Some(b)) if order == SortOrder::Descending => b.cmp(a),
+            (Some(a), Some(b)) => a.cmp(b),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
         };
+        by_key
+            .then_with(|| a_path.cmp(b_path))
+            .then_with(|| a_id.cmp(b_id))
+    });
 
-        let extension = {
-            if path.is_dir() {
-                None
-            } else {
-                os_str_to_string(path.extension()).ok()
+    entries.extend(keyed.into_iter().map(|(_, entry)| entry));
+}
+
+/// Returns `true` when every component of `path` matches the `/`-separated `pattern`, where a
+/// `**` component matches any number of components.
+fn path_matches(pattern: &str, path: &Path) -> bool {
+    let pattern: Vec<&str> = pattern
+        .split('/')
+        .filter(|component| !component.is_empty())
+        .collect();
+    let components: Vec<Cow<'_, str>> = path
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect();
+    let components: Vec<&str> = components
+        .iter()
+        .map(|component| component.as_ref())
+        .collect();
+
+    components_match(&pattern, &components)
+}
+
+/// Returns `true` when the path components in `path` match the pattern components in
+/// `pattern`, where a `**` component matches any number of components.
+fn components_match(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| components_match(rest, &path[skip..])),
+        Some((first, rest)) => path.split_first().is_some_and(|(head, tail)| {
+            wildcard_match(first, head) && components_match(rest, tail)
+        }),
+    }
+}
+
+/// Returns `true` when `text` matches `pattern`, where `*` matches any run of characters and
+/// `?` matches a single character.
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
             }
-        };
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Returns the `index` key for `name`, lowercasing its extension when `normalize_extensions`
+/// is enabled.
+fn index_key(name: &str, normalize_extensions: bool) -> Cow<'_, str> {
+    if !normalize_extensions {
+        return Cow::Borrowed(name);
+    }
 
-        let size = FileSize::from(metadata.len());
+    match name.rsplit_once('.') {
+        Some((stem, extension))
+            if !stem.is_empty() && extension.chars().any(|c| c.is_ascii_uppercase()) =>
+        {
+            Cow::Owned(format!("{stem}.{}", extension.to_ascii_lowercase()))
+        }
+        _ => Cow::Borrowed(name),
+    }
+}
 
-        let unix_created = sys_time_to_unsigned_int(metadata.created());
-        let time_since_created = sys_time_to_time_since(metadata.created());
+/// Builds the **`FileInformation`** for `path`.
+///
+/// Without `metadata`, the item counts as a file when its name has an extension, the size is
+/// zero, and every timestamp is `None`.
+fn file_information(path: &Path, metadata: Option<&fs::Metadata>) -> FileInformation {
+    let is_dir = match metadata {
+        Some(metadata) => metadata.is_dir(),
+        None => path.extension().is_none(),
+    };
+
+    let name = {
+        let os = if is_dir {
+            path.file_name()
+        } else {
+            path.file_stem()
+        };
 
-        let unix_last_opened = sys_time_to_unsigned_int(metadata.accessed());
-        let time_since_last_opened = sys_time_to_time_since(metadata.accessed());
+        os_str_to_string(os).ok()
+    };
 
-        let unix_last_modified = sys_time_to_unsigned_int(metadata.modified());
-        let time_since_last_modified = sys_time_to_time_since(metadata.modified());
+    let extension = {
+        if is_dir {
+            None
+        } else {
+            os_str_to_string(path.extension()).ok()
+        }
+    };
 
-        Ok(FileInformation {
+    let Some(metadata) = metadata else {
+        return FileInformation {
             name,
             extension,
-            size,
-            unix_created,
-            time_since_created,
-            unix_last_opened,
-            time_since_last_opened,
-            unix_last_modified,
-            time_since_last_modified,
-        })
-    }
+            ..FileInformation::default()
+        };
+    };
 
-    /// Returns all stored `(ItemId, relative_path)` pairs.
-    fn all_paths(&self) -> Vec<(ItemId, &PathBuf)> {
-        let mut result = Vec::new();
+    let size = FileSize::from(metadata.len());
 
-        for (name, paths) in &self.items {
-            for (index, path) in paths.iter() {
-                result.push((ItemId::with_index(name.clone(), index), path));
-            }
-        }
+    let unix_created = sys_time_to_unsigned_int(metadata.created());
+    let time_since_created = sys_time_to_time_since(metadata.created());
 
-        result
-    }
+    let unix_last_opened = sys_time_to_unsigned_int(metadata.accessed());
+    let time_since_last_opened = sys_time_to_time_since(metadata.accessed());
 
-    /// Returns `true` when any stored item already uses `relative_path`.
-    fn path_exists_in_index(&self, relative_path: &Path) -> bool {
-        self.items
-            .values()
-            .any(|paths| paths.iter().any(|(_, path)| path == relative_path))
+    let unix_last_modified = sys_time_to_unsigned_int(metadata.modified());
+    let time_since_last_modified = sys_time_to_time_since(metadata.modified());
+
+    FileInformation {
+        name,
+        extension,
+        size,
+        unix_created,
+        time_since_created,
+        unix_last_opened,
+        time_since_last_opened,
+        unix_last_modified,
+        time_since_last_modified,
     }
+}
 
-    /// Inserts an exact `ItemId` -> path mapping.
-    fn insert_path_for_id(&mut self, id: &ItemId, path: PathBuf) -> Result<(), DatabaseError> {
-        let paths = self.items.entry(id.get_name().to_string()).or_default();
-        if !paths.insert_at(id.get_index(), path) {
-            return Err(DatabaseError::IdAlreadyExists(id.as_string()));
+/// Reads one directory for a recursive scan.
+///
+/// Pushes the path (relative to `root`) of every file and directory that is not internal to
+/// `collected`, and the absolute path of every such directory to `subdirectories`.
+fn scan_directory(
+    root: &Path,
+    directory: &Path,
+    collected: &mut Vec<PathBuf>,
+    subdirectories: &mut Vec<PathBuf>,
+) -> Result<(), DatabaseError> {
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        let absolute_path = entry.path();
+        let relative_path = absolute_path.strip_prefix(root)?.to_path_buf();
+
+        if is_internal_file_name(&entry.file_name()) {
+            continue;
+        }
+
+        if absolute_path.is_dir() {
+            collected.push(relative_path);
+            subdirectories.push(absolute_path);
+        } else if absolute_path.is_file() {
+            collected.push(relative_path);
         }
-        Ok(())
     }
+    Ok(())
+}
 
-    /// Inserts a generated id for a shared name and returns the generated `ItemId`.
-    fn insert_generated_path(&mut self, name: String, path: PathBuf) -> ItemId {
-        let paths = self.items.entry(name.clone()).or_default();
-        let index = paths.push(path);
-        ItemId::with_index(name, index)
+/// Walks the tree below `from` one level at a time, splitting each level's directories across
+/// up to `threads` scoped worker threads. Returns paths relative to `root`, unsorted.
+#[cfg(feature = "parallel")]
+fn scan_tree_parallel(
+    root: &Path,
+    from: &Path,
+    threads: usize,
+) -> Result<Vec<PathBuf>, DatabaseError> {
+    let mut collected = Vec::new();
+    let mut frontier = vec![from.to_path_buf()];
+
+    while !frontier.is_empty() {
+        let mut next = Vec::new();
+        if threads <= 1 || frontier.len() == 1 {
+            for directory in &frontier {
+                scan_directory(root, directory, &mut collected, &mut next)?;
+            }
+        } else {
+            let chunk_size = frontier.len().div_ceil(threads);
+            thread::scope(|scope| -> Result<(), DatabaseError> {
+                let workers: Vec<_> = frontier
+                    .chunks(chunk_size)
+                    .map(|chunk| {
+                        scope.spawn(move || {
+                            let (mut found, mut subdirectories) = (Vec::new(), Vec::new());
+                            for directory in chunk {
+                                scan_directory(root, directory, &mut found, &mut subdirectories)?;
+                            }
+                            Ok::<_, DatabaseError>((found, subdirectories))
+                        })
+                    })
+                    .collect();
+
+                for worker in workers {
+                    let (found, subdirectories) = worker.join().expect("scan worker panicked")?;
+                    collected.extend(found);
+                    next.extend(subdirectories);
+                }
+                Ok(())
+            })?;
+        }
+        frontier = next;
     }
 
-    /// Removes one exact id entry from the index and prunes empty name buckets.
-    fn remove_id_from_index(&mut self, id: &ItemId) -> Result<(), DatabaseError> {
-        let name = id.get_name().to_string();
-        let should_drop_name = {
-            let paths = self
-                .items
-                .get_mut(&name)
-                .ok_or_else(|| DatabaseError::NoMatchingID(id.as_string()))?;
+    Ok(collected)
+}
 
-            if !paths.remove(id.get_index()) {
-                return Err(DatabaseError::NoMatchingID(id.as_string()));
-            }
+/// Returns `time` as nanoseconds since the unix epoch, or `0` before it.
+fn unix_nanos(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+}
 
-            paths.is_empty()
-        };
+/// Returns `true` when `path`, or one of its ancestors, matches one of the `ignore` patterns.
+fn is_ignored(ignore: &[Selector], path: &Path) -> bool {
+    path.ancestors()
+        .filter(|ancestor| !ancestor.as_os_str().is_empty())
+        .any(|ancestor| ignore.iter().any(|selector| selector.matches(ancestor)))
+}
 
-        if should_drop_name {
-            self.items.remove(&name);
-        }
+/// Returns `true` when the item at `path` is hidden: its name starts with `.`, or on Windows it
+/// has the hidden attribute.
+fn is_hidden(path: &Path) -> bool {
+    if path
+        .file_name()
+        .is_some_and(|name| name.as_encoded_bytes().starts_with(b"."))
+    {
+        return true;
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
 
-        Ok(())
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+        if fs::symlink_metadata(path)
+            .is_ok_and(|metadata| metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0)
+        {
+            return true;
+        }
     }
+    false
+}
 
-    /// Gets one specific path for an exact **`ItemId`** key.
-    ///
-    /// This resolves `id.name` to a `StableVec` bucket and `id.index` to its stable slot.
-    ///
-    /// # Errors
-    /// Returns an error if:
-    /// - the exact key does not exist.
-    fn resolve_path_by_id(&self, id: &ItemId) -> Result<&PathBuf, DatabaseError> {
-        self.items
-            .get(id.get_name())
-            .and_then(|paths| paths.get(id.get_index()))
-            .ok_or_else(|| DatabaseError::NoMatchingID(id.as_string()))
+/// Returns `true` when `relative` (below `root`) or one of its ancestors is hidden.
+fn has_hidden_component(root: &Path, relative: &Path) -> bool {
+    relative
+        .ancestors()
+        .filter(|ancestor| !ancestor.as_os_str().is_empty())
+        .any(|ancestor| is_hidden(&root.join(ancestor)))
+}
+
+/// Returns `true` for files the database writes for itself and never tracks.
+fn is_internal_file_name(name: &OsStr) -> bool {
+    name.to_str().is_some_and(|name| {
+        name.starts_with(MANIFEST_FILE_NAME)
+            || name.starts_with(INDEX_FILE_NAME)
+            || name.starts_with(JOURNAL_FILE_NAME)
+            || name.starts_with(SCAN_STATE_FILE_NAME)
+            || name == UNDO_DIR_NAME
+            || name == TRASH_DIR_NAME
+            || name == LOCK_FILE_NAME
+            || name == LOCKS_DIR_NAME
+            || name == STAGING_DIR_NAME
+            || name == IGNORE_FILE_NAME
+            || name.ends_with(TEMP_FILE_SUFFIX)
+    })
+}
+
+/// Reads the journal entries stored in `root`, or none when there is no journal.
+fn read_journal(root: &Path) -> Result<Vec<JournalEntry>, DatabaseError> {
+    match fs::read(root.join(JOURNAL_FILE_NAME)) {
+        Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(error) => Err(error.into()),
     }
+}
 
-    /// Overwrites a file safely by using a temp file and rename.
-    ///
-    /// `write_fn` is responsible for writing bytes to the temporary file and returning
-    /// the number of bytes written.
-    ///
-    /// # Errors
-    /// Returns an error if:
-    /// - `path` points to a directory,
-    /// - temp create/write/sync/rename fails.
-    fn overwrite_path_atomic_with<F>(&self, path: &Path, write_fn: F) -> Result<u64, DatabaseError>
-    where
-        F: FnOnce(&mut File) -> Result<u64, DatabaseError>,
-    {
-        if path.is_dir() {
-            return Err(DatabaseError::NotAFile(path.to_path_buf()));
+/// Durably replaces the journal in `root` with `entries`, removing it when `entries` is empty.
+fn write_journal(root: &Path, entries: &[JournalEntry]) -> Result<(), DatabaseError> {
+    let journal = root.join(JOURNAL_FILE_NAME);
+    if entries.is_empty() {
+        if journal.is_file() {
+            remove_file(journal)?;
         }
+        return Ok(());
+    }
 
-        let buffer = path.with_extension("tmp");
+    let staging = staging_path(&journal);
+    let mut file = File::create(&staging)?;
+    file.write_all(&serde_json::to_vec(entries)?)?;
+    file.sync_all()?;
+    fs::rename(&staging, &journal)?;
+    Ok(())
+}
 
-        let result = (|| {
-            let mut file = File::create(&buffer)?;
-            let bytes_written = write_fn(&mut file)?;
-            file.sync_all()?;
-            fs::rename(&buffer, path)?;
-            Ok(bytes_written)
-        })();
+/// Finishes or rolls back every move left in the journal of `root`, then removes the journal.
+///
+/// A move whose destination holds a complete copy is finished by removing the source. Any
+/// other move is rolled back by removing the partial destination. Returns the source paths of
+/// the moves that needed work.
+fn replay_journal(root: &Path) -> Result<Vec<PathBuf>, DatabaseError> {
+    let entries = read_journal(root)?;
+    let mut recovered = Vec::new();
+
+    for entry in entries {
+        if fs::symlink_metadata(&entry.from).is_err() {
+            continue;
+        }
 
-        if result.is_err() && buffer.exists() {
-            let _ = remove_file(&buffer);
+        let leftover = if entry.copied { &entry.from } else { &entry.to };
+        if let Ok(metadata) = fs::symlink_metadata(leftover) {
+            if metadata.is_dir() {
+                remove_dir_all(leftover)?;
+            } else {
+                remove_file(leftover)?;
+            }
         }
+        recovered.push(entry.from);
+    }
 
-        result
+    let staging = staging_path(&root.join(JOURNAL_FILE_NAME));
+    if staging.is_file() {
+        remove_file(staging)?;
     }
+    write_journal(root, &[])?;
+    Ok(recovered)
+}
 
-    /// Collects relative file and folder paths in the scan area.
-    ///
-    /// # Parameters
-    /// - `scope_absolute`: absolute root directory for collection.
-    /// - `recursive`: whether to include descendants recursively.
-    ///
-    /// # Errors
-    /// Returns an error if reading folders fails or converting to a relative prefix fails.
-    fn collect_paths_in_scope(
-        &self,
-        scope_absolute: &Path,
-        recursive: bool,
-    ) -> Result<Vec<PathBuf>, DatabaseError> {
-        let mut collected = Vec::new();
+/// Returns the staging path used while atomically replacing `path`.
+fn staging_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(TEMP_FILE_SUFFIX);
+    path.with_file_name(name)
+}
 
-        if recursive {
-            let mut stack = vec![scope_absolute.to_path_buf()];
-            while let Some(directory) = stack.pop() {
-                for entry in fs::read_dir(&directory)? {
-                    let entry = entry?;
-                    let absolute_path = entry.path();
-                    let relative_path = absolute_path.strip_prefix(&self.path)?.to_path_buf();
-
-                    if absolute_path.is_dir() {
-                        collected.push(relative_path);
-                        stack.push(absolute_path);
-                    } else if absolute_path.is_file() {
-                        collected.push(relative_path);
-                    }
-                }
-            }
-        } else {
-            for entry in fs::read_dir(scope_absolute)? {
-                let entry = entry?;
-                let absolute_path = entry.path();
-                let relative_path = absolute_path.strip_prefix(&self.path)?.to_path_buf();
+/// Returns every staging file left anywhere below `root`.
+fn find_temp_files(root: &Path) -> Result<Vec<PathBuf>, DatabaseError> {
+    let mut found = Vec::new();
+    let mut stack = vec![root.to_path_buf()];
 
-                if absolute_path.is_dir() || absolute_path.is_file() {
-                    collected.push(relative_path);
-                }
+    while let Some(directory) = stack.pop() {
+        for entry in fs::read_dir(&directory)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                stack.push(entry.path());
+            } else if entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| name.ends_with(TEMP_FILE_SUFFIX))
+            {
+                found.push(entry.path());
             }
         }
-
-        Ok(collected)
     }
 
-    /// Recursively copies a directory tree from `from` to `to`.
-    fn copy_directory_recursive(&self, from: &Path, to: &Path) -> Result<(), DatabaseError> {
-        fs::create_dir_all(to)?;
+    found.sort();
+    Ok(found)
+}
 
-        for entry in fs::read_dir(from)? {
-            let entry = entry?;
-            let source_path = entry.path();
-            let destination_path = to.join(entry.file_name());
+/// Returns every manifest file anywhere below `root`.
+fn find_manifest_files(root: &Path) -> Result<Vec<PathBuf>, DatabaseError> {
+    let mut found = Vec::new();
+    let mut stack = vec![root.to_path_buf()];
 
-            if source_path.is_dir() {
-                self.copy_directory_recursive(&source_path, &destination_path)?;
-            } else {
-                fs::copy(&source_path, &destination_path)?;
+    while let Some(directory) = stack.pop() {
+        for entry in fs::read_dir(&directory)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            if file_type.is_dir()
+                && entry.file_name() != UNDO_DIR_NAME
+                && entry.file_name() != TRASH_DIR_NAME
+                && entry.file_name() != LOCKS_DIR_NAME
+                && entry.file_name() != STAGING_DIR_NAME
+            {
+                stack.push(entry.path());
+            } else if entry.file_name() == MANIFEST_FILE_NAME {
+                found.push(entry.path());
             }
         }
+    }
 
-        Ok(())
+    found.sort();
+    Ok(found)
+}
+
+/// Starting value for `fnv1a`.
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// Continues a 64-bit FNV-1a `hash` over `bytes`.
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(PRIME);
     }
+    hash
+}
 
-    /// Returns whether `path` is inside the requested scan scope.
-    fn is_path_in_scope(
-        &self,
-        path: &Path,
-        scope_relative: Option<&Path>,
-        recursive: bool,
-    ) -> bool {
-        match scope_relative {
-            None => {
-                if recursive {
-                    true
-                } else {
-                    path.parent()
-                        .is_some_and(|parent| parent.as_os_str().is_empty())
-                }
-            }
-            Some(scope_relative) => {
-                if recursive {
-                    path.starts_with(scope_relative) && path != scope_relative
-                } else {
-                    path.parent() == Some(scope_relative)
-                }
-            }
+/// Hashes the contents of the file at `path` with 64-bit FNV-1a.
+fn fnv1a_file(path: &Path) -> Result<u64, DatabaseError> {
+    let mut file = File::open(path)?;
+    let mut buffer = [0u8; 8192];
+    let mut hash = FNV_OFFSET_BASIS;
+
+    loop {
+        let read = io::Read::read(&mut file, &mut buffer)?;
+        if read == 0 {
+            break;
         }
+        hash = fnv1a(hash, &buffer[..read]);
     }
 
-    /// Deletes a directory in forced or non-forced mode.
-    fn delete_directory(&self, path: &Path, force: bool) -> Result<(), DatabaseError> {
-        if force {
-            remove_dir_all(path)?;
+    Ok(hash)
+}
+
+/// Returns whether `path` itself is a symbolic link.
+fn is_symlink(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_symlink())
+}
+
+/// Creates a symbolic link at `link` pointing to `target`.
+fn create_symlink(target: &Path, link: &Path) -> Result<(), DatabaseError> {
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(target, link)?;
+        Ok(())
+    }
+    #[cfg(windows)]
+    {
+        if target.is_dir() {
+            std::os::windows::fs::symlink_dir(target, link)?;
         } else {
-            remove_dir(path)?;
+            std::os::windows::fs::symlink_file(target, link)?;
         }
         Ok(())
     }
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = (target, link);
+        Err(io::Error::from(io::ErrorKind::Unsupported).into())
+    }
 }
 
-// -------- Functions --------
-/// Removes `steps` trailing segments from `path`.
+/// Returns the kind of the item at `path`, or a `DirectoryNotEmpty` error when it is a non-empty
+/// directory and `force` is off. Used by deletes that move the item instead of removing it.
+fn removable_kind(path: &Path, force: bool) -> Result<ItemKind, DatabaseError> {
+    if !path.is_dir() {
+        return Ok(ItemKind::File);
+    }
+    if !is_symlink(path) && !force && fs::read_dir(path)?.next().is_some() {
+        return Err(io::Error::from(io::ErrorKind::DirectoryNotEmpty).into());
+    }
+    Ok(ItemKind::Directory)
+}
+
+/// Returns the bytes available to this process on the filesystem holding `path`, or on its
+/// nearest existing ancestor when `path` does not exist yet.
 ///
-/// # Errors
-/// Returns [`DatabaseError::PathStepOverflow`] when `steps` is too large for `path`.
-fn truncate(mut path: PathBuf, steps: i32) -> Result<PathBuf, DatabaseError> {
-    let parents = (path.ancestors().count() - 1) as i32;
+/// Only 64-bit Linux and Windows are supported; other targets get `io::ErrorKind::Unsupported`,
+/// and callers that only preflight skip their check on that error.
+fn available_space(path: &Path) -> io::Result<u64> {
+    let existing = path
+        .ancestors()
+        .find(|ancestor| ancestor.exists())
+        .unwrap_or(path);
+
+    #[cfg(all(target_os = "linux", target_pointer_width = "64"))]
+    {
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = std::ffi::CString::new(existing.as_os_str().as_bytes())
+            .map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+        let mut stats = std::mem::MaybeUninit::<disk_space_sys::Statvfs>::zeroed();
+        // SAFETY: `path` is NUL-terminated and `stats` points to writable memory at least as
+        // large as `struct statvfs`.
+        if unsafe { disk_space_sys::statvfs(path.as_ptr(), stats.as_mut_ptr()) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `statvfs` succeeded and filled the struct.
+        let stats = unsafe { stats.assume_init() };
+        Ok(stats.f_bavail.saturating_mul(stats.f_frsize))
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::OsStrExt;
+
+        let path: Vec<u16> = existing.as_os_str().encode_wide().chain([0]).collect();
+        let mut available = 0u64;
+        // SAFETY: `path` is NUL-terminated and the output pointers are valid or null.
+        let succeeded = unsafe {
+            disk_space_sys::GetDiskFreeSpaceExW(
+                path.as_ptr(),
+                &mut available,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            )
+        };
+        if succeeded == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(available)
+    }
+    #[cfg(not(any(all(target_os = "linux", target_pointer_width = "64"), windows)))]
+    {
+        let _ = existing;
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+}
 
-    if parents <= steps {
-        return Err(DatabaseError::PathStepOverflow(steps, parents));
+/// Removes the symbolic link at `path` without touching its target.
+fn remove_symlink(path: &Path) -> Result<(), DatabaseError> {
+    #[cfg(windows)]
+    if path.is_dir() {
+        remove_dir(path)?;
+        return Ok(());
     }
+    remove_file(path)?;
+    Ok(())
+}
 
-    for _ in 0..steps {
-        path.pop();
+#[cfg(all(feature = "mmap", unix, target_pointer_width = "64"))]
+mod mmap_sys {
+    use std::ffi::{c_int, c_void};
+
+    pub const PROT_READ: c_int = 1;
+    pub const MAP_PRIVATE: c_int = 2;
+
+    unsafe extern "C" {
+        pub fn mmap(
+            address: *mut c_void,
+            length: usize,
+            protection: c_int,
+            flags: c_int,
+            descriptor: c_int,
+            offset: i64,
+        ) -> *mut c_void;
+        pub fn munmap(address: *mut c_void, length: usize) -> c_int;
+    }
+}
+
+#[cfg(all(target_os = "linux", target_pointer_width = "64"))]
+mod disk_space_sys {
+    use std::ffi::{c_char, c_int, c_ulong};
+
+    /// `struct statvfs` as laid out by glibc and musl on 64-bit Linux.
+    #[repr(C)]
+    pub struct Statvfs {
+        pub f_bsize: c_ulong,
+        pub f_frsize: c_ulong,
+        pub f_blocks: u64,
+        pub f_bfree: u64,
+        pub f_bavail: u64,
+        pub f_files: u64,
+        pub f_ffree: u64,
+        pub f_favail: u64,
+        pub f_fsid: c_ulong,
+        pub f_flag: c_ulong,
+        pub f_namemax: c_ulong,
+        pub f_spare: [c_int; 6],
     }
 
-    Ok(path)
+    unsafe extern "C" {
+        pub fn statvfs(path: *const c_char, buffer: *mut Statvfs) -> c_int;
+    }
 }
 
-/// Converts an optional `OsStr` into an owned `String`.
-///
-/// # Errors
-/// Returns [`DatabaseError::OsStringConversion`] if the value is `None` or invalid UTF-8.
-fn os_str_to_string(os_str: Option<&OsStr>) -> Result<String, DatabaseError> {
-    let os_str = match os_str {
-        Some(os_str) => os_str,
-        None => return Err(DatabaseError::OsStringConversion),
-    };
+#[cfg(windows)]
+mod disk_space_sys {
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        pub fn GetDiskFreeSpaceExW(
+            directory: *const u16,
+            free_bytes_available: *mut u64,
+            total_bytes: *mut u64,
+            total_free_bytes: *mut u64,
+        ) -> i32;
+    }
+}
 
-    match os_str.to_os_string().into_string() {
-        Ok(string) => Ok(string),
-        Err(_) => Err(DatabaseError::OsStringConversion),
+/// Maps `length` bytes of `file` read-only and returns the start of the mapping.
+#[cfg(feature = "mmap")]
+fn map_file(file: &File, length: usize) -> io::Result<*const u8> {
+    #[cfg(all(unix, target_pointer_width = "64"))]
+    {
+        use std::os::fd::AsRawFd;
+
+        // SAFETY: a null hint, a valid descriptor, and a non-zero length are valid arguments;
+        // failure is reported through `MAP_FAILED`.
+        let pointer = unsafe {
+            mmap_sys::mmap(
+                std::ptr::null_mut(),
+                length,
+                mmap_sys::PROT_READ,
+                mmap_sys::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if pointer as isize == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(pointer as *const u8)
+    }
+    #[cfg(not(all(unix, target_pointer_width = "64")))]
+    {
+        let _ = (file, length);
+        Err(io::Error::from(io::ErrorKind::Unsupported))
     }
 }
 
-/// Converts `SystemTime` to Unix timestamp seconds.
-///
-/// Returns `None` for platform or conversion failures.
-fn sys_time_to_unsigned_int(time: io::Result<SystemTime>) -> Option<u64> {
-    match time {
-        Ok(time) => match time.duration_since(UNIX_EPOCH) {
-            Ok(duration) => Some(duration.as_secs()),
-            Err(_) => None,
-        },
-        Err(_) => None,
+/// Releases a mapping created by `map_file`.
+#[cfg(feature = "mmap")]
+fn unmap_file(pointer: *const u8, length: usize) {
+    #[cfg(all(unix, target_pointer_width = "64"))]
+    // SAFETY: `pointer` and `length` describe a live mapping returned by `map_file`.
+    unsafe {
+        mmap_sys::munmap(pointer as *mut std::ffi::c_void, length);
     }
+    #[cfg(not(all(unix, target_pointer_width = "64")))]
+    let _ = (pointer, length);
 }
 
-/// Converts `SystemTime` to "time since now" represented as Unix-seconds duration.
-///
-/// Returns `None` for platform or conversion failures.
-fn sys_time_to_time_since(time: io::Result<SystemTime>) -> Option<u64> {
-    let duration = match time {
-        Ok(time) => match SystemTime::now().duration_since(time) {
-            Ok(duration) => duration,
-            Err(_) => return None,
-        },
-        Err(_) => return None,
-    };
+/// Sends files and directories to the operating system's trash.
+#[cfg(feature = "os-trash")]
+mod os_trash {
+    use std::{io, path::Path};
 
-    sys_time_to_unsigned_int(Ok(UNIX_EPOCH + duration))
+    #[cfg(all(unix, not(target_os = "macos")))]
+    unsafe extern "C" {
+        fn getuid() -> u32;
+    }
+
+    /// Moves `path` into the user's trash so it can be restored from the desktop.
+    ///
+    /// Items on the home filesystem go to the home trash. Items on another filesystem go to
+    /// `$topdir/.Trash-$uid` at the root of their own filesystem, since a rename cannot cross
+    /// filesystems.
+    #[cfg(all(unix, not(target_os = "macos")))]
+    pub fn move_to_trash(path: &Path) -> io::Result<()> {
+        use std::{
+            env, fs,
+            io::Write,
+            os::unix::{
+                ffi::OsStrExt,
+                fs::{DirBuilderExt, MetadataExt},
+            },
+            path::PathBuf,
+            time::{SystemTime, UNIX_EPOCH},
+        };
+
+        let absolute = path.canonicalize()?;
+        let base = absolute
+            .file_name()
+            .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))?
+            .to_string_lossy()
+            .into_owned();
+        let device = fs::symlink_metadata(&absolute)?.dev();
+
+        // Freedesktop home trash: `$XDG_DATA_HOME/Trash`, falling back to `~/.local/share/Trash`.
+        let home_trash = match env::var_os("XDG_DATA_HOME").filter(|value| !value.is_empty()) {
+            Some(data_home) => PathBuf::from(data_home).join("Trash"),
+            None => env::var_os("HOME")
+                .map(|home| PathBuf::from(home).join(".local/share/Trash"))
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "HOME is not set"))?,
+        };
+        let home_device = home_trash
+            .ancestors()
+            .find_map(|ancestor| fs::metadata(ancestor).ok())
+            .map(|metadata| metadata.dev());
+
+        // Paths in a top directory trash are stored relative to the top directory.
+        let (trash, original) = if home_device == Some(device) {
+            fs::create_dir_all(home_trash.join("files"))?;
+            fs::create_dir_all(home_trash.join("info"))?;
+            (home_trash, absolute.clone())
+        } else {
+            let mut top = absolute.as_path();
+            while let Some(parent) = top.parent() {
+                if fs::metadata(parent)?.dev() != device {
+                    break;
+                }
+                top = parent;
+            }
+            // SAFETY: `getuid` has no preconditions and cannot fail.
+            let uid = unsafe { getuid() };
+            let trash = top.join(format!(".Trash-{uid}"));
+            let mut builder = fs::DirBuilder::new();
+            builder.recursive(true).mode(0o700);
+            builder.create(trash.join("files"))?;
+            builder.create(trash.join("info"))?;
+            let original = absolute
+                .strip_prefix(top)
+                .unwrap_or(&absolute)
+                .to_path_buf();
+            (trash, original)
+        };
+        let files = trash.join("files");
+        let info = trash.join("info");
+
+        let mut encoded = String::new();
+        for &byte in original.as_os_str().as_bytes() {
+            if byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte) {
+                encoded.push(byte as char);
+            } else {
+                encoded.push_str(&format!("%{byte:02X}"));
+            }
+        }
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let contents = format!(
+            "[Trash Info]\nPath={encoded}\nDeletionDate={}\n",
+            format_timestamp(seconds)
+        );
+
+        // Creating the `.trashinfo` file with `create_new` reserves the name in `files`.
+        for attempt in 1u32.. {
+            let name = match attempt {
+                1 => base.clone(),
+                _ => format!("{base}.{attempt}"),
+            };
+            let info_path = info.join(format!("{name}.trashinfo"));
+            let mut info_file = match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&info_path)
+            {
+                Ok(file) => file,
+                Err(error) if error.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(error) => return Err(error),
+            };
+            if files.join(&name).symlink_metadata().is_ok() {
+                drop(info_file);
+                fs::remove_file(&info_path)?;
+                continue;
+            }
+
+            let moved = info_file
+                .write_all(contents.as_bytes())
+                .and_then(|_| fs::rename(&absolute, files.join(&name)));
+            if let Err(error) = moved {
+                let _ = fs::remove_file(&info_path);
+                return Err(error);
+            }
+            return Ok(());
+        }
+        unreachable!("the trash name search only ends by returning")
+    }
+
+    /// Formats `seconds` since the unix epoch as `YYYY-MM-DDThh:mm:ss` (UTC).
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn format_timestamp(seconds: u64) -> String {
+        let days = (seconds / 86_400) as i64;
+        let time = seconds % 86_400;
+
+        // Civil-from-days conversion for the proleptic Gregorian calendar.
+        let shifted = days + 719_468;
+        let era = shifted.div_euclid(146_097);
+        let day_of_era = shifted.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+        let month = if month_index < 10 {
+            month_index + 3
+        } else {
+            month_index - 9
+        };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+        format!(
+            "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}",
+            time / 3_600,
+            time % 3_600 / 60,
+            time % 60
+        )
+    }
+
+    /// Moves `path` into the Finder trash.
+    #[cfg(target_os = "macos")]
+    pub fn move_to_trash(path: &Path) -> io::Result<()> {
+        let absolute = path.canonicalize()?;
+        let escaped = absolute
+            .to_string_lossy()
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"");
+        run(std::process::Command::new("osascript").args([
+            "-e",
+            &format!("tell application \"Finder\" to delete POSIX file \"{escaped}\""),
+        ]))
+    }
+
+    /// Moves `path` into the Recycle Bin.
+    #[cfg(windows)]
+    pub fn move_to_trash(path: &Path) -> io::Result<()> {
+        let absolute = path.canonicalize()?;
+        let method = if absolute.is_dir() {
+            "DeleteDirectory"
+        } else {
+            "DeleteFile"
+        };
+        // The path goes through the environment so it never needs PowerShell quoting.
+        run(std::process::Command::new("powershell")
+            .args([
+                "-NoProfile",
+                "-NonInteractive",
+                "-Command",
+                &format!(
+                    "Add-Type -AssemblyName Microsoft.VisualBasic; \
+                     [Microsoft.VisualBasic.FileIO.FileSystem]::{method}(\
+                     $env:FDB_TRASH_PATH, 'OnlyErrorDialogs', 'SendToRecycleBin')"
+                ),
+            ])
+            .env("FDB_TRASH_PATH", &absolute))
+    }
+
+    /// Runs a platform helper and turns a non-zero exit into an error carrying its stderr.
+    #[cfg(any(target_os = "macos", windows))]
+    fn run(command: &mut std::process::Command) -> io::Result<()> {
+        let output = command.output()?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(io::Error::other(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ))
+        }
+    }
+
+    /// No trash is available on this platform.
+    #[cfg(not(any(unix, windows)))]
+    pub fn move_to_trash(path: &Path) -> io::Result<()> {
+        let _ = path;
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
 }